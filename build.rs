@@ -1,13 +1,13 @@
 extern crate gl_generator;
 
-use gl_generator::{Registry, Api, Profile, Fallbacks, GlobalGenerator};
+use gl_generator::{Registry, Api, Profile, Fallbacks, StructGenerator};
 use std::env;
 use std::fs::File;
 use std::path::Path;
 
 fn main() {
     let dest = env::var("OUT_DIR").unwrap();
-    let mut file = File::create(&Path::new(&dest).join("bindings.rs")).unwrap();
+    let mut file = File::create(Path::new(&dest).join("bindings.rs")).unwrap();
 
     Registry::new(
         Api::Gl,
@@ -17,5 +17,20 @@ fn main() {
         [
             "GL_ARB_bindless_texture"
         ]
-    ).write_bindings(GlobalGenerator, &mut file).unwrap();
+    ).write_bindings(StructGenerator, &mut file).unwrap();
+
+    if env::var_os("CARGO_FEATURE_GLES").is_some() {
+        let mut file = File::create(Path::new(&dest).join("gles_bindings.rs")).unwrap();
+
+        Registry::new(
+            Api::Gles2,
+            (3, 2),
+            Profile::Core,
+            Fallbacks::All,
+            [
+                "GL_EXT_multi_draw_indirect",
+                "GL_EXT_buffer_storage"
+            ]
+        ).write_bindings(StructGenerator, &mut file).unwrap();
+    }
 }
\ No newline at end of file
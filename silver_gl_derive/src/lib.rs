@@ -0,0 +1,249 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitInt};
+
+// Generates <Name>Std140, a #[repr(C)] copy of the struct with the padding std140 needs,
+// and implements silver_gl::AsStd140 with it. <Name>::STD140 holds the offset of every field.
+// #[std140(offset = N)] on a field or #[std140(size = N)] on the struct fail the build if the layout differs
+#[proc_macro_derive(AsStd140, attributes(std140))]
+pub fn derive_as_std140(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(&input, &Layout::Std140).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+// Same as AsStd140 for std430, generating <Name>Std430 and <Name>::STD430
+#[proc_macro_derive(AsStd430, attributes(std430))]
+pub fn derive_as_std430(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    expand(&input, &Layout::Std430).unwrap_or_else(|err| err.to_compile_error()).into()
+}
+
+enum Layout {
+    Std140,
+    Std430
+}
+
+impl Layout {
+    fn get_name(&self) -> &'static str {
+        match self {
+            Layout::Std140 => "std140",
+            Layout::Std430 => "std430"
+        }
+    }
+
+    fn get_trait(&self) -> TokenStream2 {
+        match self {
+            Layout::Std140 => quote!(::silver_gl::AsStd140),
+            Layout::Std430 => quote!(::silver_gl::AsStd430)
+        }
+    }
+
+    fn get_field_type(&self) -> TokenStream2 {
+        match self {
+            Layout::Std140 => quote!(::silver_gl::Std140Field),
+            Layout::Std430 => quote!(::silver_gl::Std430Field)
+        }
+    }
+
+    // std140 structs are aligned like a vec4
+    fn get_min_align(&self) -> usize {
+        match self {
+            Layout::Std140 => 16,
+            Layout::Std430 => 1
+        }
+    }
+}
+
+// Checks asked for with #[std140(...)] or #[std430(...)]
+#[derive(Default)]
+struct Checks {
+    offset: Option<LitInt>,
+    size: Option<LitInt>
+}
+
+fn parse_checks(attrs: &[syn::Attribute], layout: &Layout) -> syn::Result<Checks> {
+    let mut checks = Checks::default();
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident(layout.get_name())) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("offset") {
+                checks.offset = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("size") {
+                checks.size = Some(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `offset = N` or `size = N`"));
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(checks)
+}
+
+fn expand(input: &DeriveInput, layout: &Layout) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let layout_name = layout.get_name();
+    let trait_ = layout.get_trait();
+    let field_type = layout.get_field_type();
+    let min_align = layout.get_min_align();
+    let suffix = match layout {
+        Layout::Std140 => "Std140",
+        Layout::Std430 => "Std430"
+    };
+    let derive_name = format!("As{}", suffix);
+
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, format!("{} can't be derived for generic structs", derive_name)));
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) if !fields.named.is_empty() => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, format!("{} needs a struct with named fields", derive_name)))
+        },
+        _ => return Err(syn::Error::new_spanned(name, format!("{} can only be derived for structs", derive_name)))
+    };
+
+    let struct_checks = parse_checks(&input.attrs, layout)?;
+    if let Some(offset) = struct_checks.offset {
+        return Err(syn::Error::new_spanned(offset, "offset can only be checked on fields"));
+    }
+
+    let vis = &input.vis;
+    let output = format_ident!("{}{}", name, suffix);
+    let fields_struct = format_ident!("{}{}Fields", name, suffix);
+    let layout_const = Ident::new(&format!("__{}_LAYOUT", layout_name.to_uppercase()), Span::call_site());
+    let fields_const = Ident::new(&layout_name.to_uppercase(), Span::call_site());
+    let convert = match layout {
+        Layout::Std140 => quote!(as_std140),
+        Layout::Std430 => quote!(as_std430)
+    };
+
+    let count = fields.len();
+    let mut layout_fields = Vec::new();
+    let mut output_fields = Vec::new();
+    let mut output_values = Vec::new();
+    let mut field_consts = Vec::new();
+    let mut field_struct_fields = Vec::new();
+    let mut checks = Vec::new();
+    let mut members = Vec::new();
+
+    for (index, field) in fields.iter().enumerate() {
+        let ident = field.ident.as_ref().unwrap();
+        let ty = &field.ty;
+        let field_vis = &field.vis;
+        let padding = format_ident!("__padding_{}", index);
+
+        layout_fields.push(quote! {
+            (<#ty as #trait_>::ALIGN, ::core::mem::size_of::<<#ty as #trait_>::Output>())
+        });
+        output_fields.push(quote! {
+            #padding: [u8; #name::#layout_const.padding_before(#index)],
+            #field_vis #ident: <#ty as #trait_>::Output
+        });
+        output_values.push(quote! {
+            #padding: [0; #name::#layout_const.padding_before(#index)],
+            #ident: #trait_::#convert(&self.#ident)
+        });
+        members.push(quote! {
+            <#ty as #trait_>::add_std140_members(
+                layout,
+                &::silver_gl::BlockLayoutInfo::get_field_name(name, ::core::stringify!(#ident)),
+                offset + #name::#layout_const.offsets[#index]
+            );
+        });
+        field_struct_fields.push(quote!(pub #ident: #field_type<#name, #ty>));
+        field_consts.push(quote! {
+            #ident: unsafe { #field_type::new(#name::#layout_const.offsets[#index]) }
+        });
+
+        // The Rust layout of the generated struct has to be the GLSL one
+        checks.push(quote! {
+            ::core::assert!(::core::mem::offset_of!(#output, #ident) == #name::#layout_const.offsets[#index]);
+        });
+
+        let field_checks = parse_checks(&field.attrs, layout)?;
+        if let Some(size) = field_checks.size {
+            return Err(syn::Error::new_spanned(size, "size can only be checked on the struct"));
+        }
+        if let Some(offset) = field_checks.offset {
+            let message = format!("{} offset of `{}::{}` is not {}", layout_name, name, ident, offset);
+            checks.push(quote! {
+                ::core::assert!(#name::#layout_const.offsets[#index] == #offset, #message);
+            });
+        }
+    }
+
+    if let Some(size) = struct_checks.size {
+        let message = format!("{} size of `{}` is not {}", layout_name, name, size);
+        checks.push(quote! {
+            ::core::assert!(#name::#layout_const.size == #size, #message);
+        });
+    }
+
+    // Only uniform blocks are checked against reflection, and those are std140
+    let layout_impl = match layout {
+        Layout::Std140 => quote! {
+            fn add_std140_members(layout: &mut ::silver_gl::BlockLayoutInfo, name: &str, offset: usize) {
+                #(#members)*
+            }
+        },
+        Layout::Std430 => quote! {
+            type ArrayElement = #output;
+
+            fn as_std430_element(&self) -> #output {
+                self.as_std430()
+            }
+        }
+    };
+
+    Ok(quote! {
+        #[repr(C)]
+        #[derive(Clone, Copy)]
+        #vis struct #output {
+            #(#output_fields,)*
+            __padding_end: [u8; #name::#layout_const.padding_after()]
+        }
+
+        #vis struct #fields_struct {
+            #(#field_struct_fields,)*
+        }
+
+        impl #name {
+            #[doc(hidden)]
+            const #layout_const: ::silver_gl::BlockLayout<#count> = ::silver_gl::BlockLayout::new(
+                [#(#layout_fields,)*],
+                #min_align
+            );
+
+            #vis const #fields_const: #fields_struct = #fields_struct {
+                #(#field_consts,)*
+            };
+        }
+
+        unsafe impl ::silver_gl::Pod for #output {}
+
+        unsafe impl #trait_ for #name {
+            type Output = #output;
+            const ALIGN: usize = #name::#layout_const.align;
+
+            fn #convert(&self) -> #output {
+                #output {
+                    #(#output_values,)*
+                    __padding_end: [0; #name::#layout_const.padding_after()]
+                }
+            }
+
+            #layout_impl
+        }
+
+        const _: () = {
+            #(#checks)*
+            ::core::assert!(::core::mem::size_of::<#output>() == #name::#layout_const.size);
+        };
+    })
+}
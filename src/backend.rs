@@ -0,0 +1,966 @@
+use std::{ffi::{CStr, c_void}, ptr};
+use super::{GlApi, gl::{self, types::{GLenum, GLvoid, GLbitfield, GLsync}}};
+
+// Every GL entry point the crate uses goes through this trait, so the GL implementation
+// can be swapped out (e.g. for MockGl when there is no GPU to test against).
+// Methods mirror the GL functions of the same name, but only create/delete one object at a time.
+// Methods taking raw pointers are unsafe for the same reasons the GL functions are
+pub trait GlBackend {
+    // Which API the functions were loaded for
+    fn get_api(&self) -> GlApi;
+    // Whether the named (DSA) methods are implemented with bind-to-edit calls,
+    // in which case the context doesn't need to support DSA itself
+    fn emulates_dsa(&self) -> bool;
+
+    // Buffers
+    fn create_buffer(&self) -> u32;
+    fn delete_buffer(&self, buffer: u32);
+    unsafe fn named_buffer_storage(&self, buffer: u32, size: isize, data: *const GLvoid, flags: GLbitfield);
+    unsafe fn named_buffer_data(&self, buffer: u32, size: isize, data: *const GLvoid, usage: GLenum);
+    unsafe fn named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, data: *const GLvoid);
+    unsafe fn get_named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, data: *mut GLvoid);
+    fn bind_buffer(&self, target: GLenum, buffer: u32);
+    fn bind_buffer_range(&self, target: GLenum, index: u32, buffer: u32, offset: isize, size: isize);
+    // Null if the buffer couldn't be mapped
+    unsafe fn map_named_buffer_range(&self, buffer: u32, offset: isize, length: isize, access: GLbitfield) -> *mut GLvoid;
+    fn unmap_named_buffer(&self, buffer: u32) -> bool;
+
+    // Sync objects
+    fn fence_sync(&self, condition: GLenum, flags: GLbitfield) -> GLsync;
+    unsafe fn client_wait_sync(&self, sync: GLsync, flags: GLbitfield, timeout: u64) -> GLenum;
+    unsafe fn delete_sync(&self, sync: GLsync);
+
+    // Vertex arrays
+    fn create_vertex_array(&self) -> u32;
+    fn delete_vertex_array(&self, array: u32);
+    fn vertex_array_vertex_buffer(&self, array: u32, binding_index: u32, buffer: u32, offset: isize, stride: i32);
+    fn vertex_array_element_buffer(&self, array: u32, buffer: u32);
+    fn enable_vertex_array_attrib(&self, array: u32, attrib_index: u32);
+    fn vertex_array_attrib_format(
+        &self,
+        array: u32,
+        attrib_index: u32,
+        size: i32,
+        type_: GLenum,
+        normalized: bool,
+        relative_offset: u32
+    );
+    fn vertex_array_attrib_binding(&self, array: u32, attrib_index: u32, binding_index: u32);
+    fn vertex_array_binding_divisor(&self, array: u32, binding_index: u32, divisor: u32);
+    fn bind_vertex_array(&self, array: u32);
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: GLenum,
+        count: i32,
+        type_: GLenum,
+        indices: *const GLvoid,
+        instance_count: i32
+    );
+    unsafe fn multi_draw_elements_indirect(
+        &self,
+        mode: GLenum,
+        type_: GLenum,
+        indirect: *const GLvoid,
+        draw_count: i32,
+        stride: i32
+    );
+    fn patch_parameter_i(&self, pname: GLenum, value: i32);
+
+    // Shaders and programs
+    fn create_shader(&self, type_: GLenum) -> u32;
+    fn delete_shader(&self, shader: u32);
+    fn shader_source(&self, shader: u32, source: &CStr);
+    fn compile_shader(&self, shader: u32);
+    fn get_shader_iv(&self, shader: u32, pname: GLenum) -> i32;
+    fn get_shader_info_log(&self, shader: u32) -> String;
+    fn shader_binary(&self, shader: u32, format: GLenum, binary: &[u8]);
+    fn specialize_shader(&self, shader: u32, entry_point: &CStr, constant_indices: &[u32], constant_values: &[u32]);
+    fn create_program(&self) -> u32;
+    fn delete_program(&self, program: u32);
+    fn attach_shader(&self, program: u32, shader: u32);
+    fn link_program(&self, program: u32);
+    fn get_program_iv(&self, program: u32, pname: GLenum) -> i32;
+    fn get_program_info_log(&self, program: u32) -> String;
+    fn program_parameter_i(&self, program: u32, pname: GLenum, value: i32);
+    // Format and binary of a linked program, the binary is empty if the driver can't give one
+    fn get_program_binary(&self, program: u32) -> (GLenum, Vec<u8>);
+    fn program_binary(&self, program: u32, format: GLenum, binary: &[u8]);
+    fn create_program_pipeline(&self) -> u32;
+    fn delete_program_pipeline(&self, pipeline: u32);
+    fn bind_program_pipeline(&self, pipeline: u32);
+    fn use_program_stages(&self, pipeline: u32, stages: GLbitfield, program: u32);
+    fn active_shader_program(&self, pipeline: u32, program: u32);
+    fn validate_program_pipeline(&self, pipeline: u32);
+    fn get_program_pipeline_iv(&self, pipeline: u32, pname: GLenum) -> i32;
+    fn get_program_pipeline_info_log(&self, pipeline: u32) -> String;
+    fn use_program(&self, program: u32);
+    fn get_uniform_location(&self, program: u32, name: &CStr) -> i32;
+    fn uniform_1i(&self, location: i32, v0: i32);
+    fn uniform_1f(&self, location: i32, v0: f32);
+    fn uniform_3f(&self, location: i32, v0: f32, v1: f32, v2: f32);
+    fn uniform_4f(&self, location: i32, v0: f32, v1: f32, v2: f32, v3: f32);
+    fn uniform_3fv(&self, location: i32, value: &[f32]);
+    fn uniform_4fv(&self, location: i32, value: &[f32]);
+    fn uniform_matrix_4fv(&self, location: i32, transpose: bool, value: &[f32]);
+    // Any vector size or matrix shape, `components` and `columns`/`rows` go from 1 (2 for matrices) to 4.
+    // The number of elements comes from the length of `value`
+    fn uniform_iv(&self, location: i32, components: i32, value: &[i32]);
+    fn uniform_uiv(&self, location: i32, components: i32, value: &[u32]);
+    fn uniform_fv(&self, location: i32, components: i32, value: &[f32]);
+    fn uniform_dv(&self, location: i32, components: i32, value: &[f64]);
+    fn uniform_matrix_fv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f32]);
+    fn uniform_matrix_dv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f64]);
+    fn get_uniform_block_index(&self, program: u32, name: &CStr) -> u32;
+    fn uniform_block_binding(&self, program: u32, block_index: u32, binding: u32);
+    fn shader_storage_block_binding(&self, program: u32, block_index: u32, binding: u32);
+    fn get_program_resource_index(&self, program: u32, interface: GLenum, name: &CStr) -> u32;
+    fn get_program_interface_iv(&self, program: u32, interface: GLenum, pname: GLenum) -> i32;
+    fn get_program_resource_name(&self, program: u32, interface: GLenum, index: u32) -> String;
+    fn get_program_resource_iv(&self, program: u32, interface: GLenum, index: u32, props: &[GLenum]) -> Vec<i32>;
+
+    // Compute
+    fn dispatch_compute(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32);
+    fn dispatch_compute_indirect(&self, indirect: isize);
+    fn memory_barrier(&self, barriers: GLbitfield);
+    #[allow(clippy::too_many_arguments)]
+    fn bind_image_texture(
+        &self,
+        unit: u32,
+        texture: u32,
+        level: i32,
+        layered: bool,
+        layer: i32,
+        access: GLenum,
+        format: GLenum
+    );
+
+    // Textures
+    fn create_texture(&self, target: GLenum) -> u32;
+    fn delete_texture(&self, texture: u32);
+    fn texture_storage_2d(&self, texture: u32, levels: i32, internal_format: GLenum, width: i32, height: i32);
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn texture_sub_image_2d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    );
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn texture_sub_image_3d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    );
+    #[allow(clippy::too_many_arguments)]
+    unsafe fn tex_image_2d(
+        &self,
+        target: GLenum,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    );
+    fn generate_texture_mipmap(&self, texture: u32);
+    fn texture_parameter_i(&self, texture: u32, pname: GLenum, param: i32);
+    fn bind_texture(&self, target: GLenum, texture: u32);
+    fn bind_texture_unit(&self, unit: u32, texture: u32);
+    fn active_texture(&self, texture: GLenum);
+    fn get_texture_handle(&self, texture: u32) -> u64;
+    fn make_texture_handle_resident(&self, handle: u64);
+    fn make_texture_handle_non_resident(&self, handle: u64);
+
+    // Framebuffers and renderbuffers
+    fn create_framebuffer(&self) -> u32;
+    fn delete_framebuffer(&self, framebuffer: u32);
+    fn named_framebuffer_texture(&self, framebuffer: u32, attachment: GLenum, texture: u32, level: i32);
+    fn named_framebuffer_draw_buffers(&self, framebuffer: u32, buffers: &[GLenum]);
+    fn named_framebuffer_renderbuffer(
+        &self,
+        framebuffer: u32,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: u32
+    );
+    fn check_named_framebuffer_status(&self, framebuffer: u32, target: GLenum) -> GLenum;
+    fn bind_framebuffer(&self, target: GLenum, framebuffer: u32);
+    fn create_renderbuffer(&self) -> u32;
+    fn delete_renderbuffer(&self, renderbuffer: u32);
+    fn named_renderbuffer_storage(&self, renderbuffer: u32, internal_format: GLenum, width: i32, height: i32);
+
+    // Fixed function state
+    fn enable(&self, cap: GLenum);
+    fn disable(&self, cap: GLenum);
+    fn depth_func(&self, func: GLenum);
+    fn blend_func(&self, sfactor: GLenum, dfactor: GLenum);
+    fn cull_face(&self, mode: GLenum);
+
+    // Debug output
+    unsafe fn debug_message_callback(&self, callback: gl::types::GLDEBUGPROC, user_param: *const GLvoid);
+    fn object_label(&self, identifier: GLenum, name: u32, label: &str);
+    fn push_debug_group(&self, source: GLenum, id: u32, message: &str);
+    fn pop_debug_group(&self);
+
+    // Queries
+    fn get_integer_v(&self, pname: GLenum) -> i32;
+    fn get_string(&self, name: GLenum) -> String;
+    fn get_string_i(&self, name: GLenum, index: u32) -> String;
+}
+
+// Calls straight into the GL function pointers it loaded.
+// The context they were loaded for has to be current whenever this is used
+#[derive(Clone)]
+pub struct NativeGl {
+    gl: gl::Gl
+}
+
+impl NativeGl {
+    pub fn load_with<F: FnMut(&'static str) -> *const c_void>(loader: F) -> NativeGl {
+        NativeGl { gl: gl::Gl::load_with(loader) }
+    }
+
+    pub(crate) fn get_gl(&self) -> &gl::Gl {
+        &self.gl
+    }
+}
+
+// Also used by GlesGl, whose info log and string functions have the same signatures
+pub(crate) unsafe fn info_log<F, G>(id: u32, get_iv: F, get_log: G) -> String
+where
+    F: Fn(u32, GLenum, *mut i32),
+    G: Fn(u32, i32, *mut i32, *mut gl::types::GLchar)
+{
+    let mut len: gl::types::GLint = 0;
+    get_iv(id, gl::INFO_LOG_LENGTH, &mut len);
+
+    if len <= 0 {
+        return String::new();
+    }
+
+    let mut buffer: Vec<u8> = vec![0; len as usize];
+    let mut written: gl::types::GLint = 0;
+    get_log(id, len, &mut written, buffer.as_mut_ptr() as *mut gl::types::GLchar);
+    buffer.truncate(written.max(0) as usize);
+
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+// `length` includes the null terminator, like GL_NAME_LENGTH
+pub(crate) unsafe fn resource_name<F>(length: i32, get_name: F) -> String
+where
+    F: FnOnce(i32, *mut i32, *mut gl::types::GLchar)
+{
+    if length <= 0 {
+        return String::new();
+    }
+
+    let mut buffer: Vec<u8> = vec![0; length as usize];
+    let mut written: gl::types::GLint = 0;
+    get_name(length, &mut written, buffer.as_mut_ptr() as *mut gl::types::GLchar);
+    buffer.truncate(written.max(0) as usize);
+
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
+// GL strings are static and owned by the driver, null on error
+pub(crate) unsafe fn to_string(string: *const gl::types::GLubyte) -> String {
+    if string.is_null() {
+        return String::new();
+    }
+
+    CStr::from_ptr(string as *const gl::types::GLchar).to_string_lossy().into_owned()
+}
+
+impl GlBackend for NativeGl {
+    fn get_api(&self) -> GlApi {
+        GlApi::Gl
+    }
+
+    fn emulates_dsa(&self) -> bool {
+        false
+    }
+
+    fn create_buffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.CreateBuffers(1, &mut id) };
+        id
+    }
+
+    fn delete_buffer(&self, buffer: u32) {
+        unsafe { self.gl.DeleteBuffers(1, &buffer) }
+    }
+
+    unsafe fn named_buffer_storage(&self, buffer: u32, size: isize, data: *const GLvoid, flags: GLbitfield) {
+        self.gl.NamedBufferStorage(buffer, size, data, flags)
+    }
+
+    unsafe fn named_buffer_data(&self, buffer: u32, size: isize, data: *const GLvoid, usage: GLenum) {
+        self.gl.NamedBufferData(buffer, size, data, usage)
+    }
+
+    unsafe fn named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, data: *const GLvoid) {
+        self.gl.NamedBufferSubData(buffer, offset, size, data)
+    }
+
+    unsafe fn get_named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, data: *mut GLvoid) {
+        self.gl.GetNamedBufferSubData(buffer, offset, size, data)
+    }
+
+    fn bind_buffer(&self, target: GLenum, buffer: u32) {
+        unsafe { self.gl.BindBuffer(target, buffer) }
+    }
+
+    fn bind_buffer_range(&self, target: GLenum, index: u32, buffer: u32, offset: isize, size: isize) {
+        unsafe { self.gl.BindBufferRange(target, index, buffer, offset, size) }
+    }
+
+    unsafe fn map_named_buffer_range(&self, buffer: u32, offset: isize, length: isize, access: GLbitfield) -> *mut GLvoid {
+        self.gl.MapNamedBufferRange(buffer, offset, length, access)
+    }
+
+    fn unmap_named_buffer(&self, buffer: u32) -> bool {
+        unsafe { self.gl.UnmapNamedBuffer(buffer) == gl::TRUE }
+    }
+
+    fn fence_sync(&self, condition: GLenum, flags: GLbitfield) -> GLsync {
+        unsafe { self.gl.FenceSync(condition, flags) }
+    }
+
+    unsafe fn client_wait_sync(&self, sync: GLsync, flags: GLbitfield, timeout: u64) -> GLenum {
+        self.gl.ClientWaitSync(sync, flags, timeout)
+    }
+
+    unsafe fn delete_sync(&self, sync: GLsync) {
+        self.gl.DeleteSync(sync)
+    }
+
+    fn create_vertex_array(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.CreateVertexArrays(1, &mut id) };
+        id
+    }
+
+    fn delete_vertex_array(&self, array: u32) {
+        unsafe { self.gl.DeleteVertexArrays(1, &array) }
+    }
+
+    fn vertex_array_vertex_buffer(&self, array: u32, binding_index: u32, buffer: u32, offset: isize, stride: i32) {
+        unsafe { self.gl.VertexArrayVertexBuffer(array, binding_index, buffer, offset, stride) }
+    }
+
+    fn vertex_array_element_buffer(&self, array: u32, buffer: u32) {
+        unsafe { self.gl.VertexArrayElementBuffer(array, buffer) }
+    }
+
+    fn enable_vertex_array_attrib(&self, array: u32, attrib_index: u32) {
+        unsafe { self.gl.EnableVertexArrayAttrib(array, attrib_index) }
+    }
+
+    fn vertex_array_attrib_format(
+        &self,
+        array: u32,
+        attrib_index: u32,
+        size: i32,
+        type_: GLenum,
+        normalized: bool,
+        relative_offset: u32
+    ) {
+        let normalized = if normalized { gl::TRUE } else { gl::FALSE };
+        unsafe { self.gl.VertexArrayAttribFormat(array, attrib_index, size, type_, normalized, relative_offset) }
+    }
+
+    fn vertex_array_attrib_binding(&self, array: u32, attrib_index: u32, binding_index: u32) {
+        unsafe { self.gl.VertexArrayAttribBinding(array, attrib_index, binding_index) }
+    }
+
+    fn vertex_array_binding_divisor(&self, array: u32, binding_index: u32, divisor: u32) {
+        unsafe { self.gl.VertexArrayBindingDivisor(array, binding_index, divisor) }
+    }
+
+    fn bind_vertex_array(&self, array: u32) {
+        unsafe { self.gl.BindVertexArray(array) }
+    }
+
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: GLenum,
+        count: i32,
+        type_: GLenum,
+        indices: *const GLvoid,
+        instance_count: i32
+    ) {
+        self.gl.DrawElementsInstanced(mode, count, type_, indices, instance_count)
+    }
+
+    unsafe fn multi_draw_elements_indirect(
+        &self,
+        mode: GLenum,
+        type_: GLenum,
+        indirect: *const GLvoid,
+        draw_count: i32,
+        stride: i32
+    ) {
+        self.gl.MultiDrawElementsIndirect(mode, type_, indirect, draw_count, stride)
+    }
+
+    fn patch_parameter_i(&self, pname: GLenum, value: i32) {
+        unsafe { self.gl.PatchParameteri(pname, value) }
+    }
+
+    fn create_shader(&self, type_: GLenum) -> u32 {
+        unsafe { self.gl.CreateShader(type_) }
+    }
+
+    fn delete_shader(&self, shader: u32) {
+        unsafe { self.gl.DeleteShader(shader) }
+    }
+
+    fn shader_source(&self, shader: u32, source: &CStr) {
+        unsafe { self.gl.ShaderSource(shader, 1, &source.as_ptr(), ptr::null()) }
+    }
+
+    fn compile_shader(&self, shader: u32) {
+        unsafe { self.gl.CompileShader(shader) }
+    }
+
+    fn get_shader_iv(&self, shader: u32, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetShaderiv(shader, pname, &mut value) };
+        value
+    }
+
+    fn get_shader_info_log(&self, shader: u32) -> String {
+        unsafe {
+            info_log(
+                shader,
+                |id, pname, value| self.gl.GetShaderiv(id, pname, value),
+                |id, len, written, log| self.gl.GetShaderInfoLog(id, len, written, log)
+            )
+        }
+    }
+
+    fn shader_binary(&self, shader: u32, format: GLenum, binary: &[u8]) {
+        unsafe { self.gl.ShaderBinary(1, &shader, format, binary.as_ptr() as *const c_void, binary.len() as i32) }
+    }
+
+    fn specialize_shader(&self, shader: u32, entry_point: &CStr, constant_indices: &[u32], constant_values: &[u32]) {
+        unsafe {
+            self.gl.SpecializeShader(
+                shader,
+                entry_point.as_ptr(),
+                constant_indices.len() as u32,
+                constant_indices.as_ptr(),
+                constant_values.as_ptr()
+            )
+        }
+    }
+
+    fn create_program(&self) -> u32 {
+        unsafe { self.gl.CreateProgram() }
+    }
+
+    fn delete_program(&self, program: u32) {
+        unsafe { self.gl.DeleteProgram(program) }
+    }
+
+    fn attach_shader(&self, program: u32, shader: u32) {
+        unsafe { self.gl.AttachShader(program, shader) }
+    }
+
+    fn link_program(&self, program: u32) {
+        unsafe { self.gl.LinkProgram(program) }
+    }
+
+    fn get_program_iv(&self, program: u32, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetProgramiv(program, pname, &mut value) };
+        value
+    }
+
+    fn get_program_info_log(&self, program: u32) -> String {
+        unsafe {
+            info_log(
+                program,
+                |id, pname, value| self.gl.GetProgramiv(id, pname, value),
+                |id, len, written, log| self.gl.GetProgramInfoLog(id, len, written, log)
+            )
+        }
+    }
+
+    fn program_parameter_i(&self, program: u32, pname: GLenum, value: i32) {
+        unsafe { self.gl.ProgramParameteri(program, pname, value) }
+    }
+
+    fn get_program_binary(&self, program: u32) -> (GLenum, Vec<u8>) {
+        let length = self.get_program_iv(program, gl::PROGRAM_BINARY_LENGTH);
+        let mut binary = vec![0u8; length.max(0) as usize];
+        let mut written = 0;
+        let mut format = 0;
+
+        unsafe {
+            self.gl.GetProgramBinary(program, length, &mut written, &mut format, binary.as_mut_ptr() as *mut c_void)
+        };
+        binary.truncate(written.max(0) as usize);
+
+        (format, binary)
+    }
+
+    fn program_binary(&self, program: u32, format: GLenum, binary: &[u8]) {
+        unsafe { self.gl.ProgramBinary(program, format, binary.as_ptr() as *const c_void, binary.len() as i32) }
+    }
+
+    fn create_program_pipeline(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.CreateProgramPipelines(1, &mut id) };
+        id
+    }
+
+    fn delete_program_pipeline(&self, pipeline: u32) {
+        unsafe { self.gl.DeleteProgramPipelines(1, &pipeline) }
+    }
+
+    fn bind_program_pipeline(&self, pipeline: u32) {
+        unsafe { self.gl.BindProgramPipeline(pipeline) }
+    }
+
+    fn use_program_stages(&self, pipeline: u32, stages: GLbitfield, program: u32) {
+        unsafe { self.gl.UseProgramStages(pipeline, stages, program) }
+    }
+
+    fn active_shader_program(&self, pipeline: u32, program: u32) {
+        unsafe { self.gl.ActiveShaderProgram(pipeline, program) }
+    }
+
+    fn validate_program_pipeline(&self, pipeline: u32) {
+        unsafe { self.gl.ValidateProgramPipeline(pipeline) }
+    }
+
+    fn get_program_pipeline_iv(&self, pipeline: u32, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetProgramPipelineiv(pipeline, pname, &mut value) };
+        value
+    }
+
+    fn get_program_pipeline_info_log(&self, pipeline: u32) -> String {
+        unsafe {
+            info_log(
+                pipeline,
+                |id, pname, value| self.gl.GetProgramPipelineiv(id, pname, value),
+                |id, len, written, log| self.gl.GetProgramPipelineInfoLog(id, len, written, log)
+            )
+        }
+    }
+
+    fn use_program(&self, program: u32) {
+        unsafe { self.gl.UseProgram(program) }
+    }
+
+    fn get_uniform_location(&self, program: u32, name: &CStr) -> i32 {
+        unsafe { self.gl.GetUniformLocation(program, name.as_ptr()) }
+    }
+
+    fn uniform_1i(&self, location: i32, v0: i32) {
+        unsafe { self.gl.Uniform1i(location, v0) }
+    }
+
+    fn uniform_1f(&self, location: i32, v0: f32) {
+        unsafe { self.gl.Uniform1f(location, v0) }
+    }
+
+    fn uniform_3f(&self, location: i32, v0: f32, v1: f32, v2: f32) {
+        unsafe { self.gl.Uniform3f(location, v0, v1, v2) }
+    }
+
+    fn uniform_4f(&self, location: i32, v0: f32, v1: f32, v2: f32, v3: f32) {
+        unsafe { self.gl.Uniform4f(location, v0, v1, v2, v3) }
+    }
+
+    fn uniform_3fv(&self, location: i32, value: &[f32]) {
+        unsafe { self.gl.Uniform3fv(location, (value.len() / 3) as i32, value.as_ptr()) }
+    }
+
+    fn uniform_4fv(&self, location: i32, value: &[f32]) {
+        unsafe { self.gl.Uniform4fv(location, (value.len() / 4) as i32, value.as_ptr()) }
+    }
+
+    fn uniform_matrix_4fv(&self, location: i32, transpose: bool, value: &[f32]) {
+        let transpose = if transpose { gl::TRUE } else { gl::FALSE };
+        unsafe { self.gl.UniformMatrix4fv(location, (value.len() / 16) as i32, transpose, value.as_ptr()) }
+    }
+
+    fn uniform_iv(&self, location: i32, components: i32, value: &[i32]) {
+        let count = value.len() as i32 / components;
+        unsafe {
+            match components {
+                1 => self.gl.Uniform1iv(location, count, value.as_ptr()),
+                2 => self.gl.Uniform2iv(location, count, value.as_ptr()),
+                3 => self.gl.Uniform3iv(location, count, value.as_ptr()),
+                _ => self.gl.Uniform4iv(location, count, value.as_ptr())
+            }
+        }
+    }
+
+    fn uniform_uiv(&self, location: i32, components: i32, value: &[u32]) {
+        let count = value.len() as i32 / components;
+        unsafe {
+            match components {
+                1 => self.gl.Uniform1uiv(location, count, value.as_ptr()),
+                2 => self.gl.Uniform2uiv(location, count, value.as_ptr()),
+                3 => self.gl.Uniform3uiv(location, count, value.as_ptr()),
+                _ => self.gl.Uniform4uiv(location, count, value.as_ptr())
+            }
+        }
+    }
+
+    fn uniform_fv(&self, location: i32, components: i32, value: &[f32]) {
+        let count = value.len() as i32 / components;
+        unsafe {
+            match components {
+                1 => self.gl.Uniform1fv(location, count, value.as_ptr()),
+                2 => self.gl.Uniform2fv(location, count, value.as_ptr()),
+                3 => self.gl.Uniform3fv(location, count, value.as_ptr()),
+                _ => self.gl.Uniform4fv(location, count, value.as_ptr())
+            }
+        }
+    }
+
+    fn uniform_dv(&self, location: i32, components: i32, value: &[f64]) {
+        let count = value.len() as i32 / components;
+        unsafe {
+            match components {
+                1 => self.gl.Uniform1dv(location, count, value.as_ptr()),
+                2 => self.gl.Uniform2dv(location, count, value.as_ptr()),
+                3 => self.gl.Uniform3dv(location, count, value.as_ptr()),
+                _ => self.gl.Uniform4dv(location, count, value.as_ptr())
+            }
+        }
+    }
+
+    fn uniform_matrix_fv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f32]) {
+        let count = value.len() as i32 / (columns * rows);
+        let transpose = if transpose { gl::TRUE } else { gl::FALSE };
+        unsafe {
+            match (columns, rows) {
+                (2, 2) => self.gl.UniformMatrix2fv(location, count, transpose, value.as_ptr()),
+                (2, 3) => self.gl.UniformMatrix2x3fv(location, count, transpose, value.as_ptr()),
+                (2, 4) => self.gl.UniformMatrix2x4fv(location, count, transpose, value.as_ptr()),
+                (3, 2) => self.gl.UniformMatrix3x2fv(location, count, transpose, value.as_ptr()),
+                (3, 3) => self.gl.UniformMatrix3fv(location, count, transpose, value.as_ptr()),
+                (3, 4) => self.gl.UniformMatrix3x4fv(location, count, transpose, value.as_ptr()),
+                (4, 2) => self.gl.UniformMatrix4x2fv(location, count, transpose, value.as_ptr()),
+                (4, 3) => self.gl.UniformMatrix4x3fv(location, count, transpose, value.as_ptr()),
+                _ => self.gl.UniformMatrix4fv(location, count, transpose, value.as_ptr())
+            }
+        }
+    }
+
+    fn uniform_matrix_dv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f64]) {
+        let count = value.len() as i32 / (columns * rows);
+        let transpose = if transpose { gl::TRUE } else { gl::FALSE };
+        unsafe {
+            match (columns, rows) {
+                (2, 2) => self.gl.UniformMatrix2dv(location, count, transpose, value.as_ptr()),
+                (2, 3) => self.gl.UniformMatrix2x3dv(location, count, transpose, value.as_ptr()),
+                (2, 4) => self.gl.UniformMatrix2x4dv(location, count, transpose, value.as_ptr()),
+                (3, 2) => self.gl.UniformMatrix3x2dv(location, count, transpose, value.as_ptr()),
+                (3, 3) => self.gl.UniformMatrix3dv(location, count, transpose, value.as_ptr()),
+                (3, 4) => self.gl.UniformMatrix3x4dv(location, count, transpose, value.as_ptr()),
+                (4, 2) => self.gl.UniformMatrix4x2dv(location, count, transpose, value.as_ptr()),
+                (4, 3) => self.gl.UniformMatrix4x3dv(location, count, transpose, value.as_ptr()),
+                _ => self.gl.UniformMatrix4dv(location, count, transpose, value.as_ptr())
+            }
+        }
+    }
+
+    fn get_uniform_block_index(&self, program: u32, name: &CStr) -> u32 {
+        unsafe { self.gl.GetUniformBlockIndex(program, name.as_ptr()) }
+    }
+
+    fn uniform_block_binding(&self, program: u32, block_index: u32, binding: u32) {
+        unsafe { self.gl.UniformBlockBinding(program, block_index, binding) }
+    }
+
+    fn shader_storage_block_binding(&self, program: u32, block_index: u32, binding: u32) {
+        unsafe { self.gl.ShaderStorageBlockBinding(program, block_index, binding) }
+    }
+
+    fn get_program_resource_index(&self, program: u32, interface: GLenum, name: &CStr) -> u32 {
+        unsafe { self.gl.GetProgramResourceIndex(program, interface, name.as_ptr()) }
+    }
+
+    fn get_program_interface_iv(&self, program: u32, interface: GLenum, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetProgramInterfaceiv(program, interface, pname, &mut value) };
+        value
+    }
+
+    fn get_program_resource_name(&self, program: u32, interface: GLenum, index: u32) -> String {
+        let length = self.get_program_resource_iv(program, interface, index, &[gl::NAME_LENGTH])[0];
+
+        unsafe {
+            resource_name(length, |buffer_size, written, name| {
+                self.gl.GetProgramResourceName(program, interface, index, buffer_size, written, name)
+            })
+        }
+    }
+
+    fn get_program_resource_iv(&self, program: u32, interface: GLenum, index: u32, props: &[GLenum]) -> Vec<i32> {
+        let mut values = vec![0; props.len()];
+
+        unsafe {
+            self.gl.GetProgramResourceiv(
+                program,
+                interface,
+                index,
+                props.len() as i32,
+                props.as_ptr(),
+                values.len() as i32,
+                ptr::null_mut(),
+                values.as_mut_ptr()
+            )
+        };
+
+        values
+    }
+
+    fn dispatch_compute(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32) {
+        unsafe { self.gl.DispatchCompute(num_groups_x, num_groups_y, num_groups_z) }
+    }
+
+    fn dispatch_compute_indirect(&self, indirect: isize) {
+        unsafe { self.gl.DispatchComputeIndirect(indirect) }
+    }
+
+    fn memory_barrier(&self, barriers: GLbitfield) {
+        unsafe { self.gl.MemoryBarrier(barriers) }
+    }
+
+    fn bind_image_texture(
+        &self,
+        unit: u32,
+        texture: u32,
+        level: i32,
+        layered: bool,
+        layer: i32,
+        access: GLenum,
+        format: GLenum
+    ) {
+        let layered = if layered { gl::TRUE } else { gl::FALSE };
+        unsafe { self.gl.BindImageTexture(unit, texture, level, layered, layer, access, format) }
+    }
+
+    fn create_texture(&self, target: GLenum) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.CreateTextures(target, 1, &mut id) };
+        id
+    }
+
+    fn delete_texture(&self, texture: u32) {
+        unsafe { self.gl.DeleteTextures(1, &texture) }
+    }
+
+    fn texture_storage_2d(&self, texture: u32, levels: i32, internal_format: GLenum, width: i32, height: i32) {
+        unsafe { self.gl.TextureStorage2D(texture, levels, internal_format, width, height) }
+    }
+
+    unsafe fn texture_sub_image_2d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.gl.TextureSubImage2D(texture, level, x_offset, y_offset, width, height, format, type_, pixels)
+    }
+
+    unsafe fn texture_sub_image_3d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.gl.TextureSubImage3D(
+            texture, level, x_offset, y_offset, z_offset, width, height, depth, format, type_, pixels
+        )
+    }
+
+    unsafe fn tex_image_2d(
+        &self,
+        target: GLenum,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.gl.TexImage2D(target, level, internal_format, width, height, border, format, type_, pixels)
+    }
+
+    fn generate_texture_mipmap(&self, texture: u32) {
+        unsafe { self.gl.GenerateTextureMipmap(texture) }
+    }
+
+    fn texture_parameter_i(&self, texture: u32, pname: GLenum, param: i32) {
+        unsafe { self.gl.TextureParameteri(texture, pname, param) }
+    }
+
+    fn bind_texture(&self, target: GLenum, texture: u32) {
+        unsafe { self.gl.BindTexture(target, texture) }
+    }
+
+    fn bind_texture_unit(&self, unit: u32, texture: u32) {
+        unsafe { self.gl.BindTextureUnit(unit, texture) }
+    }
+
+    fn active_texture(&self, texture: GLenum) {
+        unsafe { self.gl.ActiveTexture(texture) }
+    }
+
+    fn get_texture_handle(&self, texture: u32) -> u64 {
+        unsafe { self.gl.GetTextureHandleARB(texture) }
+    }
+
+    fn make_texture_handle_resident(&self, handle: u64) {
+        unsafe { self.gl.MakeTextureHandleResidentARB(handle) }
+    }
+
+    fn make_texture_handle_non_resident(&self, handle: u64) {
+        unsafe { self.gl.MakeTextureHandleNonResidentARB(handle) }
+    }
+
+    fn create_framebuffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.CreateFramebuffers(1, &mut id) };
+        id
+    }
+
+    fn delete_framebuffer(&self, framebuffer: u32) {
+        unsafe { self.gl.DeleteFramebuffers(1, &framebuffer) }
+    }
+
+    fn named_framebuffer_texture(&self, framebuffer: u32, attachment: GLenum, texture: u32, level: i32) {
+        unsafe { self.gl.NamedFramebufferTexture(framebuffer, attachment, texture, level) }
+    }
+
+    fn named_framebuffer_draw_buffers(&self, framebuffer: u32, buffers: &[GLenum]) {
+        unsafe { self.gl.NamedFramebufferDrawBuffers(framebuffer, buffers.len() as i32, buffers.as_ptr()) }
+    }
+
+    fn named_framebuffer_renderbuffer(
+        &self,
+        framebuffer: u32,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: u32
+    ) {
+        unsafe { self.gl.NamedFramebufferRenderbuffer(framebuffer, attachment, renderbuffer_target, renderbuffer) }
+    }
+
+    fn check_named_framebuffer_status(&self, framebuffer: u32, target: GLenum) -> GLenum {
+        unsafe { self.gl.CheckNamedFramebufferStatus(framebuffer, target) }
+    }
+
+    fn bind_framebuffer(&self, target: GLenum, framebuffer: u32) {
+        unsafe { self.gl.BindFramebuffer(target, framebuffer) }
+    }
+
+    fn create_renderbuffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.CreateRenderbuffers(1, &mut id) };
+        id
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: u32) {
+        unsafe { self.gl.DeleteRenderbuffers(1, &renderbuffer) }
+    }
+
+    fn named_renderbuffer_storage(&self, renderbuffer: u32, internal_format: GLenum, width: i32, height: i32) {
+        unsafe { self.gl.NamedRenderbufferStorage(renderbuffer, internal_format, width, height) }
+    }
+
+    fn enable(&self, cap: GLenum) {
+        unsafe { self.gl.Enable(cap) }
+    }
+
+    fn disable(&self, cap: GLenum) {
+        unsafe { self.gl.Disable(cap) }
+    }
+
+    fn depth_func(&self, func: GLenum) {
+        unsafe { self.gl.DepthFunc(func) }
+    }
+
+    fn blend_func(&self, sfactor: GLenum, dfactor: GLenum) {
+        unsafe { self.gl.BlendFunc(sfactor, dfactor) }
+    }
+
+    fn cull_face(&self, mode: GLenum) {
+        unsafe { self.gl.CullFace(mode) }
+    }
+
+    unsafe fn debug_message_callback(&self, callback: gl::types::GLDEBUGPROC, user_param: *const GLvoid) {
+        self.gl.DebugMessageCallback(callback, user_param)
+    }
+
+    fn object_label(&self, identifier: GLenum, name: u32, label: &str) {
+        unsafe { self.gl.ObjectLabel(identifier, name, label.len() as i32, label.as_ptr() as *const gl::types::GLchar) }
+    }
+
+    fn push_debug_group(&self, source: GLenum, id: u32, message: &str) {
+        unsafe {
+            self.gl.PushDebugGroup(source, id, message.len() as i32, message.as_ptr() as *const gl::types::GLchar)
+        }
+    }
+
+    fn pop_debug_group(&self) {
+        unsafe { self.gl.PopDebugGroup() }
+    }
+
+    fn get_integer_v(&self, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetIntegerv(pname, &mut value) };
+        value
+    }
+
+    fn get_string(&self, name: GLenum) -> String {
+        unsafe { to_string(self.gl.GetString(name)) }
+    }
+
+    fn get_string_i(&self, name: GLenum, index: u32) -> String {
+        unsafe { to_string(self.gl.GetStringi(name, index)) }
+    }
+}
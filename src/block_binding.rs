@@ -0,0 +1,135 @@
+use std::{cell::RefCell, collections::HashMap, fmt::Display};
+use super::{GlError, gl::{self, types::GLenum}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BufferBlockType {
+    Uniform,
+    ShaderStorage
+}
+
+impl BufferBlockType {
+    // Indexed target the block's buffers are bound to
+    pub fn get_target(&self) -> GLenum {
+        match self {
+            BufferBlockType::Uniform => gl::UNIFORM_BUFFER,
+            BufferBlockType::ShaderStorage => gl::SHADER_STORAGE_BUFFER
+        }
+    }
+
+    // GL enum of the binding limit, for errors
+    pub(crate) fn get_limit_name(&self) -> &'static str {
+        match self {
+            BufferBlockType::Uniform => "GL_MAX_UNIFORM_BUFFER_BINDINGS",
+            BufferBlockType::ShaderStorage => "GL_MAX_SHADER_STORAGE_BUFFER_BINDINGS"
+        }
+    }
+}
+
+impl Display for BufferBlockType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            BufferBlockType::Uniform => "Uniform",
+            BufferBlockType::ShaderStorage => "Shader storage"
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+// Binding points of buffer blocks, owned by the context. Every block with the same name gets the same
+// binding in every program, so buffers bound there reach all of them, and blocks with different names
+// don't overwrite each other. Uniform and storage blocks have separate binding points.
+// Bindings are kept for the life of the context, so programs bound to a block still match a buffer created for it later
+#[derive(Default)]
+pub(crate) struct BlockBindings {
+    bindings: RefCell<HashMap<(BufferBlockType, String), u32>>
+}
+
+impl BlockBindings {
+    // Gives the block the lowest free binding the first time it is asked for, `limit` is the number of bindings
+    pub(crate) fn get(&self, type_: BufferBlockType, name: &str, limit: i32) -> Result<u32, GlError> {
+        let mut bindings = self.bindings.borrow_mut();
+
+        if let Some(binding) = bindings.get(&(type_, name.to_owned())) {
+            return Ok(*binding);
+        }
+
+        let used: Vec<u32> = bindings.iter()
+            .filter(|((bound_type, _), _)| *bound_type == type_)
+            .map(|(_, binding)| *binding)
+            .collect();
+        let binding = (0..).find(|binding| !used.contains(binding)).unwrap_or(0);
+
+        if binding as i32 >= limit {
+            return Err(GlError::LimitExceeded(type_.get_limit_name(), binding as i32 + 1, limit));
+        }
+
+        bindings.insert((type_, name.to_owned()), binding);
+
+        Ok(binding)
+    }
+
+    // For bindings the crate can't choose, like layout(binding = N) on GLES storage blocks
+    pub(crate) fn set(&self, type_: BufferBlockType, name: &str, binding: u32) -> Result<(), GlError> {
+        let mut bindings = self.bindings.borrow_mut();
+        let conflict = |reason: String| Err(GlError::BlockBindingConflict(name.to_owned(), binding, reason));
+
+        if let Some(previous) = bindings.get(&(type_, name.to_owned())).filter(|previous| **previous != binding) {
+            return conflict(format!("it is already at binding {}", previous));
+        }
+
+        let other = bindings.iter()
+            .find(|((bound_type, bound_name), bound)| *bound_type == type_ && bound_name != name && **bound == binding);
+        if let Some(((_, other), _)) = other {
+            return conflict(format!("'{}' is bound there", other));
+        }
+
+        bindings.insert((type_, name.to_owned()), binding);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::GlError;
+    use super::{BlockBindings, BufferBlockType};
+
+    #[test]
+    fn get_gives_lowest_free_binding() {
+        let bindings = BlockBindings::default();
+
+        assert_eq!(bindings.get(BufferBlockType::Uniform, "Camera", 4).unwrap(), 0);
+        assert_eq!(bindings.get(BufferBlockType::Uniform, "Lights", 4).unwrap(), 1);
+        // Same name, same binding
+        assert_eq!(bindings.get(BufferBlockType::Uniform, "Camera", 4).unwrap(), 0);
+        // Storage blocks have their own binding points
+        assert_eq!(bindings.get(BufferBlockType::ShaderStorage, "Camera", 4).unwrap(), 0);
+
+        bindings.set(BufferBlockType::Uniform, "Fixed", 2).unwrap();
+        assert_eq!(bindings.get(BufferBlockType::Uniform, "Bones", 4).unwrap(), 3);
+        assert!(matches!(
+            bindings.get(BufferBlockType::Uniform, "Material", 4),
+            Err(GlError::LimitExceeded("GL_MAX_UNIFORM_BUFFER_BINDINGS", 5, 4))
+        ));
+    }
+
+    #[test]
+    fn set_rejects_conflicts() {
+        let bindings = BlockBindings::default();
+
+        bindings.set(BufferBlockType::ShaderStorage, "Particles", 3).unwrap();
+        bindings.set(BufferBlockType::ShaderStorage, "Particles", 3).unwrap();
+        assert_eq!(bindings.get(BufferBlockType::ShaderStorage, "Particles", 8).unwrap(), 3);
+
+        assert!(matches!(
+            bindings.set(BufferBlockType::ShaderStorage, "Particles", 4),
+            Err(GlError::BlockBindingConflict(name, 4, reason)) if name == "Particles" && reason == "it is already at binding 3"
+        ));
+        assert!(matches!(
+            bindings.set(BufferBlockType::ShaderStorage, "Grid", 3),
+            Err(GlError::BlockBindingConflict(name, 3, reason)) if name == "Grid" && reason == "'Particles' is bound there"
+        ));
+        bindings.set(BufferBlockType::Uniform, "Grid", 3).unwrap();
+    }
+}
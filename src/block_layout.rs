@@ -0,0 +1,414 @@
+use std::marker::PhantomData;
+use cgmath::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use super::Pod;
+
+// Rust types that can be written to std140 blocks, which uniform blocks use by default.
+// Structs get it with #[derive(AsStd140)], which generates a padded copy of the struct named <Name>Std140.
+// In blocks, [T; N] is a GLSL array and vectors and matrices are cgmath types.
+// Unsafe because Output has to have exactly the block's layout
+pub unsafe trait AsStd140 {
+    type Output: Pod;
+    // Base alignment from the std140 rules, the Rust alignment of Output can be lower
+    const ALIGN: usize;
+
+    fn as_std140(&self) -> Self::Output;
+
+    // Adds the members GL reflects for a value of this type named `name` at `offset` bytes.
+    // Arrays add each element and structs each field, anything else is one member
+    fn add_std140_members(layout: &mut BlockLayoutInfo, name: &str, offset: usize) {
+        layout.add_member(name, offset);
+    }
+}
+
+// Same as AsStd140 for std430, which storage blocks can use. Derived with #[derive(AsStd430)]
+pub unsafe trait AsStd430 {
+    type Output: Pod;
+    // std430 arrays are tightly packed, only 3 component vectors are padded to the size of 4
+    type ArrayElement: Pod;
+    const ALIGN: usize;
+
+    fn as_std430(&self) -> Self::Output;
+    fn as_std430_element(&self) -> Self::ArrayElement;
+}
+
+// Offsets a Rust type gives the members of a uniform block, named the way GL reflects them
+// (e.g. "lights[1].color" or "weights[0]"), for ShaderProgram::check_block_layout()
+#[derive(Debug, Clone, Default)]
+pub struct BlockLayoutInfo {
+    pub size: usize,
+    pub members: Vec<BlockMemberInfo>
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockMemberInfo {
+    pub name: String,
+    pub offset: usize
+}
+
+impl BlockLayoutInfo {
+    pub fn new(size: usize) -> BlockLayoutInfo {
+        BlockLayoutInfo { size, members: Vec::new() }
+    }
+
+    // The layout of a struct deriving AsStd140
+    pub fn std140<T: AsStd140>() -> BlockLayoutInfo {
+        let mut layout = BlockLayoutInfo::new(std::mem::size_of::<T::Output>());
+        T::add_std140_members(&mut layout, "", 0);
+
+        layout
+    }
+
+    pub fn add_member(&mut self, name: &str, offset: usize) {
+        self.members.push(BlockMemberInfo { name: name.to_owned(), offset });
+    }
+
+    pub fn get_member(&self, name: &str) -> Option<&BlockMemberInfo> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    // Name of the field of a struct member, used by the derives
+    #[doc(hidden)]
+    pub fn get_field_name(name: &str, field: &str) -> String {
+        match name {
+            "" => field.to_owned(),
+            _ => format!("{}.{}", name, field)
+        }
+    }
+}
+
+// A value followed by P bytes of zeroes
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct Padded<T: Copy, const P: usize> {
+    pub value: T,
+    padding: [u8; P]
+}
+
+impl<T: Copy, const P: usize> Padded<T, P> {
+    pub fn new(value: T) -> Padded<T, P> {
+        Padded { value, padding: [0; P] }
+    }
+}
+
+unsafe impl<T: Pod, const P: usize> Pod for Padded<T, P> {}
+
+// Element of a std140 array, where the stride is rounded up to 16 bytes
+#[repr(C, align(16))]
+#[derive(Debug, Clone, Copy)]
+pub struct Std140Element<T: Copy>(pub T);
+
+unsafe impl<T: Pod> Pod for Std140Element<T> {}
+
+// Offset of a field of T in its std140 layout, from <Name>::STD140 on structs deriving AsStd140
+pub struct Std140Field<T, F> {
+    offset: usize,
+    marker: PhantomData<fn() -> (T, F)>
+}
+
+// Offset of a field of T in its std430 layout, from <Name>::STD430 on structs deriving AsStd430
+pub struct Std430Field<T, F> {
+    offset: usize,
+    marker: PhantomData<fn() -> (T, F)>
+}
+
+macro_rules! impl_field {
+    ($field:ident) => {
+        impl<T, F> $field<T, F> {
+            // Only for the derives, the offset has to be the one the layout gives the field
+            #[doc(hidden)]
+            pub const unsafe fn new(offset: usize) -> $field<T, F> {
+                $field { offset, marker: PhantomData }
+            }
+
+            pub fn get_offset(&self) -> usize {
+                self.offset
+            }
+        }
+
+        impl<T, F> Clone for $field<T, F> {
+            fn clone(&self) -> Self {
+                *self
+            }
+        }
+
+        impl<T, F> Copy for $field<T, F> {}
+    };
+}
+
+impl_field!(Std140Field);
+impl_field!(Std430Field);
+
+// Field offsets of a struct, computed at compile time by the derives.
+// `fields` are (base alignment, size) pairs in declaration order
+#[doc(hidden)]
+pub struct BlockLayout<const N: usize> {
+    pub offsets: [usize; N],
+    pub sizes: [usize; N],
+    pub size: usize,
+    pub align: usize
+}
+
+impl<const N: usize> BlockLayout<N> {
+    // `min_align` is 16 for std140, where structs are aligned like a vec4
+    pub const fn new(fields: [(usize, usize); N], min_align: usize) -> BlockLayout<N> {
+        let mut offsets = [0; N];
+        let mut sizes = [0; N];
+        let mut align = min_align;
+        let mut end = 0;
+
+        let mut i = 0;
+        while i < N {
+            let (field_align, field_size) = fields[i];
+            offsets[i] = round_up(end, field_align);
+            sizes[i] = field_size;
+            end = offsets[i] + field_size;
+
+            if field_align > align {
+                align = field_align;
+            }
+            i += 1;
+        }
+
+        BlockLayout { offsets, sizes, size: round_up(end, align), align }
+    }
+
+    pub const fn padding_before(&self, index: usize) -> usize {
+        match index {
+            0 => self.offsets[0],
+            _ => self.offsets[index] - self.offsets[index - 1] - self.sizes[index - 1]
+        }
+    }
+
+    pub const fn padding_after(&self) -> usize {
+        match N {
+            0 => self.size,
+            _ => self.size - self.offsets[N - 1] - self.sizes[N - 1]
+        }
+    }
+}
+
+const fn round_up(value: usize, align: usize) -> usize {
+    value.div_ceil(align) * align
+}
+
+macro_rules! impl_scalar {
+    ($type_:ty, $output:ty, $convert:expr) => {
+        unsafe impl AsStd140 for $type_ {
+            type Output = $output;
+            const ALIGN: usize = std::mem::size_of::<$output>();
+
+            fn as_std140(&self) -> $output {
+                $convert(*self)
+            }
+        }
+
+        unsafe impl AsStd430 for $type_ {
+            type Output = $output;
+            type ArrayElement = $output;
+            const ALIGN: usize = std::mem::size_of::<$output>();
+
+            fn as_std430(&self) -> $output {
+                $convert(*self)
+            }
+
+            fn as_std430_element(&self) -> $output {
+                $convert(*self)
+            }
+        }
+    };
+}
+
+impl_scalar!(f32, f32, |value| value);
+impl_scalar!(f64, f64, |value| value);
+impl_scalar!(i32, i32, |value| value);
+impl_scalar!(u32, u32, |value| value);
+// GLSL bools take 4 bytes
+impl_scalar!(bool, u32, |value| value as u32);
+
+macro_rules! impl_vector {
+    ($scalar:ty, $size:literal) => {
+        unsafe impl AsStd140 for Vector2<$scalar> {
+            type Output = [$scalar; 2];
+            const ALIGN: usize = 2 * $size;
+
+            fn as_std140(&self) -> [$scalar; 2] {
+                (*self).into()
+            }
+        }
+
+        // vec3 is aligned like a vec4, but a scalar can follow it in the last 4 bytes
+        unsafe impl AsStd140 for Vector3<$scalar> {
+            type Output = [$scalar; 3];
+            const ALIGN: usize = 4 * $size;
+
+            fn as_std140(&self) -> [$scalar; 3] {
+                (*self).into()
+            }
+        }
+
+        unsafe impl AsStd140 for Vector4<$scalar> {
+            type Output = [$scalar; 4];
+            const ALIGN: usize = 4 * $size;
+
+            fn as_std140(&self) -> [$scalar; 4] {
+                (*self).into()
+            }
+        }
+
+        unsafe impl AsStd430 for Vector2<$scalar> {
+            type Output = [$scalar; 2];
+            type ArrayElement = [$scalar; 2];
+            const ALIGN: usize = 2 * $size;
+
+            fn as_std430(&self) -> [$scalar; 2] {
+                (*self).into()
+            }
+
+            fn as_std430_element(&self) -> [$scalar; 2] {
+                (*self).into()
+            }
+        }
+
+        unsafe impl AsStd430 for Vector3<$scalar> {
+            type Output = [$scalar; 3];
+            type ArrayElement = Padded<[$scalar; 3], $size>;
+            const ALIGN: usize = 4 * $size;
+
+            fn as_std430(&self) -> [$scalar; 3] {
+                (*self).into()
+            }
+
+            fn as_std430_element(&self) -> Padded<[$scalar; 3], $size> {
+                Padded::new((*self).into())
+            }
+        }
+
+        unsafe impl AsStd430 for Vector4<$scalar> {
+            type Output = [$scalar; 4];
+            type ArrayElement = [$scalar; 4];
+            const ALIGN: usize = 4 * $size;
+
+            fn as_std430(&self) -> [$scalar; 4] {
+                (*self).into()
+            }
+
+            fn as_std430_element(&self) -> [$scalar; 4] {
+                (*self).into()
+            }
+        }
+    };
+}
+
+impl_vector!(f32, 4);
+impl_vector!(f64, 8);
+impl_vector!(i32, 4);
+impl_vector!(u32, 4);
+
+// Matrices are arrays of column vectors. In std140 every column is 16 byte aligned,
+// in std430 only vec3 columns are padded
+macro_rules! impl_matrix {
+    ($scalar:ty, $size:literal) => {
+        unsafe impl AsStd140 for Matrix2<$scalar> {
+            type Output = [Padded<[$scalar; 2], { round_up(2 * $size, 16) - 2 * $size }>; 2];
+            const ALIGN: usize = round_up(2 * $size, 16);
+
+            fn as_std140(&self) -> Self::Output {
+                [Padded::new(self.x.into()), Padded::new(self.y.into())]
+            }
+        }
+
+        unsafe impl AsStd140 for Matrix3<$scalar> {
+            type Output = [Padded<[$scalar; 3], $size>; 3];
+            const ALIGN: usize = round_up(4 * $size, 16);
+
+            fn as_std140(&self) -> Self::Output {
+                [Padded::new(self.x.into()), Padded::new(self.y.into()), Padded::new(self.z.into())]
+            }
+        }
+
+        unsafe impl AsStd140 for Matrix4<$scalar> {
+            type Output = [[$scalar; 4]; 4];
+            const ALIGN: usize = round_up(4 * $size, 16);
+
+            fn as_std140(&self) -> Self::Output {
+                (*self).into()
+            }
+        }
+
+        unsafe impl AsStd430 for Matrix2<$scalar> {
+            type Output = [[$scalar; 2]; 2];
+            type ArrayElement = [[$scalar; 2]; 2];
+            const ALIGN: usize = 2 * $size;
+
+            fn as_std430(&self) -> Self::Output {
+                (*self).into()
+            }
+
+            fn as_std430_element(&self) -> Self::Output {
+                (*self).into()
+            }
+        }
+
+        unsafe impl AsStd430 for Matrix3<$scalar> {
+            type Output = [Padded<[$scalar; 3], $size>; 3];
+            type ArrayElement = [Padded<[$scalar; 3], $size>; 3];
+            const ALIGN: usize = 4 * $size;
+
+            fn as_std430(&self) -> Self::Output {
+                [Padded::new(self.x.into()), Padded::new(self.y.into()), Padded::new(self.z.into())]
+            }
+
+            fn as_std430_element(&self) -> Self::Output {
+                self.as_std430()
+            }
+        }
+
+        unsafe impl AsStd430 for Matrix4<$scalar> {
+            type Output = [[$scalar; 4]; 4];
+            type ArrayElement = [[$scalar; 4]; 4];
+            const ALIGN: usize = 4 * $size;
+
+            fn as_std430(&self) -> Self::Output {
+                (*self).into()
+            }
+
+            fn as_std430_element(&self) -> Self::Output {
+                (*self).into()
+            }
+        }
+    };
+}
+
+impl_matrix!(f32, 4);
+impl_matrix!(f64, 8);
+
+unsafe impl<T: AsStd140, const N: usize> AsStd140 for [T; N] {
+    type Output = [Std140Element<T::Output>; N];
+    const ALIGN: usize = round_up(T::ALIGN, 16);
+
+    fn as_std140(&self) -> Self::Output {
+        std::array::from_fn(|i| Std140Element(self[i].as_std140()))
+    }
+
+    fn add_std140_members(layout: &mut BlockLayoutInfo, name: &str, offset: usize) {
+        let stride = std::mem::size_of::<Std140Element<T::Output>>();
+
+        for i in 0..N {
+            T::add_std140_members(layout, &format!("{}[{}]", name, i), offset + i * stride);
+        }
+    }
+}
+
+unsafe impl<T: AsStd430, const N: usize> AsStd430 for [T; N] {
+    type Output = [T::ArrayElement; N];
+    type ArrayElement = [T::ArrayElement; N];
+    const ALIGN: usize = T::ALIGN;
+
+    fn as_std430(&self) -> Self::Output {
+        std::array::from_fn(|i| self[i].as_std430_element())
+    }
+
+    fn as_std430_element(&self) -> Self::Output {
+        self.as_std430()
+    }
+}
@@ -1,28 +1,24 @@
 use std::ops::Range;
-use super::gl;
+use super::{GlContext, gl};
 
 pub struct Buffer<T> {
     id: u32,
     data: Vec<T>,
-    binding_index: u32
+    binding_index: u32,
+    label: Option<String>,
+    gl: GlContext
 }
 
 impl<T> Buffer<T> {
-    pub fn new() -> Buffer<T> {
-        let mut buffer = Buffer {
-            data: Vec::<T>::new(), id: 0, binding_index: 0
-        };
-
-        unsafe {
-            gl::CreateBuffers(1, &mut buffer.id);
+    pub fn new(gl: &GlContext) -> Buffer<T> {
+        Buffer {
+            data: Vec::<T>::new(), id: gl.create_buffer(), binding_index: 0, label: None, gl: gl.clone()
         }
-
-        buffer
     }
 
     pub fn send_data(&self) {
         unsafe {
-            gl::NamedBufferStorage(
+            self.gl.named_buffer_storage(
                 self.id,
                 (self.data.len() * std::mem::size_of::<T>()) as isize,
                 self.data.as_ptr() as *const gl::types::GLvoid,
@@ -33,7 +29,7 @@ impl<T> Buffer<T> {
 
     pub fn send_data_mut(&self) {
         unsafe {
-            gl::NamedBufferData(
+            self.gl.named_buffer_data(
                 self.id,
                 (self.data.len() * std::mem::size_of::<T>()) as isize,
                 self.data.as_ptr() as *const gl::types::GLvoid,
@@ -45,7 +41,7 @@ impl<T> Buffer<T> {
     pub unsafe fn send_data_index(&self, index: usize) {
         let size = std::mem::size_of::<T>();
 
-        gl::NamedBufferSubData(
+        self.gl.named_buffer_sub_data(
             self.id,
             (index * size) as isize,
             size as isize,
@@ -56,7 +52,7 @@ impl<T> Buffer<T> {
     pub unsafe fn send_data_range(&self, range: Range<usize>) {
         let size = std::mem::size_of::<T>();
 
-        gl::NamedBufferSubData(
+        self.gl.named_buffer_sub_data(
             self.id,
             (range.start * size) as isize,
             ((range.end - range.start) * size) as isize,
@@ -130,7 +126,7 @@ impl<T> Buffer<T> {
     // Unsafe functions designed to be called from VAO
     // These need to exist because the buffer handles its own binding_index
     pub unsafe fn add_vertex_to_vertex_array(&mut self, vao_id: u32, binding_index: u32) {
-        gl::VertexArrayVertexBuffer(
+        self.gl.vertex_array_vertex_buffer(
             vao_id,
             binding_index,
             self.id,
@@ -142,26 +138,76 @@ impl<T> Buffer<T> {
     }
 
     pub unsafe fn bind_to_vao_attrib(&mut self, vao_id: u32, attrib_index: u32) {
-        gl::VertexArrayAttribBinding(vao_id, attrib_index, self.binding_index)
+        self.gl.vertex_array_attrib_binding(vao_id, attrib_index, self.binding_index)
     }
 
     pub unsafe fn set_divisor(&mut self, vao_id: u32, divisor: u32) {
-        gl::VertexArrayBindingDivisor(vao_id, self.binding_index, divisor);
+        self.gl.vertex_array_binding_divisor(vao_id, self.binding_index, divisor);
+    }
+
+    pub fn bind(&self, target: gl::types::GLenum) {
+        self.gl.bind_buffer(target, self.id);
     }
 
     pub fn len(&self) -> usize {
         self.data.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::BUFFER, self.id, label);
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
 }
 
 impl<T> Drop for Buffer<T> {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteBuffers(1, &self.id);
-        }
+        self.gl.delete_buffer(self.id);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GlCall, gl, mock_backend::testing::{get_names, mock_context}};
+    use super::Buffer;
+
+    #[test]
+    fn buffer_sends_data() {
+        let (mock, gl) = mock_context(|_| ());
+
+        let mut buffer = Buffer::<[f32; 3]>::new(&gl);
+        let id = buffer.get_id() as i64;
+        assert_eq!(mock.take_calls(), vec![GlCall { name: "CreateBuffers", args: vec![id], text: None }]);
+
+        // Immutable storage, then resized with glNamedBufferData, then updated in place
+        buffer.set_data(vec![[0.0; 3]; 4]);
+        buffer.push([1.0; 3]);
+        buffer.set_data_index([2.0; 3], 2);
+        buffer.set_data_range(vec![[3.0; 3]; 2], 3);
+
+        let calls = mock.take_calls();
+        assert_eq!(get_names(&calls), ["NamedBufferStorage", "NamedBufferData", "NamedBufferSubData", "NamedBufferSubData"]);
+        assert_eq!(calls[0].args, [id, 48, 0]);
+        assert_eq!(calls[1].args, [id, 60, gl::DYNAMIC_DRAW as i64]);
+        assert_eq!(calls[2].args, [id, 24, 12]);
+        assert_eq!(calls[3].args, [id, 36, 24]);
+        assert_eq!(buffer.len(), 5);
+
+        buffer.remove(0);
+        assert_eq!(mock.find("NamedBufferData").unwrap().args, [id, 48, gl::DYNAMIC_DRAW as i64]);
+
+        drop(buffer);
+        assert_eq!(mock.find("DeleteBuffers").unwrap().args, [id]);
+    }
+}
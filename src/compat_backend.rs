@@ -0,0 +1,906 @@
+use std::{cell::{Cell, RefCell}, collections::HashMap, ffi::{CStr, CString, c_void}};
+use super::{GlBackend, GlApi, NativeGl, gl::{self, types::{GLenum, GLvoid, GLbitfield, GLsync}}};
+use super::backend::resource_name;
+
+// Texture targets that can be bound, used when a target has to be guessed
+const TEXTURE_TARGETS: [GLenum; 4] = [gl::TEXTURE_2D, gl::TEXTURE_CUBE_MAP, gl::TEXTURE_2D_ARRAY, gl::TEXTURE_3D];
+
+#[derive(Clone, Copy, Default)]
+struct VertexBinding {
+    buffer: u32,
+    offset: isize,
+    stride: i32,
+    divisor: u32
+}
+
+#[derive(Clone, Copy)]
+struct VertexAttrib {
+    size: i32,
+    type_: GLenum,
+    normalized: bool,
+    relative_offset: u32,
+    binding: u32
+}
+
+// Separate attrib formats and buffer bindings only exist from GL 4.3,
+// so they are kept here and combined into glVertexAttribPointer calls
+#[derive(Default)]
+struct VertexArrayState {
+    bindings: HashMap<u32, VertexBinding>,
+    attribs: HashMap<u32, VertexAttrib>
+}
+
+// Backend for GL 3.3 core contexts without DSA, picked by GlContext::load_with() when DSA is missing.
+// The named methods bind the object, make the classic call and then rebind whatever was bound before,
+// so they have no side effects on bindings, same as the DSA functions they stand in for.
+// Everything else goes straight to NativeGl
+pub struct CompatGl {
+    native: NativeGl,
+    // Edits need the target a texture was created with to bind it
+    texture_targets: RefCell<HashMap<u32, GLenum>>,
+    // bind_texture_unit() switches units to bind, then switches back to this one
+    active_texture: Cell<GLenum>,
+    vertex_arrays: RefCell<HashMap<u32, VertexArrayState>>
+}
+
+impl CompatGl {
+    pub fn load_with<F: FnMut(&'static str) -> *const c_void>(loader: F) -> CompatGl {
+        CompatGl::from_native(NativeGl::load_with(loader))
+    }
+
+    pub fn from_native(native: NativeGl) -> CompatGl {
+        CompatGl {
+            native,
+            texture_targets: RefCell::new(HashMap::new()),
+            active_texture: Cell::new(gl::TEXTURE0),
+            vertex_arrays: RefCell::new(HashMap::new())
+        }
+    }
+
+    fn gl(&self) -> &gl::Gl {
+        self.native.get_gl()
+    }
+
+    fn edit<R, B: Fn(u32), F: FnOnce() -> R>(&self, binding: GLenum, bind: B, object: u32, f: F) -> R {
+        let previous = self.get_integer_v(binding) as u32;
+
+        if previous != object { bind(object); }
+        let result = f();
+        if previous != object { bind(previous); }
+
+        result
+    }
+
+    // Buffers are edited through GL_COPY_WRITE_BUFFER, which nothing else in the crate binds
+    fn edit_buffer<R, F: FnOnce() -> R>(&self, buffer: u32, f: F) -> R {
+        self.edit(
+            gl::COPY_WRITE_BUFFER_BINDING,
+            |id| unsafe { self.gl().BindBuffer(gl::COPY_WRITE_BUFFER, id) },
+            buffer,
+            f
+        )
+    }
+
+    fn edit_vertex_array<R, F: FnOnce() -> R>(&self, array: u32, f: F) -> R {
+        self.edit(gl::VERTEX_ARRAY_BINDING, |id| unsafe { self.gl().BindVertexArray(id) }, array, f)
+    }
+
+    // Binds on the active unit
+    fn edit_texture<R, F: FnOnce(GLenum) -> R>(&self, texture: u32, f: F) -> R {
+        let target = self.get_texture_target(texture);
+        let binding = match target {
+            gl::TEXTURE_CUBE_MAP => gl::TEXTURE_BINDING_CUBE_MAP,
+            gl::TEXTURE_2D_ARRAY => gl::TEXTURE_BINDING_2D_ARRAY,
+            gl::TEXTURE_3D => gl::TEXTURE_BINDING_3D,
+            _ => gl::TEXTURE_BINDING_2D
+        };
+
+        self.edit(binding, |id| unsafe { self.gl().BindTexture(target, id) }, texture, || f(target))
+    }
+
+    // Only the draw binding changes, which is all the framebuffer functions need
+    fn edit_framebuffer<R, F: FnOnce() -> R>(&self, framebuffer: u32, f: F) -> R {
+        self.edit(
+            gl::DRAW_FRAMEBUFFER_BINDING,
+            |id| unsafe { self.gl().BindFramebuffer(gl::DRAW_FRAMEBUFFER, id) },
+            framebuffer,
+            f
+        )
+    }
+
+    fn edit_renderbuffer<R, F: FnOnce() -> R>(&self, renderbuffer: u32, f: F) -> R {
+        self.edit(
+            gl::RENDERBUFFER_BINDING,
+            |id| unsafe { self.gl().BindRenderbuffer(gl::RENDERBUFFER, id) },
+            renderbuffer,
+            f
+        )
+    }
+
+    // Textures from outside the crate are assumed to be 2D
+    fn get_texture_target(&self, texture: u32) -> GLenum {
+        self.texture_targets.borrow().get(&texture).copied().unwrap_or(gl::TEXTURE_2D)
+    }
+
+    // Re-specifies every attrib that reads from `binding`, or only `attrib` if given.
+    // Attribs are skipped until both their format and buffer are known
+    fn update_attribs(&self, array: u32, binding: Option<u32>, attrib: Option<u32>) {
+        let vertex_arrays = self.vertex_arrays.borrow();
+        let Some(state) = vertex_arrays.get(&array) else { return };
+
+        let attribs = state.attribs.iter().filter(|(index, format)| {
+            attrib.is_none_or(|attrib| attrib == **index) && binding.is_none_or(|binding| binding == format.binding)
+        });
+
+        self.edit_vertex_array(array, || {
+            let previous_buffer = self.get_integer_v(gl::ARRAY_BUFFER_BINDING) as u32;
+
+            for (index, format) in attribs {
+                let Some(source) = state.bindings.get(&format.binding) else { continue };
+                if source.buffer == 0 { continue; }
+
+                let normalized = if format.normalized { gl::TRUE } else { gl::FALSE };
+                let offset = source.offset + format.relative_offset as isize;
+
+                unsafe {
+                    self.gl().BindBuffer(gl::ARRAY_BUFFER, source.buffer);
+                    self.gl().VertexAttribPointer(
+                        *index,
+                        format.size,
+                        format.type_,
+                        normalized,
+                        source.stride,
+                        offset as *const GLvoid
+                    );
+                    self.gl().VertexAttribDivisor(*index, source.divisor);
+                }
+            }
+
+            unsafe { self.gl().BindBuffer(gl::ARRAY_BUFFER, previous_buffer) };
+        });
+    }
+
+    fn update_binding<F: FnOnce(&mut VertexBinding)>(&self, array: u32, binding_index: u32, f: F) {
+        if let Some(state) = self.vertex_arrays.borrow_mut().get_mut(&array) {
+            f(state.bindings.entry(binding_index).or_default());
+        }
+
+        self.update_attribs(array, Some(binding_index), None);
+    }
+}
+
+// Unsized format and type that TexImage2D accepts with `internal_format` when no data is given
+fn get_transfer_format(internal_format: GLenum) -> (GLenum, GLenum) {
+    match internal_format {
+        gl::R8 | gl::R16F | gl::R32F => (gl::RED, gl::FLOAT),
+        gl::RG8 | gl::RG16F | gl::RG32F => (gl::RG, gl::FLOAT),
+        gl::RGB8 | gl::SRGB8 | gl::RGB16F | gl::RGB32F => (gl::RGB, gl::FLOAT),
+        gl::DEPTH_COMPONENT16 | gl::DEPTH_COMPONENT24 | gl::DEPTH_COMPONENT32F => (gl::DEPTH_COMPONENT, gl::FLOAT),
+        gl::DEPTH24_STENCIL8 => (gl::DEPTH_STENCIL, gl::UNSIGNED_INT_24_8),
+        _ => (gl::RGBA, gl::FLOAT)
+    }
+}
+
+impl GlBackend for CompatGl {
+    fn get_api(&self) -> GlApi {
+        GlApi::Gl
+    }
+
+    fn emulates_dsa(&self) -> bool {
+        true
+    }
+
+    // Names from glGen* only become objects once bound, labels and edits need the object to exist
+    fn create_buffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl().GenBuffers(1, &mut id) };
+        self.edit_buffer(id, || ());
+        id
+    }
+
+    fn delete_buffer(&self, buffer: u32) {
+        self.native.delete_buffer(buffer)
+    }
+
+    // Uses immutable storage when GL_ARB_buffer_storage is there, otherwise a regular allocation
+    unsafe fn named_buffer_storage(&self, buffer: u32, size: isize, data: *const GLvoid, flags: GLbitfield) {
+        self.edit_buffer(buffer, || {
+            if self.gl().BufferStorage.is_loaded() {
+                self.gl().BufferStorage(gl::COPY_WRITE_BUFFER, size, data, flags)
+            } else {
+                let usage = if flags & gl::DYNAMIC_STORAGE_BIT != 0 { gl::DYNAMIC_DRAW } else { gl::STATIC_DRAW };
+                self.gl().BufferData(gl::COPY_WRITE_BUFFER, size, data, usage)
+            }
+        })
+    }
+
+    unsafe fn named_buffer_data(&self, buffer: u32, size: isize, data: *const GLvoid, usage: GLenum) {
+        self.edit_buffer(buffer, || self.gl().BufferData(gl::COPY_WRITE_BUFFER, size, data, usage))
+    }
+
+    unsafe fn named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, data: *const GLvoid) {
+        self.edit_buffer(buffer, || self.gl().BufferSubData(gl::COPY_WRITE_BUFFER, offset, size, data))
+    }
+
+    unsafe fn get_named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, data: *mut GLvoid) {
+        self.edit_buffer(buffer, || self.gl().GetBufferSubData(gl::COPY_WRITE_BUFFER, offset, size, data))
+    }
+
+    fn bind_buffer(&self, target: GLenum, buffer: u32) {
+        self.native.bind_buffer(target, buffer)
+    }
+
+    fn bind_buffer_range(&self, target: GLenum, index: u32, buffer: u32, offset: isize, size: isize) {
+        self.native.bind_buffer_range(target, index, buffer, offset, size)
+    }
+
+    // Persistent mappings stay valid after the buffer is unbound
+    unsafe fn map_named_buffer_range(&self, buffer: u32, offset: isize, length: isize, access: GLbitfield) -> *mut GLvoid {
+        self.edit_buffer(buffer, || self.gl().MapBufferRange(gl::COPY_WRITE_BUFFER, offset, length, access))
+    }
+
+    fn unmap_named_buffer(&self, buffer: u32) -> bool {
+        self.edit_buffer(buffer, || unsafe { self.gl().UnmapBuffer(gl::COPY_WRITE_BUFFER) == gl::TRUE })
+    }
+
+    fn fence_sync(&self, condition: GLenum, flags: GLbitfield) -> GLsync {
+        self.native.fence_sync(condition, flags)
+    }
+
+    unsafe fn client_wait_sync(&self, sync: GLsync, flags: GLbitfield, timeout: u64) -> GLenum {
+        self.native.client_wait_sync(sync, flags, timeout)
+    }
+
+    unsafe fn delete_sync(&self, sync: GLsync) {
+        self.native.delete_sync(sync)
+    }
+
+    fn create_vertex_array(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl().GenVertexArrays(1, &mut id) };
+        self.edit_vertex_array(id, || ());
+        self.vertex_arrays.borrow_mut().insert(id, VertexArrayState::default());
+        id
+    }
+
+    fn delete_vertex_array(&self, array: u32) {
+        self.vertex_arrays.borrow_mut().remove(&array);
+        self.native.delete_vertex_array(array)
+    }
+
+    fn vertex_array_vertex_buffer(&self, array: u32, binding_index: u32, buffer: u32, offset: isize, stride: i32) {
+        self.update_binding(array, binding_index, |binding| {
+            binding.buffer = buffer;
+            binding.offset = offset;
+            binding.stride = stride;
+        });
+    }
+
+    fn vertex_array_element_buffer(&self, array: u32, buffer: u32) {
+        self.edit_vertex_array(array, || unsafe { self.gl().BindBuffer(gl::ELEMENT_ARRAY_BUFFER, buffer) })
+    }
+
+    fn enable_vertex_array_attrib(&self, array: u32, attrib_index: u32) {
+        self.edit_vertex_array(array, || unsafe { self.gl().EnableVertexAttribArray(attrib_index) })
+    }
+
+    fn vertex_array_attrib_format(
+        &self,
+        array: u32,
+        attrib_index: u32,
+        size: i32,
+        type_: GLenum,
+        normalized: bool,
+        relative_offset: u32
+    ) {
+        if let Some(state) = self.vertex_arrays.borrow_mut().get_mut(&array) {
+            // Attribs read from the binding with the same index until told otherwise
+            let binding = state.attribs.get(&attrib_index).map_or(attrib_index, |attrib| attrib.binding);
+            state.attribs.insert(attrib_index, VertexAttrib { size, type_, normalized, relative_offset, binding });
+        }
+
+        self.update_attribs(array, None, Some(attrib_index));
+    }
+
+    // Only takes effect once the attrib has a format
+    fn vertex_array_attrib_binding(&self, array: u32, attrib_index: u32, binding_index: u32) {
+        if let Some(attrib) = self.vertex_arrays.borrow_mut().get_mut(&array)
+            .and_then(|state| state.attribs.get_mut(&attrib_index))
+        {
+            attrib.binding = binding_index;
+        }
+
+        self.update_attribs(array, None, Some(attrib_index));
+    }
+
+    fn vertex_array_binding_divisor(&self, array: u32, binding_index: u32, divisor: u32) {
+        self.update_binding(array, binding_index, |binding| binding.divisor = divisor);
+    }
+
+    fn bind_vertex_array(&self, array: u32) {
+        self.native.bind_vertex_array(array)
+    }
+
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: GLenum,
+        count: i32,
+        type_: GLenum,
+        indices: *const GLvoid,
+        instance_count: i32
+    ) {
+        self.native.draw_elements_instanced(mode, count, type_, indices, instance_count)
+    }
+
+    // Needs GL 4.3 or GL_ARB_multi_draw_indirect, which GlContext checks for
+    unsafe fn multi_draw_elements_indirect(
+        &self,
+        mode: GLenum,
+        type_: GLenum,
+        indirect: *const GLvoid,
+        draw_count: i32,
+        stride: i32
+    ) {
+        self.native.multi_draw_elements_indirect(mode, type_, indirect, draw_count, stride)
+    }
+
+    // Needs GL 4.0 or GL_ARB_tessellation_shader, which VertexArray checks for
+    fn patch_parameter_i(&self, pname: GLenum, value: i32) {
+        self.native.patch_parameter_i(pname, value)
+    }
+
+    fn create_shader(&self, type_: GLenum) -> u32 {
+        self.native.create_shader(type_)
+    }
+
+    fn delete_shader(&self, shader: u32) {
+        self.native.delete_shader(shader)
+    }
+
+    fn shader_source(&self, shader: u32, source: &CStr) {
+        self.native.shader_source(shader, source)
+    }
+
+    fn compile_shader(&self, shader: u32) {
+        self.native.compile_shader(shader)
+    }
+
+    fn get_shader_iv(&self, shader: u32, pname: GLenum) -> i32 {
+        self.native.get_shader_iv(shader, pname)
+    }
+
+    fn get_shader_info_log(&self, shader: u32) -> String {
+        self.native.get_shader_info_log(shader)
+    }
+
+    fn shader_binary(&self, shader: u32, format: GLenum, binary: &[u8]) {
+        self.native.shader_binary(shader, format, binary)
+    }
+
+    fn specialize_shader(&self, shader: u32, entry_point: &CStr, constant_indices: &[u32], constant_values: &[u32]) {
+        self.native.specialize_shader(shader, entry_point, constant_indices, constant_values)
+    }
+
+    fn create_program(&self) -> u32 {
+        self.native.create_program()
+    }
+
+    fn delete_program(&self, program: u32) {
+        self.native.delete_program(program)
+    }
+
+    fn attach_shader(&self, program: u32, shader: u32) {
+        self.native.attach_shader(program, shader)
+    }
+
+    fn link_program(&self, program: u32) {
+        self.native.link_program(program)
+    }
+
+    fn get_program_iv(&self, program: u32, pname: GLenum) -> i32 {
+        self.native.get_program_iv(program, pname)
+    }
+
+    fn get_program_info_log(&self, program: u32) -> String {
+        self.native.get_program_info_log(program)
+    }
+
+    fn program_parameter_i(&self, program: u32, pname: GLenum, value: i32) {
+        self.native.program_parameter_i(program, pname, value)
+    }
+
+    fn get_program_binary(&self, program: u32) -> (GLenum, Vec<u8>) {
+        self.native.get_program_binary(program)
+    }
+
+    fn program_binary(&self, program: u32, format: GLenum, binary: &[u8]) {
+        self.native.program_binary(program, format, binary)
+    }
+
+    // Binding creates the object, so it can be labelled right away
+    fn create_program_pipeline(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl().GenProgramPipelines(1, &mut id) };
+        self.edit(gl::PROGRAM_PIPELINE_BINDING, |id| unsafe { self.gl().BindProgramPipeline(id) }, id, || ());
+        id
+    }
+
+    fn delete_program_pipeline(&self, pipeline: u32) {
+        self.native.delete_program_pipeline(pipeline)
+    }
+
+    fn bind_program_pipeline(&self, pipeline: u32) {
+        self.native.bind_program_pipeline(pipeline)
+    }
+
+    fn use_program_stages(&self, pipeline: u32, stages: GLbitfield, program: u32) {
+        self.native.use_program_stages(pipeline, stages, program)
+    }
+
+    fn active_shader_program(&self, pipeline: u32, program: u32) {
+        self.native.active_shader_program(pipeline, program)
+    }
+
+    fn validate_program_pipeline(&self, pipeline: u32) {
+        self.native.validate_program_pipeline(pipeline)
+    }
+
+    fn get_program_pipeline_iv(&self, pipeline: u32, pname: GLenum) -> i32 {
+        self.native.get_program_pipeline_iv(pipeline, pname)
+    }
+
+    fn get_program_pipeline_info_log(&self, pipeline: u32) -> String {
+        self.native.get_program_pipeline_info_log(pipeline)
+    }
+
+    fn use_program(&self, program: u32) {
+        self.native.use_program(program)
+    }
+
+    fn get_uniform_location(&self, program: u32, name: &CStr) -> i32 {
+        self.native.get_uniform_location(program, name)
+    }
+
+    fn uniform_1i(&self, location: i32, v0: i32) {
+        self.native.uniform_1i(location, v0)
+    }
+
+    fn uniform_1f(&self, location: i32, v0: f32) {
+        self.native.uniform_1f(location, v0)
+    }
+
+    fn uniform_3f(&self, location: i32, v0: f32, v1: f32, v2: f32) {
+        self.native.uniform_3f(location, v0, v1, v2)
+    }
+
+    fn uniform_4f(&self, location: i32, v0: f32, v1: f32, v2: f32, v3: f32) {
+        self.native.uniform_4f(location, v0, v1, v2, v3)
+    }
+
+    fn uniform_3fv(&self, location: i32, value: &[f32]) {
+        self.native.uniform_3fv(location, value)
+    }
+
+    fn uniform_4fv(&self, location: i32, value: &[f32]) {
+        self.native.uniform_4fv(location, value)
+    }
+
+    fn uniform_matrix_4fv(&self, location: i32, transpose: bool, value: &[f32]) {
+        self.native.uniform_matrix_4fv(location, transpose, value)
+    }
+
+    fn uniform_iv(&self, location: i32, components: i32, value: &[i32]) {
+        self.native.uniform_iv(location, components, value)
+    }
+
+    fn uniform_uiv(&self, location: i32, components: i32, value: &[u32]) {
+        self.native.uniform_uiv(location, components, value)
+    }
+
+    fn uniform_fv(&self, location: i32, components: i32, value: &[f32]) {
+        self.native.uniform_fv(location, components, value)
+    }
+
+    fn uniform_dv(&self, location: i32, components: i32, value: &[f64]) {
+        self.native.uniform_dv(location, components, value)
+    }
+
+    fn uniform_matrix_fv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f32]) {
+        self.native.uniform_matrix_fv(location, columns, rows, transpose, value)
+    }
+
+    fn uniform_matrix_dv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f64]) {
+        self.native.uniform_matrix_dv(location, columns, rows, transpose, value)
+    }
+
+    fn get_uniform_block_index(&self, program: u32, name: &CStr) -> u32 {
+        self.native.get_uniform_block_index(program, name)
+    }
+
+    fn uniform_block_binding(&self, program: u32, block_index: u32, binding: u32) {
+        self.native.uniform_block_binding(program, block_index, binding)
+    }
+
+    fn shader_storage_block_binding(&self, program: u32, block_index: u32, binding: u32) {
+        self.native.shader_storage_block_binding(program, block_index, binding)
+    }
+
+    fn get_program_resource_index(&self, program: u32, interface: GLenum, name: &CStr) -> u32 {
+        if self.gl().GetProgramResourceIndex.is_loaded() {
+            return self.native.get_program_resource_index(program, interface, name);
+        }
+
+        match interface {
+            gl::UNIFORM_BLOCK => self.get_uniform_block_index(program, name),
+            _ => gl::INVALID_INDEX
+        }
+    }
+
+    // Program interface queries are GL 4.3, older contexts get the uniform and block queries they stand for
+    fn get_program_interface_iv(&self, program: u32, interface: GLenum, pname: GLenum) -> i32 {
+        if self.gl().GetProgramInterfaceiv.is_loaded() {
+            return self.native.get_program_interface_iv(program, interface, pname);
+        }
+
+        match (interface, pname) {
+            (gl::UNIFORM, gl::ACTIVE_RESOURCES) => self.get_program_iv(program, gl::ACTIVE_UNIFORMS),
+            (gl::UNIFORM, gl::MAX_NAME_LENGTH) => self.get_program_iv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH),
+            (gl::UNIFORM_BLOCK, gl::ACTIVE_RESOURCES) => self.get_program_iv(program, gl::ACTIVE_UNIFORM_BLOCKS),
+            (gl::UNIFORM_BLOCK, gl::MAX_NAME_LENGTH) =>
+                self.get_program_iv(program, gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH),
+            _ => 0
+        }
+    }
+
+    fn get_program_resource_name(&self, program: u32, interface: GLenum, index: u32) -> String {
+        if self.gl().GetProgramResourceName.is_loaded() {
+            return self.native.get_program_resource_name(program, interface, index);
+        }
+
+        let length = self.get_program_resource_iv(program, interface, index, &[gl::NAME_LENGTH])[0];
+
+        unsafe {
+            resource_name(length, |buffer_size, written, name| match interface {
+                gl::UNIFORM => self.gl().GetActiveUniformName(program, index, buffer_size, written, name),
+                gl::UNIFORM_BLOCK => self.gl().GetActiveUniformBlockName(program, index, buffer_size, written, name),
+                _ => ()
+            })
+        }
+    }
+
+    // Properties without an older equivalent are -1
+    fn get_program_resource_iv(&self, program: u32, interface: GLenum, index: u32, props: &[GLenum]) -> Vec<i32> {
+        if self.gl().GetProgramResourceiv.is_loaded() {
+            return self.native.get_program_resource_iv(program, interface, index, props);
+        }
+
+        props.iter()
+            .map(|prop| unsafe {
+                let mut value = -1;
+
+                match (interface, *prop) {
+                    (gl::UNIFORM, gl::LOCATION) => {
+                        let name = self.get_program_resource_name(program, interface, index);
+                        value = CString::new(name).map_or(-1, |name| self.get_uniform_location(program, &name));
+                    },
+                    (gl::UNIFORM, prop) => {
+                        let pname = match prop {
+                            gl::TYPE => gl::UNIFORM_TYPE,
+                            gl::ARRAY_SIZE => gl::UNIFORM_SIZE,
+                            gl::NAME_LENGTH => gl::UNIFORM_NAME_LENGTH,
+                            gl::BLOCK_INDEX => gl::UNIFORM_BLOCK_INDEX,
+                            gl::OFFSET => gl::UNIFORM_OFFSET,
+                            _ => return value
+                        };
+                        self.gl().GetActiveUniformsiv(program, 1, &index, pname, &mut value);
+                    },
+                    (gl::UNIFORM_BLOCK, prop) => {
+                        let pname = match prop {
+                            gl::BUFFER_BINDING => gl::UNIFORM_BLOCK_BINDING,
+                            gl::BUFFER_DATA_SIZE => gl::UNIFORM_BLOCK_DATA_SIZE,
+                            gl::NAME_LENGTH => gl::UNIFORM_BLOCK_NAME_LENGTH,
+                            gl::NUM_ACTIVE_VARIABLES => gl::UNIFORM_BLOCK_ACTIVE_UNIFORMS,
+                            _ => return value
+                        };
+                        self.gl().GetActiveUniformBlockiv(program, index, pname, &mut value);
+                    },
+                    _ => ()
+                }
+
+                value
+            })
+            .collect()
+    }
+
+    // Needs GL 4.2 or 4.3 (or their extensions), which ComputeProgram checks for
+    fn dispatch_compute(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32) {
+        self.native.dispatch_compute(num_groups_x, num_groups_y, num_groups_z)
+    }
+
+    fn dispatch_compute_indirect(&self, indirect: isize) {
+        self.native.dispatch_compute_indirect(indirect)
+    }
+
+    fn memory_barrier(&self, barriers: GLbitfield) {
+        self.native.memory_barrier(barriers)
+    }
+
+    fn bind_image_texture(
+        &self,
+        unit: u32,
+        texture: u32,
+        level: i32,
+        layered: bool,
+        layer: i32,
+        access: GLenum,
+        format: GLenum
+    ) {
+        self.native.bind_image_texture(unit, texture, level, layered, layer, access, format)
+    }
+
+    fn create_texture(&self, target: GLenum) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl().GenTextures(1, &mut id) };
+        self.texture_targets.borrow_mut().insert(id, target);
+        self.edit_texture(id, |_| ());
+        id
+    }
+
+    fn delete_texture(&self, texture: u32) {
+        self.texture_targets.borrow_mut().remove(&texture);
+        self.native.delete_texture(texture)
+    }
+
+    // Without GL_ARB_texture_storage every level (and face) is allocated with TexImage2D
+    fn texture_storage_2d(&self, texture: u32, levels: i32, internal_format: GLenum, width: i32, height: i32) {
+        self.edit_texture(texture, |target| unsafe {
+            if self.gl().TexStorage2D.is_loaded() {
+                self.gl().TexStorage2D(target, levels, internal_format, width, height);
+                return;
+            }
+
+            let (format, type_) = get_transfer_format(internal_format);
+            let faces: Vec<GLenum> = if target == gl::TEXTURE_CUBE_MAP {
+                (0..6).map(|face| gl::TEXTURE_CUBE_MAP_POSITIVE_X + face).collect()
+            } else {
+                vec![target]
+            };
+
+            for level in 0..levels {
+                for face in faces.iter() {
+                    self.gl().TexImage2D(
+                        *face,
+                        level,
+                        internal_format as i32,
+                        (width >> level).max(1),
+                        (height >> level).max(1),
+                        0,
+                        format,
+                        type_,
+                        std::ptr::null()
+                    );
+                }
+            }
+
+            self.gl().TexParameteri(target, gl::TEXTURE_MAX_LEVEL, levels - 1);
+        })
+    }
+
+    unsafe fn texture_sub_image_2d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.edit_texture(texture, |target| {
+            self.gl().TexSubImage2D(target, level, x_offset, y_offset, width, height, format, type_, pixels)
+        })
+    }
+
+    // Cube map faces are layers here like with DSA, but have to be uploaded one face (depth 1) at a time
+    unsafe fn texture_sub_image_3d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.edit_texture(texture, |target| {
+            if target == gl::TEXTURE_CUBE_MAP {
+                let face = gl::TEXTURE_CUBE_MAP_POSITIVE_X + z_offset as u32;
+                self.gl().TexSubImage2D(face, level, x_offset, y_offset, width, height, format, type_, pixels)
+            } else {
+                self.gl().TexSubImage3D(
+                    target, level, x_offset, y_offset, z_offset, width, height, depth, format, type_, pixels
+                )
+            }
+        })
+    }
+
+    unsafe fn tex_image_2d(
+        &self,
+        target: GLenum,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.native.tex_image_2d(target, level, internal_format, width, height, border, format, type_, pixels)
+    }
+
+    fn generate_texture_mipmap(&self, texture: u32) {
+        self.edit_texture(texture, |target| unsafe { self.gl().GenerateMipmap(target) })
+    }
+
+    fn texture_parameter_i(&self, texture: u32, pname: GLenum, param: i32) {
+        self.edit_texture(texture, |target| unsafe { self.gl().TexParameteri(target, pname, param) })
+    }
+
+    fn bind_texture(&self, target: GLenum, texture: u32) {
+        self.native.bind_texture(target, texture)
+    }
+
+    // Binding 0 unbinds every target on the unit, like glBindTextureUnit does
+    fn bind_texture_unit(&self, unit: u32, texture: u32) {
+        let active = self.active_texture.get();
+        let unit = gl::TEXTURE0 + unit;
+
+        unsafe {
+            if unit != active { self.gl().ActiveTexture(unit); }
+
+            if texture == 0 {
+                for target in TEXTURE_TARGETS {
+                    self.gl().BindTexture(target, 0);
+                }
+            } else {
+                self.gl().BindTexture(self.get_texture_target(texture), texture);
+            }
+
+            if unit != active { self.gl().ActiveTexture(active); }
+        }
+    }
+
+    fn active_texture(&self, texture: GLenum) {
+        self.active_texture.set(texture);
+        self.native.active_texture(texture)
+    }
+
+    // Bindless textures only need GL 4.0, so these work as long as the extension is there
+    fn get_texture_handle(&self, texture: u32) -> u64 {
+        self.native.get_texture_handle(texture)
+    }
+
+    fn make_texture_handle_resident(&self, handle: u64) {
+        self.native.make_texture_handle_resident(handle)
+    }
+
+    fn make_texture_handle_non_resident(&self, handle: u64) {
+        self.native.make_texture_handle_non_resident(handle)
+    }
+
+    fn create_framebuffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl().GenFramebuffers(1, &mut id) };
+        self.edit_framebuffer(id, || ());
+        id
+    }
+
+    fn delete_framebuffer(&self, framebuffer: u32) {
+        self.native.delete_framebuffer(framebuffer)
+    }
+
+    fn named_framebuffer_texture(&self, framebuffer: u32, attachment: GLenum, texture: u32, level: i32) {
+        self.edit_framebuffer(framebuffer, || unsafe {
+            self.gl().FramebufferTexture(gl::DRAW_FRAMEBUFFER, attachment, texture, level)
+        })
+    }
+
+    fn named_framebuffer_draw_buffers(&self, framebuffer: u32, buffers: &[GLenum]) {
+        self.edit_framebuffer(framebuffer, || unsafe {
+            self.gl().DrawBuffers(buffers.len() as i32, buffers.as_ptr())
+        })
+    }
+
+    fn named_framebuffer_renderbuffer(
+        &self,
+        framebuffer: u32,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: u32
+    ) {
+        self.edit_framebuffer(framebuffer, || unsafe {
+            self.gl().FramebufferRenderbuffer(gl::DRAW_FRAMEBUFFER, attachment, renderbuffer_target, renderbuffer)
+        })
+    }
+
+    // Checked as the draw framebuffer whatever the target, both bindings take the same attachments here
+    fn check_named_framebuffer_status(&self, framebuffer: u32, _target: GLenum) -> GLenum {
+        self.edit_framebuffer(framebuffer, || unsafe { self.gl().CheckFramebufferStatus(gl::DRAW_FRAMEBUFFER) })
+    }
+
+    fn bind_framebuffer(&self, target: GLenum, framebuffer: u32) {
+        self.native.bind_framebuffer(target, framebuffer)
+    }
+
+    fn create_renderbuffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl().GenRenderbuffers(1, &mut id) };
+        self.edit_renderbuffer(id, || ());
+        id
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: u32) {
+        self.native.delete_renderbuffer(renderbuffer)
+    }
+
+    fn named_renderbuffer_storage(&self, renderbuffer: u32, internal_format: GLenum, width: i32, height: i32) {
+        self.edit_renderbuffer(renderbuffer, || unsafe {
+            self.gl().RenderbufferStorage(gl::RENDERBUFFER, internal_format, width, height)
+        })
+    }
+
+    fn enable(&self, cap: GLenum) {
+        self.native.enable(cap)
+    }
+
+    fn disable(&self, cap: GLenum) {
+        self.native.disable(cap)
+    }
+
+    fn depth_func(&self, func: GLenum) {
+        self.native.depth_func(func)
+    }
+
+    fn blend_func(&self, sfactor: GLenum, dfactor: GLenum) {
+        self.native.blend_func(sfactor, dfactor)
+    }
+
+    fn cull_face(&self, mode: GLenum) {
+        self.native.cull_face(mode)
+    }
+
+    unsafe fn debug_message_callback(&self, callback: gl::types::GLDEBUGPROC, user_param: *const GLvoid) {
+        self.native.debug_message_callback(callback, user_param)
+    }
+
+    fn object_label(&self, identifier: GLenum, name: u32, label: &str) {
+        self.native.object_label(identifier, name, label)
+    }
+
+    fn push_debug_group(&self, source: GLenum, id: u32, message: &str) {
+        self.native.push_debug_group(source, id, message)
+    }
+
+    fn pop_debug_group(&self) {
+        self.native.pop_debug_group()
+    }
+
+    fn get_integer_v(&self, pname: GLenum) -> i32 {
+        self.native.get_integer_v(pname)
+    }
+
+    fn get_string(&self, name: GLenum) -> String {
+        self.native.get_string(name)
+    }
+
+    fn get_string_i(&self, name: GLenum, index: u32) -> String {
+        self.native.get_string_i(name, index)
+    }
+}
@@ -0,0 +1,107 @@
+use std::ops::BitOr;
+use super::{GlError, GlContext, GlFeature, ShaderCodeBundle, ShaderCompileType, ShaderProgram, ObjectName, gl};
+
+// Program with only a compute stage, which runs through dispatches instead of draws
+pub struct ComputeProgram {
+    program: ShaderProgram
+}
+
+impl ComputeProgram {
+    // The bundle needs a compute stage, GLSL or SPIR-V, and no other stage
+    pub fn new(gl: &GlContext, shader_bundle: ShaderCodeBundle) -> Result<ComputeProgram, GlError> {
+        gl.require(GlFeature::ComputeShader)?;
+
+        let graphics_stages = [
+            &shader_bundle.vertex,
+            &shader_bundle.tess_control,
+            &shader_bundle.tess_evaluation,
+            &shader_bundle.geometry,
+            &shader_bundle.fragment
+        ];
+        let has_compute = shader_bundle.compute.is_some()
+            || shader_bundle.spirv.iter().any(|(type_, _)| matches!(type_, ShaderCompileType::Compute));
+        let has_graphics = graphics_stages.iter().any(|stage| stage.is_some())
+            || shader_bundle.spirv.iter().any(|(type_, _)| !matches!(type_, ShaderCompileType::Compute));
+
+        if !has_compute || has_graphics {
+            return Err(GlError::ShaderCompileError(
+                ShaderCompileType::Compute,
+                ObjectName::new(0, &shader_bundle.name),
+                String::from("Compute programs need a compute stage and no other stages").into()
+            ));
+        }
+
+        Ok(ComputeProgram { program: ShaderProgram::new(gl, shader_bundle)? })
+    }
+
+    // Uniforms are set through the program, it needs to be mutable to recompile it
+    pub fn get_program(&self) -> &ShaderProgram {
+        &self.program
+    }
+
+    pub fn get_program_mut(&mut self) -> &mut ShaderProgram {
+        &mut self.program
+    }
+
+    // Counts are in work groups, not invocations
+    pub fn dispatch(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32) {
+        let _group = self.program.get_context().debug_group(self.program.get_label().unwrap_or("ComputeProgram"));
+
+        self.program.use_program();
+        self.program.get_context().dispatch_compute(num_groups_x, num_groups_y, num_groups_z);
+    }
+
+    // Reads a DispatchCommand at `offset` bytes into the buffer bound to GL_DISPATCH_INDIRECT_BUFFER,
+    // which a previous dispatch can write to (see MemoryBarrier::COMMAND)
+    pub fn dispatch_indirect(&self, offset: usize) {
+        let _group = self.program.get_context().debug_group(self.program.get_label().unwrap_or("ComputeProgram"));
+
+        self.program.use_program();
+        self.program.get_context().dispatch_compute_indirect(offset as isize);
+    }
+}
+
+// To be used with glDispatchComputeIndirect,
+// Longer name is DispatchIndirectCommand
+#[repr(C, packed)]
+pub struct DispatchCommand {
+    pub num_groups_x: u32,
+    pub num_groups_y: u32,
+    pub num_groups_z: u32
+}
+
+// Passed to GlContext::memory_barrier() after a dispatch, and before anything reads what it wrote.
+// Named after how the data is read next, combine them with |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryBarrier(pub gl::types::GLbitfield);
+
+impl MemoryBarrier {
+    // Buffer<T> as vertex attributes or instance transforms
+    pub const VERTEX_ATTRIB_ARRAY: MemoryBarrier = MemoryBarrier(gl::VERTEX_ATTRIB_ARRAY_BARRIER_BIT);
+    // Buffer<u32> as an element buffer
+    pub const ELEMENT_ARRAY: MemoryBarrier = MemoryBarrier(gl::ELEMENT_ARRAY_BARRIER_BIT);
+    pub const UNIFORM: MemoryBarrier = MemoryBarrier(gl::UNIFORM_BARRIER_BIT);
+    // Texture sampled in shaders
+    pub const TEXTURE_FETCH: MemoryBarrier = MemoryBarrier(gl::TEXTURE_FETCH_BARRIER_BIT);
+    // Texture bound with Texture::bind_image() in a later dispatch
+    pub const SHADER_IMAGE_ACCESS: MemoryBarrier = MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+    // DrawCommand or DispatchCommand buffers
+    pub const COMMAND: MemoryBarrier = MemoryBarrier(gl::COMMAND_BARRIER_BIT);
+    pub const PIXEL_BUFFER: MemoryBarrier = MemoryBarrier(gl::PIXEL_BUFFER_BARRIER_BIT);
+    // Texture read back or updated from the CPU
+    pub const TEXTURE_UPDATE: MemoryBarrier = MemoryBarrier(gl::TEXTURE_UPDATE_BARRIER_BIT);
+    // Buffer read back or updated from the CPU
+    pub const BUFFER_UPDATE: MemoryBarrier = MemoryBarrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+    // Texture attached to a Framebuffer
+    pub const FRAMEBUFFER: MemoryBarrier = MemoryBarrier(gl::FRAMEBUFFER_BARRIER_BIT);
+    pub const SHADER_STORAGE: MemoryBarrier = MemoryBarrier(gl::SHADER_STORAGE_BARRIER_BIT);
+    pub const ALL: MemoryBarrier = MemoryBarrier(gl::ALL_BARRIER_BITS);
+}
+
+impl BitOr for MemoryBarrier {
+    type Output = MemoryBarrier;
+
+    fn bitor(self, rhs: MemoryBarrier) -> MemoryBarrier {
+        MemoryBarrier(self.0 | rhs.0)
+    }
+}
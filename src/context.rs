@@ -0,0 +1,559 @@
+use std::{cell::RefCell, collections::HashSet, ffi::c_void, fmt::Display, ops::Deref, rc::Rc};
+use super::{GlBackend, GlError, NativeGl, CompatGl, DebugSink, DebugSeverity, DebugGroup, StateCacheStats, MemoryBarrier, gl::{self, types::GLenum}};
+use super::{BufferBlockType, debug::{DebugState, debug_callback}, state_cache::StateCache, block_binding::BlockBindings};
+#[cfg(feature = "gles")]
+use super::GlesGl;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct GlVersion {
+    pub major: u32,
+    pub minor: u32
+}
+
+impl GlVersion {
+    pub fn new(major: u32, minor: u32) -> GlVersion {
+        GlVersion { major, minor }
+    }
+}
+
+impl Display for GlVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlApi {
+    Gl,
+    Gles
+}
+
+impl Display for GlApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            GlApi::Gl => "OpenGL",
+            GlApi::Gles => "OpenGL ES"
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+// Optional functionality that depends on the GL version or extensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlFeature {
+    DirectStateAccess,
+    MultiDrawIndirect,
+    BindlessTexture,
+    Debug,
+    ComputeShader,
+    Tessellation,
+    ProgramBinary,
+    Spirv,
+    SeparateShaderObjects,
+    ShaderStorageBuffer,
+    PersistentMapping,
+    DoublePrecision
+}
+
+impl Display for GlFeature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            GlFeature::DirectStateAccess => "Direct state access (GL 4.5 or GL_ARB_direct_state_access)",
+            GlFeature::MultiDrawIndirect =>
+                "Multi-draw indirect (GL 4.3, GL_ARB_multi_draw_indirect or GL_EXT_multi_draw_indirect)",
+            GlFeature::BindlessTexture => "Bindless textures (GL_ARB_bindless_texture, not on GLES)",
+            GlFeature::Debug => "Debug output (GL 4.3, GLES 3.2 or GL_KHR_debug)",
+            GlFeature::ComputeShader => "Compute shaders (GL 4.3 or GL_ARB_compute_shader)",
+            GlFeature::Tessellation => "Tessellation shaders (GL 4.0 or GL_ARB_tessellation_shader)",
+            GlFeature::ProgramBinary => "Program binaries (GL 4.1, GLES 3.0 or GL_ARB_get_program_binary)",
+            GlFeature::Spirv => "SPIR-V shaders (GL 4.6 or GL_ARB_gl_spirv)",
+            GlFeature::SeparateShaderObjects =>
+                "Separable programs and pipelines (GL 4.1, GLES 3.1 or GL_ARB_separate_shader_objects)",
+            GlFeature::ShaderStorageBuffer =>
+                "Shader storage buffers (GL 4.3, GLES 3.1 or GL_ARB_shader_storage_buffer_object)",
+            GlFeature::PersistentMapping =>
+                "Persistently mapped buffers (GL 4.4, GL_ARB_buffer_storage or GL_EXT_buffer_storage)",
+            GlFeature::DoublePrecision => "Double precision uniforms (GL 4.0 or GL_ARB_gpu_shader_fp64, not on GLES)"
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+// Implementation limits, queried once when the context is created
+#[derive(Debug, Clone)]
+pub struct GlLimits {
+    pub max_texture_image_units: i32,
+    pub max_combined_texture_image_units: i32,
+    pub max_uniform_block_size: i32,
+    pub max_uniform_buffer_bindings: i32,
+    pub max_color_attachments: i32,
+    pub max_draw_buffers: i32,
+    pub max_texture_size: i32,
+    pub max_vertex_attribs: i32
+}
+
+impl GlLimits {
+    fn query(backend: &dyn GlBackend) -> GlLimits {
+        GlLimits {
+            max_texture_image_units: backend.get_integer_v(gl::MAX_TEXTURE_IMAGE_UNITS),
+            max_combined_texture_image_units: backend.get_integer_v(gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS),
+            max_uniform_block_size: backend.get_integer_v(gl::MAX_UNIFORM_BLOCK_SIZE),
+            max_uniform_buffer_bindings: backend.get_integer_v(gl::MAX_UNIFORM_BUFFER_BINDINGS),
+            max_color_attachments: backend.get_integer_v(gl::MAX_COLOR_ATTACHMENTS),
+            max_draw_buffers: backend.get_integer_v(gl::MAX_DRAW_BUFFERS),
+            max_texture_size: backend.get_integer_v(gl::MAX_TEXTURE_SIZE),
+            max_vertex_attribs: backend.get_integer_v(gl::MAX_VERTEX_ATTRIBS)
+        }
+    }
+}
+
+struct ContextInner {
+    backend: Rc<dyn GlBackend>,
+    api: GlApi,
+    version: GlVersion,
+    extensions: HashSet<String>,
+    limits: GlLimits,
+    debug: RefCell<Option<Box<DebugState>>>,
+    state: StateCache,
+    block_bindings: BlockBindings
+}
+
+impl Drop for ContextInner {
+    fn drop(&mut self) {
+        // The callback can't be left pointing at the state once it is freed
+        if self.debug.get_mut().take().is_some() {
+            unsafe { self.backend.debug_message_callback(None, std::ptr::null()) };
+        }
+    }
+}
+
+// Owns the GL functions for one context, along with what was detected about it.
+// Every resource keeps a clone of the context it was created with (clones are cheap),
+// so it always talks to the right context, including when it is dropped.
+// Derefs to the backend so GL calls can be made on it directly
+#[derive(Clone)]
+pub struct GlContext {
+    inner: Rc<ContextInner>
+}
+
+impl GlContext {
+    // The context the loader belongs to must already be current on this thread.
+    // Contexts without DSA get CompatGl, which needs at least OpenGL 3.3
+    pub fn load_with<F: FnMut(&'static str) -> *const c_void>(loader: F) -> Result<GlContext, GlError> {
+        let native = NativeGl::load_with(loader);
+        let context = GlContext::new(Rc::new(native.clone()));
+
+        if context.supports(GlFeature::DirectStateAccess) {
+            context.check()?;
+            Ok(context)
+        } else {
+            GlContext::from_backend(Rc::new(CompatGl::from_native(native)))
+        }
+    }
+
+    // Same as load_with() for an OpenGL ES 3.2 context
+    #[cfg(feature = "gles")]
+    pub fn load_gles_with<F: FnMut(&'static str) -> *const c_void>(loader: F) -> Result<GlContext, GlError> {
+        GlContext::from_backend(Rc::new(GlesGl::load_with(loader)))
+    }
+
+    // Errors if the backend can't run the crate on this context
+    pub fn from_backend(backend: Rc<dyn GlBackend>) -> Result<GlContext, GlError> {
+        let context = GlContext::new(backend);
+        context.check()?;
+        Ok(context)
+    }
+
+    fn new(backend: Rc<dyn GlBackend>) -> GlContext {
+        let api = backend.get_api();
+        let version = GlVersion::new(
+            backend.get_integer_v(gl::MAJOR_VERSION) as u32,
+            backend.get_integer_v(gl::MINOR_VERSION) as u32
+        );
+
+        let extension_count = backend.get_integer_v(gl::NUM_EXTENSIONS).max(0) as u32;
+        let extensions = (0..extension_count)
+            .map(|i| backend.get_string_i(gl::EXTENSIONS, i))
+            .collect();
+
+        let limits = GlLimits::query(&*backend);
+
+        GlContext {
+            inner: Rc::new(ContextInner {
+                backend,
+                api,
+                version,
+                extensions,
+                limits,
+                debug: RefCell::new(None),
+                state: StateCache::default(),
+                block_bindings: BlockBindings::default()
+            })
+        }
+    }
+
+    fn check(&self) -> Result<(), GlError> {
+        let version = self.inner.version;
+        let minimum = match self.inner.api {
+            // Backends without DSA entry points emulate them
+            GlApi::Gl if !self.inner.backend.emulates_dsa() => return self.require(GlFeature::DirectStateAccess),
+            GlApi::Gl => GlVersion::new(3, 3),
+            GlApi::Gles => GlVersion::new(3, 2)
+        };
+
+        if version < minimum {
+            return Err(GlError::ContextCreationError(
+                format!("{} {} is required, the context is {}", self.inner.api, minimum, version)
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_backend(&self) -> &Rc<dyn GlBackend> {
+        &self.inner.backend
+    }
+
+    pub fn get_api(&self) -> GlApi {
+        self.inner.api
+    }
+
+    pub fn get_version(&self) -> GlVersion {
+        self.inner.version
+    }
+
+    pub fn get_extensions(&self) -> &HashSet<String> {
+        &self.inner.extensions
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.inner.extensions.contains(name)
+    }
+
+    pub fn get_limits(&self) -> &GlLimits {
+        &self.inner.limits
+    }
+
+    pub fn supports(&self, feature: GlFeature) -> bool {
+        let version = self.inner.version;
+
+        match (self.inner.api, feature) {
+            (GlApi::Gl, GlFeature::DirectStateAccess) =>
+                version >= GlVersion::new(4, 5) || self.has_extension("GL_ARB_direct_state_access"),
+            (GlApi::Gl, GlFeature::MultiDrawIndirect) =>
+                version >= GlVersion::new(4, 3) || self.has_extension("GL_ARB_multi_draw_indirect"),
+            (GlApi::Gl, GlFeature::Debug) => version >= GlVersion::new(4, 3) || self.has_extension("GL_KHR_debug"),
+            // Also brings glMemoryBarrier and image load/store, which are older
+            (GlApi::Gl, GlFeature::ComputeShader) =>
+                version >= GlVersion::new(4, 3) || self.has_extension("GL_ARB_compute_shader"),
+            (GlApi::Gl, GlFeature::Tessellation) =>
+                version >= GlVersion::new(4, 0) || self.has_extension("GL_ARB_tessellation_shader"),
+            (GlApi::Gl, GlFeature::ProgramBinary) =>
+                version >= GlVersion::new(4, 1) || self.has_extension("GL_ARB_get_program_binary"),
+            (GlApi::Gl, GlFeature::Spirv) => version >= GlVersion::new(4, 6) || self.has_extension("GL_ARB_gl_spirv"),
+            (GlApi::Gl, GlFeature::SeparateShaderObjects) =>
+                version >= GlVersion::new(4, 1) || self.has_extension("GL_ARB_separate_shader_objects"),
+            (GlApi::Gl, GlFeature::ShaderStorageBuffer) =>
+                version >= GlVersion::new(4, 3) || self.has_extension("GL_ARB_shader_storage_buffer_object"),
+            (GlApi::Gl, GlFeature::PersistentMapping) =>
+                version >= GlVersion::new(4, 4) || self.has_extension("GL_ARB_buffer_storage"),
+            (GlApi::Gl, GlFeature::DoublePrecision) =>
+                version >= GlVersion::new(4, 0) || self.has_extension("GL_ARB_gpu_shader_fp64"),
+            (GlApi::Gl, GlFeature::BindlessTexture) => self.has_extension("GL_ARB_bindless_texture"),
+            (GlApi::Gles, GlFeature::DirectStateAccess) => false,
+            (GlApi::Gles, GlFeature::MultiDrawIndirect) => self.has_extension("GL_EXT_multi_draw_indirect"),
+            (GlApi::Gles, GlFeature::Debug) => version >= GlVersion::new(3, 2) || self.has_extension("GL_KHR_debug"),
+            (GlApi::Gles, GlFeature::ComputeShader) => version >= GlVersion::new(3, 1),
+            (GlApi::Gles, GlFeature::Tessellation) => version >= GlVersion::new(3, 2),
+            (GlApi::Gles, GlFeature::ProgramBinary) => version >= GlVersion::new(3, 0),
+            (GlApi::Gles, GlFeature::Spirv) => false,
+            (GlApi::Gles, GlFeature::SeparateShaderObjects) => version >= GlVersion::new(3, 1),
+            (GlApi::Gles, GlFeature::ShaderStorageBuffer) => version >= GlVersion::new(3, 1),
+            (GlApi::Gles, GlFeature::PersistentMapping) => self.has_extension("GL_EXT_buffer_storage"),
+            // GLSL ES has no doubles
+            (GlApi::Gles, GlFeature::DoublePrecision) => false,
+            // The GLES backend has no bindless entry points, so its handle functions are never reached
+            (GlApi::Gles, GlFeature::BindlessTexture) => false
+        }
+    }
+
+    pub fn require(&self, feature: GlFeature) -> Result<(), GlError> {
+        if self.supports(feature) {
+            Ok(())
+        } else {
+            Err(GlError::FeatureNotSupported(feature))
+        }
+    }
+
+    // Errors if `requested` is over the limit, `name` is the GL enum the limit was queried with
+    pub fn check_limit(&self, name: &'static str, requested: i32, limit: i32) -> Result<(), GlError> {
+        if requested > limit {
+            Err(GlError::LimitExceeded(name, requested, limit))
+        } else {
+            Ok(())
+        }
+    }
+
+    // Sends driver messages at or above `min_severity` to the sink.
+    // High severity messages are also kept as GlError::DriverError until take_debug_errors() is called.
+    // Replaces the sink if debug output was already enabled
+    pub fn enable_debug(&self, sink: DebugSink, min_severity: DebugSeverity) -> Result<(), GlError> {
+        self.require(GlFeature::Debug)?;
+        self.disable_debug();
+
+        let state = Box::new(DebugState::new(sink, min_severity));
+
+        unsafe {
+            self.debug_message_callback(Some(debug_callback), &*state as *const DebugState as *const c_void);
+        }
+
+        self.enable(gl::DEBUG_OUTPUT);
+        // Makes messages arrive during the call that caused them, on the same thread
+        self.enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+
+        *self.inner.debug.borrow_mut() = Some(state);
+
+        Ok(())
+    }
+
+    pub fn disable_debug(&self) {
+        if self.inner.debug.borrow().is_none() {
+            return;
+        }
+
+        // The callback reads the state through its pointer, so it is unregistered before the state is dropped
+        unsafe { self.debug_message_callback(None, std::ptr::null()) };
+        self.disable(gl::DEBUG_OUTPUT);
+        self.disable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+
+        self.inner.debug.borrow_mut().take();
+    }
+
+    // Intended to be checked once a frame, returns every high severity message since the last call
+    pub fn take_debug_errors(&self) -> Vec<GlError> {
+        match &*self.inner.debug.borrow() {
+            Some(state) => state.take_errors(),
+            None => Vec::new()
+        }
+    }
+
+    // Names the object in frame debuggers (RenderDoc, apitrace) and debug messages.
+    // Does nothing if the context has no debug output
+    pub fn label_object(&self, identifier: gl::types::GLenum, id: u32, label: &str) {
+        if self.supports(GlFeature::Debug) {
+            self.object_label(identifier, id, label);
+        }
+    }
+
+    // Everything until the returned guard is dropped is grouped under `message` in frame debuggers
+    pub fn debug_group(&self, message: &str) -> DebugGroup<'_> {
+        DebugGroup::new(self, message)
+    }
+
+    // Whether two handles refer to the same context
+    pub fn same_context(&self, other: &GlContext) -> bool {
+        Rc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    pub fn get_state_stats(&self) -> StateCacheStats {
+        self.inner.state.get_stats()
+    }
+
+    pub fn reset_state_stats(&self) {
+        self.inner.state.reset_stats();
+    }
+
+    // Must be called after GL state is changed without going through this context
+    // (other libraries, or calls made on get_backend() directly)
+    pub fn invalidate_state_cache(&self) {
+        self.inner.state.invalidate();
+    }
+
+    // Binding point shared by every block called `name`, allocated the first time it is asked for.
+    // See ShaderProgram::bind_to_ubo() and bind_to_ssbo()
+    pub fn get_block_binding(&self, type_: BufferBlockType, name: &str) -> Result<u32, GlError> {
+        let limit = match type_ {
+            BufferBlockType::Uniform => self.inner.limits.max_uniform_buffer_bindings,
+            // Only queried here, the enum is invalid on contexts without storage buffers
+            BufferBlockType::ShaderStorage => {
+                self.require(GlFeature::ShaderStorageBuffer)?;
+                self.inner.backend.get_integer_v(gl::MAX_SHADER_STORAGE_BUFFER_BINDINGS)
+            }
+        };
+
+        self.inner.block_bindings.get(type_, name, limit)
+    }
+
+    // Makes blocks called `name` use `binding`. Errors if another block has it,
+    // or if the block was already given a different one
+    pub fn set_block_binding(&self, type_: BufferBlockType, name: &str, binding: u32) -> Result<(), GlError> {
+        self.inner.block_bindings.set(type_, name, binding)
+    }
+
+    // Makes what earlier dispatches wrote visible to the kinds of reads in `barriers`.
+    // Shadows the backend method, which takes the raw bits
+    pub fn memory_barrier(&self, barriers: MemoryBarrier) {
+        self.inner.backend.memory_barrier(barriers.0);
+    }
+}
+
+// These shadow the backend methods of the same name, so calls that would change nothing are skipped.
+// get_backend() bypasses the cache
+impl GlContext {
+    pub fn use_program(&self, program: u32) {
+        if self.inner.state.use_program(program) {
+            self.inner.backend.use_program(program);
+        }
+    }
+
+    pub fn bind_program_pipeline(&self, pipeline: u32) {
+        if self.inner.state.bind_program_pipeline(pipeline) {
+            self.inner.backend.bind_program_pipeline(pipeline);
+        }
+    }
+
+    pub fn delete_program_pipeline(&self, pipeline: u32) {
+        self.inner.state.delete_program_pipeline(pipeline);
+        self.inner.backend.delete_program_pipeline(pipeline);
+    }
+
+    pub fn bind_vertex_array(&self, array: u32) {
+        if self.inner.state.bind_vertex_array(array) {
+            self.inner.backend.bind_vertex_array(array);
+        }
+    }
+
+    pub fn delete_vertex_array(&self, array: u32) {
+        self.inner.state.delete_vertex_array(array);
+        self.inner.backend.delete_vertex_array(array);
+    }
+
+    pub fn create_texture(&self, target: GLenum) -> u32 {
+        let texture = self.inner.backend.create_texture(target);
+        self.inner.state.create_texture(texture, target);
+
+        texture
+    }
+
+    pub fn delete_texture(&self, texture: u32) {
+        self.inner.state.delete_texture(texture);
+        self.inner.backend.delete_texture(texture);
+    }
+
+    pub fn active_texture(&self, texture: GLenum) {
+        if self.inner.state.active_texture(texture) {
+            self.inner.backend.active_texture(texture);
+        }
+    }
+
+    pub fn bind_texture(&self, target: GLenum, texture: u32) {
+        if self.inner.state.bind_texture(target, texture) {
+            self.inner.backend.bind_texture(target, texture);
+        }
+    }
+
+    pub fn bind_texture_unit(&self, unit: u32, texture: u32) {
+        if self.inner.state.bind_texture_unit(unit, texture) {
+            self.inner.backend.bind_texture_unit(unit, texture);
+        }
+    }
+
+    pub fn bind_framebuffer(&self, target: GLenum, framebuffer: u32) {
+        if self.inner.state.bind_framebuffer(target, framebuffer) {
+            self.inner.backend.bind_framebuffer(target, framebuffer);
+        }
+    }
+
+    pub fn delete_framebuffer(&self, framebuffer: u32) {
+        self.inner.state.delete_framebuffer(framebuffer);
+        self.inner.backend.delete_framebuffer(framebuffer);
+    }
+
+    pub fn enable(&self, cap: GLenum) {
+        if self.inner.state.set_capability(cap, true) {
+            self.inner.backend.enable(cap);
+        }
+    }
+
+    pub fn disable(&self, cap: GLenum) {
+        if self.inner.state.set_capability(cap, false) {
+            self.inner.backend.disable(cap);
+        }
+    }
+
+    pub fn depth_func(&self, func: GLenum) {
+        if self.inner.state.depth_func(func) {
+            self.inner.backend.depth_func(func);
+        }
+    }
+
+    pub fn blend_func(&self, sfactor: GLenum, dfactor: GLenum) {
+        if self.inner.state.blend_func(sfactor, dfactor) {
+            self.inner.backend.blend_func(sfactor, dfactor);
+        }
+    }
+
+    pub fn cull_face(&self, mode: GLenum) {
+        if self.inner.state.cull_face(mode) {
+            self.inner.backend.cull_face(mode);
+        }
+    }
+
+    // Only GL_PATCH_VERTICES is cached
+    pub fn patch_parameter_i(&self, pname: GLenum, value: i32) {
+        if pname != gl::PATCH_VERTICES || self.inner.state.patch_vertices(value) {
+            self.inner.backend.patch_parameter_i(pname, value);
+        }
+    }
+}
+
+impl Deref for GlContext {
+    type Target = dyn GlBackend;
+
+    fn deref(&self) -> &Self::Target {
+        &*self.inner.backend
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+    use crate::{DebugSeverity, DebugSink, MockGl, gl, mock_backend::testing::{get_names, mock_context}};
+
+    // Records the calls made before the sink (and the state owning it) was dropped
+    struct DropCheck(Rc<MockGl>, Rc<RefCell<Vec<&'static str>>>);
+
+    impl Drop for DropCheck {
+        fn drop(&mut self) {
+            *self.1.borrow_mut() = get_names(&self.0.get_calls());
+        }
+    }
+
+    #[test]
+    fn disable_debug_unregisters_before_dropping() {
+        let (mock, gl) = mock_context(|_| ());
+        let dropped_after = Rc::new(RefCell::new(Vec::new()));
+        let check = DropCheck(mock.clone(), dropped_after.clone());
+
+        let sink = DebugSink::Callback(Box::new(move |_| { let _ = &check; }));
+        gl.enable_debug(sink, DebugSeverity::High).unwrap();
+        mock.emit_debug_message(gl::DEBUG_SOURCE_API, gl::DEBUG_TYPE_ERROR, 1, gl::DEBUG_SEVERITY_HIGH, "error");
+        assert_eq!(gl.take_debug_errors().len(), 1);
+        mock.clear_calls();
+
+        gl.disable_debug();
+        assert_eq!(*dropped_after.borrow(), ["DebugMessageCallback", "Disable", "Disable"]);
+
+        let calls = mock.take_calls();
+        assert_eq!(calls[0].args, [0]);
+        assert_eq!([calls[1].args[0], calls[2].args[0]], [gl::DEBUG_OUTPUT as i64, gl::DEBUG_OUTPUT_SYNCHRONOUS as i64]);
+
+        // Nothing is registered anymore, so this goes nowhere
+        mock.emit_debug_message(gl::DEBUG_SOURCE_API, gl::DEBUG_TYPE_ERROR, 2, gl::DEBUG_SEVERITY_HIGH, "error");
+        assert!(gl.take_debug_errors().is_empty());
+        gl.disable_debug();
+        assert!(mock.get_calls().is_empty());
+    }
+}
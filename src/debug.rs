@@ -0,0 +1,238 @@
+use std::{cell::RefCell, ffi::{CStr, c_void}, fmt::Display};
+use super::{GlError, GlContext, GlFeature, gl::{self, types::{GLenum, GLchar}}};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugSource {
+    Api,
+    WindowSystem,
+    ShaderCompiler,
+    ThirdParty,
+    Application,
+    Other
+}
+
+impl DebugSource {
+    fn from_gl(source: GLenum) -> DebugSource {
+        match source {
+            gl::DEBUG_SOURCE_API => DebugSource::Api,
+            gl::DEBUG_SOURCE_WINDOW_SYSTEM => DebugSource::WindowSystem,
+            gl::DEBUG_SOURCE_SHADER_COMPILER => DebugSource::ShaderCompiler,
+            gl::DEBUG_SOURCE_THIRD_PARTY => DebugSource::ThirdParty,
+            gl::DEBUG_SOURCE_APPLICATION => DebugSource::Application,
+            _ => DebugSource::Other
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugType {
+    Error,
+    DeprecatedBehavior,
+    UndefinedBehavior,
+    Portability,
+    Performance,
+    Marker,
+    PushGroup,
+    PopGroup,
+    Other
+}
+
+impl DebugType {
+    fn from_gl(type_: GLenum) -> DebugType {
+        match type_ {
+            gl::DEBUG_TYPE_ERROR => DebugType::Error,
+            gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => DebugType::DeprecatedBehavior,
+            gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => DebugType::UndefinedBehavior,
+            gl::DEBUG_TYPE_PORTABILITY => DebugType::Portability,
+            gl::DEBUG_TYPE_PERFORMANCE => DebugType::Performance,
+            gl::DEBUG_TYPE_MARKER => DebugType::Marker,
+            gl::DEBUG_TYPE_PUSH_GROUP => DebugType::PushGroup,
+            gl::DEBUG_TYPE_POP_GROUP => DebugType::PopGroup,
+            _ => DebugType::Other
+        }
+    }
+}
+
+// Ordered from least to most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DebugSeverity {
+    Notification,
+    Low,
+    Medium,
+    High
+}
+
+impl DebugSeverity {
+    fn from_gl(severity: GLenum) -> DebugSeverity {
+        match severity {
+            gl::DEBUG_SEVERITY_HIGH => DebugSeverity::High,
+            gl::DEBUG_SEVERITY_MEDIUM => DebugSeverity::Medium,
+            gl::DEBUG_SEVERITY_LOW => DebugSeverity::Low,
+            _ => DebugSeverity::Notification
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DebugMessage {
+    pub source: DebugSource,
+    pub type_: DebugType,
+    pub id: u32,
+    pub severity: DebugSeverity,
+    pub message: String
+}
+
+impl Display for DebugMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[{:?}] {:?} {:?} ({}): {}",
+            self.severity, self.source, self.type_, self.id, self.message
+        )
+    }
+}
+
+// Where debug messages end up, besides high severity ones also being kept as errors
+pub enum DebugSink {
+    // Emits a tracing event with the level matching the severity.
+    // Also reaches log-based loggers when no tracing subscriber is installed
+    Log,
+    Callback(Box<dyn Fn(&DebugMessage)>)
+}
+
+impl DebugSink {
+    fn send(&self, message: &DebugMessage) {
+        match self {
+            DebugSink::Log => {
+                // Tracing levels have to be known at compile time
+                macro_rules! emit {
+                    ($level:ident) => {
+                        tracing::$level!(
+                            source = ?message.source,
+                            type_ = ?message.type_,
+                            id = message.id,
+                            "{}", message.message
+                        )
+                    };
+                }
+
+                match message.severity {
+                    DebugSeverity::High => emit!(error),
+                    DebugSeverity::Medium => emit!(warn),
+                    DebugSeverity::Low => emit!(info),
+                    DebugSeverity::Notification => emit!(debug)
+                }
+            },
+            DebugSink::Callback(callback) => callback(message)
+        }
+    }
+}
+
+// Lives in a box owned by the context, the GL callback gets a pointer to it as its user param
+pub(crate) struct DebugState {
+    sink: DebugSink,
+    min_severity: DebugSeverity,
+    errors: RefCell<Vec<GlError>>
+}
+
+impl DebugState {
+    pub(crate) fn new(sink: DebugSink, min_severity: DebugSeverity) -> DebugState {
+        DebugState { sink, min_severity, errors: RefCell::new(Vec::new()) }
+    }
+
+    pub(crate) fn take_errors(&self) -> Vec<GlError> {
+        std::mem::take(&mut *self.errors.borrow_mut())
+    }
+
+    fn handle(&self, message: DebugMessage) {
+        if message.severity >= self.min_severity {
+            self.sink.send(&message);
+        }
+
+        if message.severity == DebugSeverity::High {
+            self.errors.borrow_mut().push(GlError::DriverError(message));
+        }
+    }
+}
+
+// Installed with glDebugMessageCallback.
+// Debug output is made synchronous, so this runs on the thread making the offending call
+pub(crate) extern "system" fn debug_callback(
+    source: GLenum,
+    type_: GLenum,
+    id: u32,
+    severity: GLenum,
+    length: i32,
+    message: *const GLchar,
+    user_param: *mut c_void
+) {
+    if user_param.is_null() || message.is_null() {
+        return;
+    }
+
+    let message = unsafe {
+        if length >= 0 {
+            let bytes = std::slice::from_raw_parts(message as *const u8, length as usize);
+            String::from_utf8_lossy(bytes).into_owned()
+        } else {
+            CStr::from_ptr(message).to_string_lossy().into_owned()
+        }
+    };
+
+    // Pointer is kept valid by the context, which uninstalls the callback before freeing it
+    let state = unsafe { &*(user_param as *const DebugState) };
+
+    state.handle(DebugMessage {
+        source: DebugSource::from_gl(source),
+        type_: DebugType::from_gl(type_),
+        id,
+        severity: DebugSeverity::from_gl(severity),
+        message
+    });
+}
+
+// How GL objects are referred to in errors, the debug label if one was set and the ID otherwise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObjectName {
+    pub id: u32,
+    pub label: Option<String>
+}
+
+impl ObjectName {
+    pub fn new(id: u32, label: &Option<String>) -> ObjectName {
+        ObjectName { id, label: label.clone() }
+    }
+}
+
+impl Display for ObjectName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.label {
+            Some(label) => write!(f, "'{}' (ID {})", label, self.id),
+            None => write!(f, "with ID {}", self.id)
+        }
+    }
+}
+
+// Pops the debug group it was created for when dropped
+pub struct DebugGroup<'a> {
+    gl: Option<&'a GlContext>
+}
+
+impl<'a> DebugGroup<'a> {
+    pub(crate) fn new(gl: &'a GlContext, message: &str) -> DebugGroup<'a> {
+        if gl.supports(GlFeature::Debug) {
+            gl.push_debug_group(gl::DEBUG_SOURCE_APPLICATION, 0, message);
+            DebugGroup { gl: Some(gl) }
+        } else {
+            DebugGroup { gl: None }
+        }
+    }
+}
+
+impl Drop for DebugGroup<'_> {
+    fn drop(&mut self) {
+        if let Some(gl) = self.gl {
+            gl.pop_debug_group();
+        }
+    }
+}
@@ -1,17 +1,33 @@
-use std::{fmt::Display, error::Error, ffi::NulError, io};
+use std::{fmt::Display, error::Error, ffi::NulError, io, path::PathBuf};
 
-use super::ShaderCompileType;
+use super::{ShaderCompileType, ShaderCompileLog, GlFeature, DebugMessage, ObjectName};
 
 #[derive(Debug)]
 pub enum GlError{
     CStringError(NulError),
-    UniformNotFound(String, u32),
-    ShaderCompileError(ShaderCompileType, u32, String),
+    UniformNotFound(String, ObjectName),
+    ShaderCompileError(ShaderCompileType, ObjectName, ShaderCompileLog),
     IoError(io::Error),
-    UniformInvalidIndex(String, u32),
-    FramebufferNotComplete(u32),
+    UniformInvalidIndex(String, ObjectName),
+    FramebufferNotComplete(ObjectName),
     UniformBufferMissing,
-    CannotResize(u32)
+    CannotResize(ObjectName),
+    ContextCreationError(String),
+    FeatureNotSupported(GlFeature),
+    LimitExceeded(&'static str, i32, i32),
+    DriverError(DebugMessage),
+    ShaderLoadError(PathBuf, String),
+    UniformFromOtherProgram(ObjectName),
+    UniformTypeMismatch(String, String, String, ObjectName),
+    InvalidShaderVariant(String, String),
+    InvalidPipeline(ObjectName, String),
+    StorageBlockNotFound(String, ObjectName),
+    BlockBindingConflict(String, u32, String),
+    BlockMemberMismatch(String, String, Option<usize>, usize, ObjectName),
+    BlockSizeMismatch(String, usize, usize, ObjectName),
+    BufferMapFailed(ObjectName),
+    FenceWaitFailed(ObjectName, String),
+    InvalidBufferSize(String)
 }
 
 impl Display for GlError {
@@ -21,16 +37,49 @@ impl Display for GlError {
             GlError::UniformNotFound(uniform, id) => 
                 write!(f, "Uniform '{}' was not found in shader {}", uniform, id),
             GlError::ShaderCompileError(type_, id, error) =>
-                write!(f, "Shader '{}' with ID {} failed to compile:\n{}", type_, id, error),
+                write!(f, "Shader '{}' {} failed to compile:\n{}", type_, id, error),
             GlError::IoError(io_error) => write!(f, "{}", io_error),
             GlError::UniformInvalidIndex(ub_name, id) => {
                 write!(f, "Uniform block '{}' was not found in shader {}", ub_name, id)
             },
             GlError::FramebufferNotComplete(id) => {
-                write!(f, "Framebuffer '{}' is not complete", id)
+                write!(f, "Framebuffer {} is not complete", id)
             },
             GlError::UniformBufferMissing => write!(f, "Uniform buffer is not present"),
-            GlError::CannotResize(id) => write!(f, "Cannot resize texture '{}'", id)
+            GlError::CannotResize(id) => write!(f, "Cannot resize texture {}", id),
+            GlError::ContextCreationError(error) => write!(f, "Could not create GL context: {}", error),
+            GlError::FeatureNotSupported(feature) => write!(f, "Not supported by this context: {}", feature),
+            GlError::LimitExceeded(limit, requested, max) =>
+                write!(f, "{} exceeded: {} were requested but the maximum is {}", limit, requested, max),
+            GlError::DriverError(message) => write!(f, "Driver reported an error: {}", message),
+            GlError::ShaderLoadError(path, error) =>
+                write!(f, "Could not load shader '{}': {}", path.display(), error),
+            GlError::UniformFromOtherProgram(id) =>
+                write!(f, "Uniform handle was not created by shader {}", id),
+            GlError::UniformTypeMismatch(uniform, glsl_type, rust_type, id) =>
+                write!(f, "Uniform '{}' in shader {} is {}, it can't be set from {}", uniform, id, glsl_type, rust_type),
+            GlError::InvalidShaderVariant(variant, error) => write!(f, "Invalid shader variant '{}': {}", variant, error),
+            GlError::InvalidPipeline(id, error) => write!(f, "Program pipeline {} is invalid: {}", id, error),
+            GlError::StorageBlockNotFound(block_name, id) =>
+                write!(f, "Storage block '{}' was not found in shader {}", block_name, id),
+            GlError::BlockBindingConflict(block_name, binding, reason) =>
+                write!(f, "Block '{}' can't use binding {}: {}", block_name, binding, reason),
+            GlError::BlockMemberMismatch(block_name, member, Some(expected), actual, id) => write!(
+                f, "Uniform block '{}' in shader {} doesn't match the Rust layout: '{}' is at offset {} in the shader but {} in Rust",
+                block_name, id, member, actual, expected
+            ),
+            GlError::BlockMemberMismatch(block_name, member, None, actual, id) => write!(
+                f, "Uniform block '{}' in shader {} doesn't match the Rust layout: '{}' at offset {} in the shader has no Rust field",
+                block_name, id, member, actual
+            ),
+            GlError::BlockSizeMismatch(block_name, expected, actual, id) => write!(
+                f, "Uniform block '{}' in shader {} doesn't match the Rust layout: it is {} bytes in the shader but {} in Rust",
+                block_name, id, actual, expected
+            ),
+            GlError::BufferMapFailed(id) => write!(f, "Could not map buffer {}", id),
+            GlError::FenceWaitFailed(id, reason) =>
+                write!(f, "Waiting for the GPU to finish with buffer {} failed: {}", id, reason),
+            GlError::InvalidBufferSize(reason) => write!(f, "Invalid buffer size: {}", reason)
         }
     }
 }
@@ -2,7 +2,7 @@ use std::rc::Rc;
 use cgmath::{Matrix4, vec3};
 use crate::model_utils::create_quad;
 
-use super::{GlError, Texture, RenderBuffer, MultiBindModel, ModelTrait, ShaderProgram, gl};
+use super::{GlError, Texture, RenderBuffer, MultiBindModel, ModelTrait, ShaderProgram, GlContext, ObjectName, gl};
 
 pub struct Framebuffer {
     id: u32,
@@ -11,34 +11,45 @@ pub struct Framebuffer {
     quad: MultiBindModel, // Uses this since each FB has a separate 1 mesh quad
     width: i32,
     height: i32,
-    pub render_buffer: Option<RenderBuffer>
+    pub render_buffer: Option<RenderBuffer>,
+    label: Option<String>,
+    gl: GlContext
 }
 
 impl Framebuffer {
     pub fn new(
+        gl: &GlContext,
         width: i32,
         height: i32,
         tex_num: usize,
         has_rb: bool
     ) -> Result<Framebuffer, GlError> {
-        let mut framebuffer = Framebuffer::new_default(width, height);
+        let span = tracing::info_span!(
+            "create_framebuffer",
+            width,
+            height,
+            textures = tex_num,
+            render_buffer = has_rb,
+            framebuffer_id = tracing::field::Empty
+        ).entered();
 
-        unsafe {
-            gl::CreateFramebuffers(1, &mut framebuffer.id);
-        }
+        let mut framebuffer = Framebuffer::new_default(gl, width, height);
+
+        framebuffer.id = framebuffer.gl.create_framebuffer();
+        span.record("framebuffer_id", framebuffer.id);
         
         // Set up renderbuffer, all these assume framebuffer is bound
-        framebuffer.gen_textures(tex_num);
+        framebuffer.gen_textures(tex_num)?;
         if has_rb { framebuffer.gen_render_buffer() };
         framebuffer.check_status()?;
 
         Ok(framebuffer)
     }
 
-    pub fn new_default(width: i32, height: i32) -> Framebuffer {
+    pub fn new_default(gl: &GlContext, width: i32, height: i32) -> Framebuffer {
         // Create quad model for framebuffer
         let model_transforms = vec![Matrix4::<f32>::from_translation(vec3(0.0, 0.0, 0.0))];
-        let quad = create_quad(model_transforms);
+        let quad = create_quad(gl, model_transforms);
 
         Framebuffer {
             id: 0,
@@ -48,57 +59,57 @@ impl Framebuffer {
             width,
             height,
             render_buffer: None,
+            label: None,
+            gl: gl.clone()
         }
     }
 
-    pub fn gen_textures(&mut self, n: usize) {
-        unsafe {
-            for i in 0..n {
-                let texture = Texture::new_mut(self.width, self.height);
-                let attachment = gl::COLOR_ATTACHMENT0 + i as u32;
-
-                // Bind to framebuffer
-                gl::NamedFramebufferTexture(
-                    self.id,
-                    attachment,
-                    texture.get_id(),
-                    0
-                );
-                
-                self.textures.push(Rc::new(texture));
-                self.draw_buffers.push(attachment);
-            }
+    pub fn gen_textures(&mut self, n: usize) -> Result<(), GlError> {
+        self.gl.check_limit(
+            "GL_MAX_COLOR_ATTACHMENTS",
+            (self.textures.len() + n) as i32,
+            self.gl.get_limits().max_color_attachments
+        )?;
+
+        for i in 0..n {
+            let texture = Texture::new_mut(&self.gl, self.width, self.height);
+            let attachment = gl::COLOR_ATTACHMENT0 + i as u32;
 
-            gl::NamedFramebufferDrawBuffers(
+            // Bind to framebuffer
+            self.gl.named_framebuffer_texture(
                 self.id,
-                self.draw_buffers.len() as i32,
-                self.draw_buffers.as_ptr()
+                attachment,
+                texture.get_id(),
+                0
             );
+            
+            self.textures.push(Rc::new(texture));
+            self.draw_buffers.push(attachment);
         }
+
+        self.gl.named_framebuffer_draw_buffers(self.id, &self.draw_buffers);
+
+        Ok(())
     }
 
     pub fn gen_render_buffer(&mut self) {
-        let render_buffer = RenderBuffer::new(self.width, self.height);
+        let render_buffer = RenderBuffer::new(&self.gl, self.width, self.height);
 
-        unsafe {
-            gl::NamedFramebufferRenderbuffer(
-                self.get_id(),
-                gl::DEPTH_STENCIL_ATTACHMENT,
-                gl::RENDERBUFFER,
-                render_buffer.get_id()
-            );
-        }
+        self.gl.named_framebuffer_renderbuffer(
+            self.get_id(),
+            gl::DEPTH_STENCIL_ATTACHMENT,
+            gl::RENDERBUFFER,
+            render_buffer.get_id()
+        );
 
         self.render_buffer = Some(render_buffer);
     }
 
     pub fn check_status(&self) -> Result<(), GlError> {
-        unsafe {
-            if gl::CheckNamedFramebufferStatus(self.id, gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE {
-                Ok(())
-            } else {
-                Err(GlError::FramebufferNotComplete(self.id))
-            }
+        if self.gl.check_named_framebuffer_status(self.id, gl::FRAMEBUFFER) == gl::FRAMEBUFFER_COMPLETE {
+            Ok(())
+        } else {
+            Err(GlError::FramebufferNotComplete(ObjectName::new(self.id, &self.label)))
         }
     }
 
@@ -106,7 +117,7 @@ impl Framebuffer {
         let mut result = Vec::new();
 
         for texture in self.textures.iter() {
-            result.push(Rc::clone(&texture));
+            result.push(Rc::clone(texture));
         }
 
         result
@@ -146,16 +157,16 @@ impl Framebuffer {
         self.textures.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+
     pub fn bind(&self) {
-        unsafe {
-            gl::BindFramebuffer(gl::FRAMEBUFFER, self.id);
-        }
+        self.gl.bind_framebuffer(gl::FRAMEBUFFER, self.id);
     }
 
-    pub fn unbind() {
-        unsafe {
-            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
-        }
+    pub fn unbind(gl: &GlContext) {
+        gl.bind_framebuffer(gl::FRAMEBUFFER, 0);
     }
 
     // Requires shader program bind
@@ -168,7 +179,7 @@ impl Framebuffer {
     }
 
     pub fn get_size(&self) -> (i32, i32) {
-        return (self.width, self.height);
+        (self.width, self.height)
     }
 
     pub fn set_size(&mut self, width: i32, height: i32) -> Result<(), GlError> {
@@ -191,12 +202,77 @@ impl Framebuffer {
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    // Also labels the attachments and quad after the framebuffer
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::FRAMEBUFFER, self.id, label);
+
+        for (i, texture) in self.textures.iter().enumerate() {
+            texture.set_label(&format!("{} color {}", label, i));
+        }
+
+        if let Some(render_buffer) = &mut self.render_buffer {
+            render_buffer.set_label(&format!("{} depth stencil", label));
+        }
+
+        self.quad.set_label(&format!("{} quad", label));
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
 }
 
 impl Drop for Framebuffer {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteFramebuffers(1, &self.id);
+        self.gl.delete_framebuffer(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GlError, gl, mock_backend::testing::{get_args, mock_context}};
+    use super::Framebuffer;
+
+    #[test]
+    fn framebuffer_attaches_textures() {
+        let (mock, gl) = mock_context(|_| ());
+
+        let framebuffer = Framebuffer::new(&gl, 800, 600, 2, true).unwrap();
+        let id = framebuffer.get_id() as i64;
+        let textures = framebuffer.get_link();
+
+        let calls = mock.take_calls();
+        assert_eq!(
+            get_args(&calls, "NamedFramebufferTexture"),
+            [
+                [id, gl::COLOR_ATTACHMENT0 as i64, textures[0].get_id() as i64, 0],
+                [id, gl::COLOR_ATTACHMENT1 as i64, textures[1].get_id() as i64, 0]
+            ]
+        );
+        assert_eq!(
+            get_args(&calls, "NamedFramebufferDrawBuffers"),
+            [[id, gl::COLOR_ATTACHMENT0 as i64, gl::COLOR_ATTACHMENT1 as i64]]
+        );
+        assert_eq!(get_args(&calls, "NamedRenderbufferStorage")[0][2..], [800, 600]);
+        assert_eq!(get_args(&calls, "CheckNamedFramebufferStatus"), [[id, gl::FRAMEBUFFER as i64]]);
+        assert_eq!(calls.last().unwrap().name, "CheckNamedFramebufferStatus");
+    }
+
+    #[test]
+    fn framebuffer_incomplete_is_error() {
+        let (mock, gl) = mock_context(|_| ());
+        mock.set_framebuffer_status(gl::FRAMEBUFFER_INCOMPLETE_ATTACHMENT);
+
+        let result = Framebuffer::new(&gl, 800, 600, 1, false);
+        let id = mock.find("CreateFramebuffers").unwrap().args[0] as u32;
+
+        match result {
+            Err(GlError::FramebufferNotComplete(name)) => assert_eq!(name.id, id),
+            _ => panic!("expected FramebufferNotComplete")
         }
+        // Nothing is left behind
+        assert_eq!(mock.find("DeleteFramebuffers").unwrap().args, [id as i64]);
     }
-}
\ No newline at end of file
+}
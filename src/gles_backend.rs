@@ -0,0 +1,808 @@
+use std::{cell::{Cell, RefCell}, collections::HashMap, ffi::{CStr, c_void}, ptr};
+use super::{GlBackend, GlApi, gles, gl::{self, types::{GLenum, GLvoid, GLbitfield, GLsync}}};
+use super::backend::{info_log, resource_name, to_string};
+
+// Texture targets that can be bound, used when a target has to be guessed
+const TEXTURE_TARGETS: [GLenum; 4] = [gles::TEXTURE_2D, gles::TEXTURE_CUBE_MAP, gles::TEXTURE_2D_ARRAY, gles::TEXTURE_3D];
+
+// Backend for OpenGL ES 3.2, which has no DSA.
+// The named methods bind the object, make the classic call and then rebind whatever was bound before,
+// so they have no side effects on bindings, same as the DSA functions they stand in for.
+// The context they were loaded for has to be current whenever this is used
+pub struct GlesGl {
+    gl: gles::Gles2,
+    // Edits need the target a texture was created with to bind it
+    texture_targets: RefCell<HashMap<u32, GLenum>>,
+    // bind_texture_unit() switches units to bind, then switches back to this one
+    active_texture: Cell<GLenum>
+}
+
+impl GlesGl {
+    pub fn load_with<F: FnMut(&'static str) -> *const c_void>(loader: F) -> GlesGl {
+        GlesGl {
+            gl: gles::Gles2::load_with(loader),
+            texture_targets: RefCell::new(HashMap::new()),
+            active_texture: Cell::new(gles::TEXTURE0)
+        }
+    }
+
+    fn edit<R, B: Fn(u32), F: FnOnce() -> R>(&self, binding: GLenum, bind: B, object: u32, f: F) -> R {
+        let previous = self.get_integer_v(binding) as u32;
+
+        if previous != object { bind(object); }
+        let result = f();
+        if previous != object { bind(previous); }
+
+        result
+    }
+
+    // Buffers are edited through GL_COPY_WRITE_BUFFER, which nothing else in the crate binds
+    fn edit_buffer<R, F: FnOnce() -> R>(&self, buffer: u32, f: F) -> R {
+        self.edit(
+            gles::COPY_WRITE_BUFFER_BINDING,
+            |id| unsafe { self.gl.BindBuffer(gles::COPY_WRITE_BUFFER, id) },
+            buffer,
+            f
+        )
+    }
+
+    fn edit_vertex_array<R, F: FnOnce() -> R>(&self, array: u32, f: F) -> R {
+        self.edit(gles::VERTEX_ARRAY_BINDING, |id| unsafe { self.gl.BindVertexArray(id) }, array, f)
+    }
+
+    // Binds on the active unit
+    fn edit_texture<R, F: FnOnce(GLenum) -> R>(&self, texture: u32, f: F) -> R {
+        let target = self.get_texture_target(texture);
+        let binding = match target {
+            gles::TEXTURE_CUBE_MAP => gles::TEXTURE_BINDING_CUBE_MAP,
+            gles::TEXTURE_2D_ARRAY => gles::TEXTURE_BINDING_2D_ARRAY,
+            gles::TEXTURE_3D => gles::TEXTURE_BINDING_3D,
+            _ => gles::TEXTURE_BINDING_2D
+        };
+
+        self.edit(binding, |id| unsafe { self.gl.BindTexture(target, id) }, texture, || f(target))
+    }
+
+    // Only the draw binding changes, which is all the framebuffer functions need
+    fn edit_framebuffer<R, F: FnOnce() -> R>(&self, framebuffer: u32, f: F) -> R {
+        self.edit(
+            gles::DRAW_FRAMEBUFFER_BINDING,
+            |id| unsafe { self.gl.BindFramebuffer(gles::DRAW_FRAMEBUFFER, id) },
+            framebuffer,
+            f
+        )
+    }
+
+    fn edit_renderbuffer<R, F: FnOnce() -> R>(&self, renderbuffer: u32, f: F) -> R {
+        self.edit(
+            gles::RENDERBUFFER_BINDING,
+            |id| unsafe { self.gl.BindRenderbuffer(gles::RENDERBUFFER, id) },
+            renderbuffer,
+            f
+        )
+    }
+
+    // Textures from outside the crate are assumed to be 2D
+    fn get_texture_target(&self, texture: u32) -> GLenum {
+        self.texture_targets.borrow().get(&texture).copied().unwrap_or(gles::TEXTURE_2D)
+    }
+}
+
+impl GlBackend for GlesGl {
+    fn get_api(&self) -> GlApi {
+        GlApi::Gles
+    }
+
+    fn emulates_dsa(&self) -> bool {
+        true
+    }
+
+    // Names from glGen* only become objects once bound, labels and edits need the object to exist
+    fn create_buffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.GenBuffers(1, &mut id) };
+        self.edit_buffer(id, || ());
+        id
+    }
+
+    fn delete_buffer(&self, buffer: u32) {
+        unsafe { self.gl.DeleteBuffers(1, &buffer) }
+    }
+
+    // There is no immutable storage without GL_EXT_buffer_storage, so then this is a regular allocation
+    unsafe fn named_buffer_storage(&self, buffer: u32, size: isize, data: *const GLvoid, flags: GLbitfield) {
+        self.edit_buffer(buffer, || {
+            if self.gl.BufferStorageEXT.is_loaded() {
+                self.gl.BufferStorageEXT(gles::COPY_WRITE_BUFFER, size, data, flags)
+            } else {
+                let usage = if flags & gl::DYNAMIC_STORAGE_BIT != 0 { gles::DYNAMIC_DRAW } else { gles::STATIC_DRAW };
+                self.gl.BufferData(gles::COPY_WRITE_BUFFER, size, data, usage)
+            }
+        })
+    }
+
+    unsafe fn named_buffer_data(&self, buffer: u32, size: isize, data: *const GLvoid, usage: GLenum) {
+        self.edit_buffer(buffer, || self.gl.BufferData(gles::COPY_WRITE_BUFFER, size, data, usage))
+    }
+
+    unsafe fn named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, data: *const GLvoid) {
+        self.edit_buffer(buffer, || self.gl.BufferSubData(gles::COPY_WRITE_BUFFER, offset, size, data))
+    }
+
+    // GLES can only read buffers by mapping them
+    unsafe fn get_named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, data: *mut GLvoid) {
+        self.edit_buffer(buffer, || {
+            let mapped = self.gl.MapBufferRange(gles::COPY_WRITE_BUFFER, offset, size, gles::MAP_READ_BIT);
+            if !mapped.is_null() {
+                ptr::copy_nonoverlapping(mapped as *const u8, data as *mut u8, size as usize);
+                self.gl.UnmapBuffer(gles::COPY_WRITE_BUFFER);
+            }
+        })
+    }
+
+    fn bind_buffer(&self, target: GLenum, buffer: u32) {
+        unsafe { self.gl.BindBuffer(target, buffer) }
+    }
+
+    fn bind_buffer_range(&self, target: GLenum, index: u32, buffer: u32, offset: isize, size: isize) {
+        unsafe { self.gl.BindBufferRange(target, index, buffer, offset, size) }
+    }
+
+    unsafe fn map_named_buffer_range(&self, buffer: u32, offset: isize, length: isize, access: GLbitfield) -> *mut GLvoid {
+        self.edit_buffer(buffer, || self.gl.MapBufferRange(gles::COPY_WRITE_BUFFER, offset, length, access))
+    }
+
+    fn unmap_named_buffer(&self, buffer: u32) -> bool {
+        self.edit_buffer(buffer, || unsafe { self.gl.UnmapBuffer(gles::COPY_WRITE_BUFFER) == gles::TRUE })
+    }
+
+    fn fence_sync(&self, condition: GLenum, flags: GLbitfield) -> GLsync {
+        unsafe { self.gl.FenceSync(condition, flags) as GLsync }
+    }
+
+    unsafe fn client_wait_sync(&self, sync: GLsync, flags: GLbitfield, timeout: u64) -> GLenum {
+        self.gl.ClientWaitSync(sync as gles::types::GLsync, flags, timeout)
+    }
+
+    unsafe fn delete_sync(&self, sync: GLsync) {
+        self.gl.DeleteSync(sync as gles::types::GLsync)
+    }
+
+    fn create_vertex_array(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.GenVertexArrays(1, &mut id) };
+        self.edit_vertex_array(id, || ());
+        id
+    }
+
+    fn delete_vertex_array(&self, array: u32) {
+        unsafe { self.gl.DeleteVertexArrays(1, &array) }
+    }
+
+    fn vertex_array_vertex_buffer(&self, array: u32, binding_index: u32, buffer: u32, offset: isize, stride: i32) {
+        self.edit_vertex_array(array, || unsafe { self.gl.BindVertexBuffer(binding_index, buffer, offset, stride) })
+    }
+
+    fn vertex_array_element_buffer(&self, array: u32, buffer: u32) {
+        self.edit_vertex_array(array, || unsafe { self.gl.BindBuffer(gles::ELEMENT_ARRAY_BUFFER, buffer) })
+    }
+
+    fn enable_vertex_array_attrib(&self, array: u32, attrib_index: u32) {
+        self.edit_vertex_array(array, || unsafe { self.gl.EnableVertexAttribArray(attrib_index) })
+    }
+
+    fn vertex_array_attrib_format(
+        &self,
+        array: u32,
+        attrib_index: u32,
+        size: i32,
+        type_: GLenum,
+        normalized: bool,
+        relative_offset: u32
+    ) {
+        let normalized = if normalized { gles::TRUE } else { gles::FALSE };
+
+        self.edit_vertex_array(array, || unsafe {
+            self.gl.VertexAttribFormat(attrib_index, size, type_, normalized, relative_offset)
+        })
+    }
+
+    fn vertex_array_attrib_binding(&self, array: u32, attrib_index: u32, binding_index: u32) {
+        self.edit_vertex_array(array, || unsafe { self.gl.VertexAttribBinding(attrib_index, binding_index) })
+    }
+
+    fn vertex_array_binding_divisor(&self, array: u32, binding_index: u32, divisor: u32) {
+        self.edit_vertex_array(array, || unsafe { self.gl.VertexBindingDivisor(binding_index, divisor) })
+    }
+
+    fn bind_vertex_array(&self, array: u32) {
+        unsafe { self.gl.BindVertexArray(array) }
+    }
+
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: GLenum,
+        count: i32,
+        type_: GLenum,
+        indices: *const GLvoid,
+        instance_count: i32
+    ) {
+        self.gl.DrawElementsInstanced(mode, count, type_, indices, instance_count)
+    }
+
+    // Needs GL_EXT_multi_draw_indirect, which GlContext checks for
+    unsafe fn multi_draw_elements_indirect(
+        &self,
+        mode: GLenum,
+        type_: GLenum,
+        indirect: *const GLvoid,
+        draw_count: i32,
+        stride: i32
+    ) {
+        self.gl.MultiDrawElementsIndirectEXT(mode, type_, indirect, draw_count, stride)
+    }
+
+    fn patch_parameter_i(&self, pname: GLenum, value: i32) {
+        unsafe { self.gl.PatchParameteri(pname, value) }
+    }
+
+    fn create_shader(&self, type_: GLenum) -> u32 {
+        unsafe { self.gl.CreateShader(type_) }
+    }
+
+    fn delete_shader(&self, shader: u32) {
+        unsafe { self.gl.DeleteShader(shader) }
+    }
+
+    fn shader_source(&self, shader: u32, source: &CStr) {
+        unsafe { self.gl.ShaderSource(shader, 1, &source.as_ptr(), ptr::null()) }
+    }
+
+    fn compile_shader(&self, shader: u32) {
+        unsafe { self.gl.CompileShader(shader) }
+    }
+
+    fn get_shader_iv(&self, shader: u32, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetShaderiv(shader, pname, &mut value) };
+        value
+    }
+
+    fn get_shader_info_log(&self, shader: u32) -> String {
+        unsafe {
+            info_log(
+                shader,
+                |id, pname, value| self.gl.GetShaderiv(id, pname, value),
+                |id, len, written, log| self.gl.GetShaderInfoLog(id, len, written, log)
+            )
+        }
+    }
+
+    fn shader_binary(&self, shader: u32, format: GLenum, binary: &[u8]) {
+        unsafe { self.gl.ShaderBinary(1, &shader, format, binary.as_ptr() as *const c_void, binary.len() as i32) }
+    }
+
+    // There is no SPIR-V on GLES, GlContext refuses to get this far
+    fn specialize_shader(&self, _shader: u32, _entry_point: &CStr, _constant_indices: &[u32], _constant_values: &[u32]) {}
+
+    fn create_program(&self) -> u32 {
+        unsafe { self.gl.CreateProgram() }
+    }
+
+    fn delete_program(&self, program: u32) {
+        unsafe { self.gl.DeleteProgram(program) }
+    }
+
+    fn attach_shader(&self, program: u32, shader: u32) {
+        unsafe { self.gl.AttachShader(program, shader) }
+    }
+
+    fn link_program(&self, program: u32) {
+        unsafe { self.gl.LinkProgram(program) }
+    }
+
+    fn get_program_iv(&self, program: u32, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetProgramiv(program, pname, &mut value) };
+        value
+    }
+
+    fn get_program_info_log(&self, program: u32) -> String {
+        unsafe {
+            info_log(
+                program,
+                |id, pname, value| self.gl.GetProgramiv(id, pname, value),
+                |id, len, written, log| self.gl.GetProgramInfoLog(id, len, written, log)
+            )
+        }
+    }
+
+    fn program_parameter_i(&self, program: u32, pname: GLenum, value: i32) {
+        unsafe { self.gl.ProgramParameteri(program, pname, value) }
+    }
+
+    fn get_program_binary(&self, program: u32) -> (GLenum, Vec<u8>) {
+        let length = self.get_program_iv(program, gl::PROGRAM_BINARY_LENGTH);
+        let mut binary = vec![0u8; length.max(0) as usize];
+        let mut written = 0;
+        let mut format = 0;
+
+        unsafe {
+            self.gl.GetProgramBinary(program, length, &mut written, &mut format, binary.as_mut_ptr() as *mut c_void)
+        };
+        binary.truncate(written.max(0) as usize);
+
+        (format, binary)
+    }
+
+    fn program_binary(&self, program: u32, format: GLenum, binary: &[u8]) {
+        unsafe { self.gl.ProgramBinary(program, format, binary.as_ptr() as *const c_void, binary.len() as i32) }
+    }
+
+    // Binding creates the object, so it can be labelled right away
+    fn create_program_pipeline(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.GenProgramPipelines(1, &mut id) };
+        self.edit(gles::PROGRAM_PIPELINE_BINDING, |id| unsafe { self.gl.BindProgramPipeline(id) }, id, || ());
+        id
+    }
+
+    fn delete_program_pipeline(&self, pipeline: u32) {
+        unsafe { self.gl.DeleteProgramPipelines(1, &pipeline) }
+    }
+
+    fn bind_program_pipeline(&self, pipeline: u32) {
+        unsafe { self.gl.BindProgramPipeline(pipeline) }
+    }
+
+    fn use_program_stages(&self, pipeline: u32, stages: GLbitfield, program: u32) {
+        unsafe { self.gl.UseProgramStages(pipeline, stages, program) }
+    }
+
+    fn active_shader_program(&self, pipeline: u32, program: u32) {
+        unsafe { self.gl.ActiveShaderProgram(pipeline, program) }
+    }
+
+    fn validate_program_pipeline(&self, pipeline: u32) {
+        unsafe { self.gl.ValidateProgramPipeline(pipeline) }
+    }
+
+    fn get_program_pipeline_iv(&self, pipeline: u32, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetProgramPipelineiv(pipeline, pname, &mut value) };
+        value
+    }
+
+    fn get_program_pipeline_info_log(&self, pipeline: u32) -> String {
+        unsafe {
+            info_log(
+                pipeline,
+                |id, pname, value| self.gl.GetProgramPipelineiv(id, pname, value),
+                |id, len, written, log| self.gl.GetProgramPipelineInfoLog(id, len, written, log)
+            )
+        }
+    }
+
+    fn use_program(&self, program: u32) {
+        unsafe { self.gl.UseProgram(program) }
+    }
+
+    fn get_uniform_location(&self, program: u32, name: &CStr) -> i32 {
+        unsafe { self.gl.GetUniformLocation(program, name.as_ptr()) }
+    }
+
+    fn uniform_1i(&self, location: i32, v0: i32) {
+        unsafe { self.gl.Uniform1i(location, v0) }
+    }
+
+    fn uniform_1f(&self, location: i32, v0: f32) {
+        unsafe { self.gl.Uniform1f(location, v0) }
+    }
+
+    fn uniform_3f(&self, location: i32, v0: f32, v1: f32, v2: f32) {
+        unsafe { self.gl.Uniform3f(location, v0, v1, v2) }
+    }
+
+    fn uniform_4f(&self, location: i32, v0: f32, v1: f32, v2: f32, v3: f32) {
+        unsafe { self.gl.Uniform4f(location, v0, v1, v2, v3) }
+    }
+
+    fn uniform_3fv(&self, location: i32, value: &[f32]) {
+        unsafe { self.gl.Uniform3fv(location, (value.len() / 3) as i32, value.as_ptr()) }
+    }
+
+    fn uniform_4fv(&self, location: i32, value: &[f32]) {
+        unsafe { self.gl.Uniform4fv(location, (value.len() / 4) as i32, value.as_ptr()) }
+    }
+
+    fn uniform_matrix_4fv(&self, location: i32, transpose: bool, value: &[f32]) {
+        let transpose = if transpose { gles::TRUE } else { gles::FALSE };
+        unsafe { self.gl.UniformMatrix4fv(location, (value.len() / 16) as i32, transpose, value.as_ptr()) }
+    }
+
+    fn uniform_iv(&self, location: i32, components: i32, value: &[i32]) {
+        let count = value.len() as i32 / components;
+        unsafe {
+            match components {
+                1 => self.gl.Uniform1iv(location, count, value.as_ptr()),
+                2 => self.gl.Uniform2iv(location, count, value.as_ptr()),
+                3 => self.gl.Uniform3iv(location, count, value.as_ptr()),
+                _ => self.gl.Uniform4iv(location, count, value.as_ptr())
+            }
+        }
+    }
+
+    fn uniform_uiv(&self, location: i32, components: i32, value: &[u32]) {
+        let count = value.len() as i32 / components;
+        unsafe {
+            match components {
+                1 => self.gl.Uniform1uiv(location, count, value.as_ptr()),
+                2 => self.gl.Uniform2uiv(location, count, value.as_ptr()),
+                3 => self.gl.Uniform3uiv(location, count, value.as_ptr()),
+                _ => self.gl.Uniform4uiv(location, count, value.as_ptr())
+            }
+        }
+    }
+
+    fn uniform_fv(&self, location: i32, components: i32, value: &[f32]) {
+        let count = value.len() as i32 / components;
+        unsafe {
+            match components {
+                1 => self.gl.Uniform1fv(location, count, value.as_ptr()),
+                2 => self.gl.Uniform2fv(location, count, value.as_ptr()),
+                3 => self.gl.Uniform3fv(location, count, value.as_ptr()),
+                _ => self.gl.Uniform4fv(location, count, value.as_ptr())
+            }
+        }
+    }
+
+    // GLSL ES has no doubles. ShaderProgram::set() and set_unsafe() return an error before getting here,
+    // raw calls through the context only log
+    fn uniform_dv(&self, _location: i32, _components: i32, _value: &[f64]) {
+        tracing::error!("double uniforms are not available on GLES");
+    }
+
+    fn uniform_matrix_fv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f32]) {
+        let count = value.len() as i32 / (columns * rows);
+        let transpose = if transpose { gles::TRUE } else { gles::FALSE };
+        unsafe {
+            match (columns, rows) {
+                (2, 2) => self.gl.UniformMatrix2fv(location, count, transpose, value.as_ptr()),
+                (2, 3) => self.gl.UniformMatrix2x3fv(location, count, transpose, value.as_ptr()),
+                (2, 4) => self.gl.UniformMatrix2x4fv(location, count, transpose, value.as_ptr()),
+                (3, 2) => self.gl.UniformMatrix3x2fv(location, count, transpose, value.as_ptr()),
+                (3, 3) => self.gl.UniformMatrix3fv(location, count, transpose, value.as_ptr()),
+                (3, 4) => self.gl.UniformMatrix3x4fv(location, count, transpose, value.as_ptr()),
+                (4, 2) => self.gl.UniformMatrix4x2fv(location, count, transpose, value.as_ptr()),
+                (4, 3) => self.gl.UniformMatrix4x3fv(location, count, transpose, value.as_ptr()),
+                _ => self.gl.UniformMatrix4fv(location, count, transpose, value.as_ptr())
+            }
+        }
+    }
+
+    fn uniform_matrix_dv(&self, _location: i32, _columns: i32, _rows: i32, _transpose: bool, _value: &[f64]) {
+        tracing::error!("double uniforms are not available on GLES");
+    }
+
+    fn get_uniform_block_index(&self, program: u32, name: &CStr) -> u32 {
+        unsafe { self.gl.GetUniformBlockIndex(program, name.as_ptr()) }
+    }
+
+    fn uniform_block_binding(&self, program: u32, block_index: u32, binding: u32) {
+        unsafe { self.gl.UniformBlockBinding(program, block_index, binding) }
+    }
+
+    // GLES has no glShaderStorageBlockBinding, storage blocks keep the binding from the shader.
+    // ShaderProgram::bind_to_ssbo() reads that binding instead of calling this
+    fn shader_storage_block_binding(&self, _program: u32, _block_index: u32, _binding: u32) {
+        tracing::warn!("glShaderStorageBlockBinding is not available on GLES");
+    }
+
+    fn get_program_resource_index(&self, program: u32, interface: GLenum, name: &CStr) -> u32 {
+        unsafe { self.gl.GetProgramResourceIndex(program, interface, name.as_ptr()) }
+    }
+
+    fn get_program_interface_iv(&self, program: u32, interface: GLenum, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetProgramInterfaceiv(program, interface, pname, &mut value) };
+        value
+    }
+
+    fn get_program_resource_name(&self, program: u32, interface: GLenum, index: u32) -> String {
+        let length = self.get_program_resource_iv(program, interface, index, &[gl::NAME_LENGTH])[0];
+
+        unsafe {
+            resource_name(length, |buffer_size, written, name| {
+                self.gl.GetProgramResourceName(program, interface, index, buffer_size, written, name)
+            })
+        }
+    }
+
+    fn get_program_resource_iv(&self, program: u32, interface: GLenum, index: u32, props: &[GLenum]) -> Vec<i32> {
+        let mut values = vec![0; props.len()];
+
+        unsafe {
+            self.gl.GetProgramResourceiv(
+                program,
+                interface,
+                index,
+                props.len() as i32,
+                props.as_ptr(),
+                values.len() as i32,
+                ptr::null_mut(),
+                values.as_mut_ptr()
+            )
+        };
+
+        values
+    }
+
+    fn dispatch_compute(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32) {
+        unsafe { self.gl.DispatchCompute(num_groups_x, num_groups_y, num_groups_z) }
+    }
+
+    fn dispatch_compute_indirect(&self, indirect: isize) {
+        unsafe { self.gl.DispatchComputeIndirect(indirect) }
+    }
+
+    fn memory_barrier(&self, barriers: GLbitfield) {
+        unsafe { self.gl.MemoryBarrier(barriers) }
+    }
+
+    fn bind_image_texture(
+        &self,
+        unit: u32,
+        texture: u32,
+        level: i32,
+        layered: bool,
+        layer: i32,
+        access: GLenum,
+        format: GLenum
+    ) {
+        let layered = if layered { gles::TRUE } else { gles::FALSE };
+        unsafe { self.gl.BindImageTexture(unit, texture, level, layered, layer, access, format) }
+    }
+
+    fn create_texture(&self, target: GLenum) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.GenTextures(1, &mut id) };
+        self.texture_targets.borrow_mut().insert(id, target);
+        self.edit_texture(id, |_| ());
+        id
+    }
+
+    fn delete_texture(&self, texture: u32) {
+        self.texture_targets.borrow_mut().remove(&texture);
+        unsafe { self.gl.DeleteTextures(1, &texture) }
+    }
+
+    fn texture_storage_2d(&self, texture: u32, levels: i32, internal_format: GLenum, width: i32, height: i32) {
+        self.edit_texture(texture, |target| unsafe {
+            self.gl.TexStorage2D(target, levels, internal_format, width, height)
+        })
+    }
+
+    unsafe fn texture_sub_image_2d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.edit_texture(texture, |target| {
+            self.gl.TexSubImage2D(target, level, x_offset, y_offset, width, height, format, type_, pixels)
+        })
+    }
+
+    // Cube map faces are layers here like with DSA, but have to be uploaded one face (depth 1) at a time
+    unsafe fn texture_sub_image_3d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.edit_texture(texture, |target| {
+            if target == gles::TEXTURE_CUBE_MAP {
+                let face = gles::TEXTURE_CUBE_MAP_POSITIVE_X + z_offset as u32;
+                self.gl.TexSubImage2D(face, level, x_offset, y_offset, width, height, format, type_, pixels)
+            } else {
+                self.gl.TexSubImage3D(
+                    target, level, x_offset, y_offset, z_offset, width, height, depth, format, type_, pixels
+                )
+            }
+        })
+    }
+
+    unsafe fn tex_image_2d(
+        &self,
+        target: GLenum,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: GLenum,
+        type_: GLenum,
+        pixels: *const GLvoid
+    ) {
+        self.gl.TexImage2D(target, level, internal_format, width, height, border, format, type_, pixels)
+    }
+
+    fn generate_texture_mipmap(&self, texture: u32) {
+        self.edit_texture(texture, |target| unsafe { self.gl.GenerateMipmap(target) })
+    }
+
+    fn texture_parameter_i(&self, texture: u32, pname: GLenum, param: i32) {
+        self.edit_texture(texture, |target| unsafe { self.gl.TexParameteri(target, pname, param) })
+    }
+
+    fn bind_texture(&self, target: GLenum, texture: u32) {
+        unsafe { self.gl.BindTexture(target, texture) }
+    }
+
+    // Binding 0 unbinds every target on the unit, like glBindTextureUnit does
+    fn bind_texture_unit(&self, unit: u32, texture: u32) {
+        let active = self.active_texture.get();
+        let unit = gles::TEXTURE0 + unit;
+
+        unsafe {
+            if unit != active { self.gl.ActiveTexture(unit); }
+
+            if texture == 0 {
+                for target in TEXTURE_TARGETS {
+                    self.gl.BindTexture(target, 0);
+                }
+            } else {
+                self.gl.BindTexture(self.get_texture_target(texture), texture);
+            }
+
+            if unit != active { self.gl.ActiveTexture(active); }
+        }
+    }
+
+    fn active_texture(&self, texture: GLenum) {
+        self.active_texture.set(texture);
+        unsafe { self.gl.ActiveTexture(texture) }
+    }
+
+    // There is no bindless on GLES, GlFeature::BindlessTexture is never supported so
+    // Texture::get_handle() returns an error before getting here. Raw calls log and get a null handle
+    fn get_texture_handle(&self, _texture: u32) -> u64 {
+        tracing::error!("bindless textures are not available on GLES");
+        0
+    }
+
+    fn make_texture_handle_resident(&self, _handle: u64) {
+        tracing::error!("bindless textures are not available on GLES");
+    }
+
+    fn make_texture_handle_non_resident(&self, _handle: u64) {
+        tracing::error!("bindless textures are not available on GLES");
+    }
+
+    fn create_framebuffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.GenFramebuffers(1, &mut id) };
+        self.edit_framebuffer(id, || ());
+        id
+    }
+
+    fn delete_framebuffer(&self, framebuffer: u32) {
+        unsafe { self.gl.DeleteFramebuffers(1, &framebuffer) }
+    }
+
+    fn named_framebuffer_texture(&self, framebuffer: u32, attachment: GLenum, texture: u32, level: i32) {
+        self.edit_framebuffer(framebuffer, || unsafe {
+            self.gl.FramebufferTexture(gles::DRAW_FRAMEBUFFER, attachment, texture, level)
+        })
+    }
+
+    fn named_framebuffer_draw_buffers(&self, framebuffer: u32, buffers: &[GLenum]) {
+        self.edit_framebuffer(framebuffer, || unsafe {
+            self.gl.DrawBuffers(buffers.len() as i32, buffers.as_ptr())
+        })
+    }
+
+    fn named_framebuffer_renderbuffer(
+        &self,
+        framebuffer: u32,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: u32
+    ) {
+        self.edit_framebuffer(framebuffer, || unsafe {
+            self.gl.FramebufferRenderbuffer(gles::DRAW_FRAMEBUFFER, attachment, renderbuffer_target, renderbuffer)
+        })
+    }
+
+    // Checked as the draw framebuffer whatever the target, both bindings take the same attachments here
+    fn check_named_framebuffer_status(&self, framebuffer: u32, _target: GLenum) -> GLenum {
+        self.edit_framebuffer(framebuffer, || unsafe { self.gl.CheckFramebufferStatus(gles::DRAW_FRAMEBUFFER) })
+    }
+
+    fn bind_framebuffer(&self, target: GLenum, framebuffer: u32) {
+        unsafe { self.gl.BindFramebuffer(target, framebuffer) }
+    }
+
+    fn create_renderbuffer(&self) -> u32 {
+        let mut id = 0;
+        unsafe { self.gl.GenRenderbuffers(1, &mut id) };
+        self.edit_renderbuffer(id, || ());
+        id
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: u32) {
+        unsafe { self.gl.DeleteRenderbuffers(1, &renderbuffer) }
+    }
+
+    fn named_renderbuffer_storage(&self, renderbuffer: u32, internal_format: GLenum, width: i32, height: i32) {
+        self.edit_renderbuffer(renderbuffer, || unsafe {
+            self.gl.RenderbufferStorage(gles::RENDERBUFFER, internal_format, width, height)
+        })
+    }
+
+    fn enable(&self, cap: GLenum) {
+        unsafe { self.gl.Enable(cap) }
+    }
+
+    fn disable(&self, cap: GLenum) {
+        unsafe { self.gl.Disable(cap) }
+    }
+
+    fn depth_func(&self, func: GLenum) {
+        unsafe { self.gl.DepthFunc(func) }
+    }
+
+    fn blend_func(&self, sfactor: GLenum, dfactor: GLenum) {
+        unsafe { self.gl.BlendFunc(sfactor, dfactor) }
+    }
+
+    fn cull_face(&self, mode: GLenum) {
+        unsafe { self.gl.CullFace(mode) }
+    }
+
+    unsafe fn debug_message_callback(&self, callback: gl::types::GLDEBUGPROC, user_param: *const GLvoid) {
+        self.gl.DebugMessageCallback(callback, user_param)
+    }
+
+    fn object_label(&self, identifier: GLenum, name: u32, label: &str) {
+        unsafe { self.gl.ObjectLabel(identifier, name, label.len() as i32, label.as_ptr() as *const gles::types::GLchar) }
+    }
+
+    fn push_debug_group(&self, source: GLenum, id: u32, message: &str) {
+        unsafe {
+            self.gl.PushDebugGroup(source, id, message.len() as i32, message.as_ptr() as *const gles::types::GLchar)
+        }
+    }
+
+    fn pop_debug_group(&self) {
+        unsafe { self.gl.PopDebugGroup() }
+    }
+
+    fn get_integer_v(&self, pname: GLenum) -> i32 {
+        let mut value = 0;
+        unsafe { self.gl.GetIntegerv(pname, &mut value) };
+        value
+    }
+
+    fn get_string(&self, name: GLenum) -> String {
+        unsafe { to_string(self.gl.GetString(name)) }
+    }
+
+    fn get_string_i(&self, name: GLenum, index: u32) -> String {
+        unsafe { to_string(self.gl.GetStringi(name, index)) }
+    }
+}
@@ -0,0 +1,154 @@
+use khronos_egl as egl;
+use super::{GlError, GlContext, GlApi};
+
+// From EGL_MESA_platform_surfaceless, not part of the core EGL constants
+const PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+// Offscreen GL 4.6 (down to 3.3) core or GLES 3.2 context with no window or surface behind it.
+// Rendering has to go to framebuffers, as there is no default framebuffer.
+// Works with Mesa's llvmpipe, so it can be used on machines without a GPU
+pub struct HeadlessContext {
+    egl: egl::DynamicInstance<egl::EGL1_5>,
+    display: egl::Display,
+    context: egl::Context,
+    gl: GlContext
+}
+
+impl HeadlessContext {
+    // Creates the context, makes it current on this thread and loads the GL functions for it.
+    // Resources created from get_context() must be dropped before this is
+    pub fn new() -> Result<HeadlessContext, GlError> {
+        HeadlessContext::with_api(GlApi::Gl)
+    }
+
+    // Same as new() with an OpenGL ES 3.2 context
+    #[cfg(feature = "gles")]
+    pub fn new_gles() -> Result<HeadlessContext, GlError> {
+        HeadlessContext::with_api(GlApi::Gles)
+    }
+
+    fn with_api(api: GlApi) -> Result<HeadlessContext, GlError> {
+        let egl = unsafe { egl::DynamicInstance::<egl::EGL1_5>::load_required() }
+            .map_err(|err| GlError::ContextCreationError(err.to_string()))?;
+
+        let extensions = egl.query_string(None, egl::EXTENSIONS)
+            .map_err(|err| GlError::ContextCreationError(err.to_string()))?;
+
+        if !extensions.to_string_lossy().split(' ').any(|ext| ext == "EGL_MESA_platform_surfaceless") {
+            return Err(GlError::ContextCreationError(
+                String::from("EGL_MESA_platform_surfaceless is not supported")
+            ));
+        }
+
+        let display = unsafe {
+            egl.get_platform_display(PLATFORM_SURFACELESS_MESA, egl::DEFAULT_DISPLAY, &[egl::ATTRIB_NONE])
+        }.map_err(|err| GlError::ContextCreationError(err.to_string()))?;
+
+        let context = HeadlessContext::create_context(&egl, display, api)
+            .map_err(|err| {
+                let _ = egl.terminate(display);
+                GlError::ContextCreationError(err.to_string())
+            })?;
+
+        let loader = |name: &str| egl.get_proc_address(name).map_or(std::ptr::null(), |func| func as *const _);
+        let gl = match api {
+            GlApi::Gl => GlContext::load_with(loader),
+            #[cfg(feature = "gles")]
+            GlApi::Gles => GlContext::load_gles_with(loader),
+            // new_gles() only exists with the feature
+            #[cfg(not(feature = "gles"))]
+            GlApi::Gles => unreachable!()
+        };
+
+        match gl {
+            Ok(gl) => Ok(HeadlessContext { egl, display, context, gl }),
+            Err(err) => {
+                let _ = egl.make_current(display, None, None, None);
+                let _ = egl.destroy_context(display, context);
+                let _ = egl.terminate(display);
+
+                Err(err)
+            }
+        }
+    }
+
+    fn create_context(
+        egl: &egl::DynamicInstance<egl::EGL1_5>,
+        display: egl::Display,
+        api: GlApi
+    ) -> Result<egl::Context, egl::Error> {
+        egl.initialize(display)?;
+
+        let (egl_api, renderable_type) = match api {
+            GlApi::Gl => (egl::OPENGL_API, egl::OPENGL_BIT),
+            GlApi::Gles => (egl::OPENGL_ES_API, egl::OPENGL_ES3_BIT)
+        };
+        egl.bind_api(egl_api)?;
+
+        let config_attribs = [
+            egl::SURFACE_TYPE, egl::PBUFFER_BIT,
+            egl::RENDERABLE_TYPE, renderable_type,
+            egl::NONE
+        ];
+        let config = egl.choose_first_config(display, &config_attribs)?
+            .ok_or(egl::Error::BadConfig)?;
+
+        let context = match api {
+            GlApi::Gl => {
+                // Older Mesa releases (llvmpipe included) top out at 4.5,
+                // 3.3 is the lowest GlContext::load_with() can run on
+                let mut result = Err(egl::Error::BadMatch);
+
+                for (major, minor) in [(4, 6), (4, 5), (3, 3)] {
+                    let context_attribs = [
+                        egl::CONTEXT_MAJOR_VERSION, major,
+                        egl::CONTEXT_MINOR_VERSION, minor,
+                        egl::CONTEXT_OPENGL_PROFILE_MASK, egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                        egl::NONE
+                    ];
+
+                    result = egl.create_context(display, config, None, &context_attribs);
+                    if result.is_ok() { break; }
+                }
+
+                result?
+            },
+            GlApi::Gles => {
+                let context_attribs = [
+                    egl::CONTEXT_MAJOR_VERSION, 3,
+                    egl::CONTEXT_MINOR_VERSION, 2,
+                    egl::NONE
+                ];
+
+                egl.create_context(display, config, None, &context_attribs)?
+            }
+        };
+
+        // No surfaces, relies on EGL_KHR_surfaceless_context which Mesa always has
+        egl.make_current(display, None, None, Some(context))?;
+
+        Ok(context)
+    }
+
+    pub fn get_context(&self) -> &GlContext {
+        &self.gl
+    }
+
+    // Needed when the context is used from a different thread than the one that created it
+    pub fn make_current(&self) -> Result<(), GlError> {
+        self.egl.make_current(self.display, None, None, Some(self.context))
+            .map_err(|err| GlError::ContextCreationError(err.to_string()))
+    }
+}
+
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        // Resources may still hold the GlContext, so the callback has to go while the context is current
+        self.gl.disable_debug();
+
+        // Nothing can be done about errors here, the display is torn down either way
+        let _ = self.egl.make_current(self.display, None, None, None);
+        let _ = self.egl.destroy_context(self.display, self.context);
+        let _ = self.egl.terminate(self.display);
+    }
+}
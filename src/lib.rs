@@ -1,8 +1,26 @@
+// Unsafe functions follow the safety rules of the GL calls they wrap
+#![allow(clippy::missing_safety_doc)]
+
 mod shader_program;
+mod shader_loader;
+mod shader_diagnostics;
+mod spirv;
+mod shader_reloader;
+mod program_cache;
+mod shader_variants;
+mod program_pipeline;
+mod reflection;
+mod uniform_value;
+mod compute;
 mod mesh;
 mod model;
 mod skybox;
 mod uniform_buffer;
+mod storage_buffer;
+mod block_layout;
+mod pod;
+mod ring_buffer;
+mod block_binding;
 mod error;
 mod vertex_array;
 mod buffer_obj;
@@ -12,17 +30,49 @@ mod framebuffer;
 mod render_buffer;
 mod render_pipeline;
 mod gl_image;
+mod backend;
+mod compat_backend;
+mod mock_backend;
+mod context;
+mod debug;
+mod state_cache;
+#[cfg(feature = "headless")]
+mod headless;
+#[cfg(feature = "gles")]
+mod gles_backend;
 
 pub mod model_utils;
+#[allow(clippy::all)]
 pub mod gl {
     include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
 }
+#[cfg(feature = "gles")]
+#[allow(clippy::all)]
+pub mod gles {
+    include!(concat!(env!("OUT_DIR"), "/gles_bindings.rs"));
+}
 
 pub use shader_program::*;
+pub use shader_loader::*;
+pub use shader_diagnostics::*;
+pub use spirv::*;
+pub use shader_reloader::*;
+pub use program_cache::*;
+pub use shader_variants::*;
+pub use program_pipeline::*;
+pub use reflection::*;
+pub use uniform_value::*;
+pub use compute::*;
 pub use mesh::*;
 pub use model::*;
 pub use skybox::*;
 pub use uniform_buffer::*;
+pub use storage_buffer::*;
+pub use block_layout::*;
+pub use pod::*;
+pub use ring_buffer::*;
+pub use silver_gl_derive::{AsStd140, AsStd430};
+pub use block_binding::*;
 pub use error::*;
 pub use vertex_array::*;
 pub use buffer_obj::*;
@@ -32,15 +82,23 @@ pub use framebuffer::*;
 pub use render_buffer::*;
 pub use render_pipeline::*;
 pub use gl_image::*;
+pub use backend::*;
+pub use compat_backend::*;
+pub use mock_backend::*;
+pub use context::*;
+pub use debug::*;
+pub use state_cache::*;
+#[cfg(feature = "headless")]
+pub use headless::*;
+#[cfg(feature = "gles")]
+pub use gles_backend::*;
 
 // TODO: Implement transparency (see if qsort is fast enough to do it each frame for each model of the scene?)
 // TODO: Add simple and efficient lighting to everything (do serious research when it comes to doing this on forward and deffered pipelines)
 // TODO: Implement multisampling on all render pipelines (maybe make multisampled versions of them?)
-// TODO: Create test suite
 // TODO: Finish LearnOpenGL and do all the guest articles as well
 // TODO: Comments that use better-comments styles
 // TODO: Create documentation using rust's documentation thing (Have GL errors section in docs as well as panics section)
-// TODO: Maybe implement compute shaders to do matrix transforms once each frame but faster than on CPU?
 // TODO: Write errors that suggest a solution as well
 
 // TODO: make it so all textures are just one big array and diff/spec/etc textures have guaranteed pos in engine
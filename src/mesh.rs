@@ -35,34 +35,41 @@ impl Mesh {
     }
 
     pub unsafe fn set_textures(&self, shader_program: &ShaderProgram) -> Result<(), GlError> {
+        let gl = shader_program.get_context();
+        let texture_count = self.diffuse_textures.len() + self.specular_textures.len() + self.normal_textures.len()
+            + self.displacement_textures.len() + self.shininess_textures.len();
+
+        // Every texture gets its own unit, so they all have to fit in the fragment shader at once
+        gl.check_limit("GL_MAX_TEXTURE_IMAGE_UNITS", texture_count as i32, gl.get_limits().max_texture_image_units)?;
+
         let mut i: i32 = 0;
         
         // Diffuse
         for texture in self.diffuse_textures.iter() {
             texture.ready_texture(i as u32);
-            shader_program.set_int_unsafe(format!("material.diffuse[{}]", i).as_str(), i)?;
+            shader_program.set_int_unsafe(("material.diffuse", i as usize), i)?;
             i += 1;
         }
         shader_program.set_int_unsafe("material.diffuseCount", self.diffuse_textures.len() as i32)?;
-        if self.diffuse_textures.len() == 0 {
+        if self.diffuse_textures.is_empty() {
             shader_program.set_vector_3_unsafe("material.diffuseFloat", &self.diffuse)?;
         }
 
         // Specular
         for texture in self.specular_textures.iter() {
             texture.ready_texture(i as u32);
-            shader_program.set_int_unsafe(format!("material.specular[{}]", i).as_str(), i)?;
+            shader_program.set_int_unsafe(("material.specular", i as usize), i)?;
             i += 1;
         }
         shader_program.set_int_unsafe("material.specularCount", self.specular_textures.len() as i32)?;
-        if self.specular_textures.len() == 0 {
+        if self.specular_textures.is_empty() {
             shader_program.set_vector_3_unsafe("material.specularFloat", &self.specular)?;
         }
 
         // Normal
         for texture in self.normal_textures.iter() {
             texture.ready_texture(i as u32);
-            shader_program.set_int_unsafe(format!("material.normal[{}]", i).as_str(), i)?;
+            shader_program.set_int_unsafe(("material.normal", i as usize), i)?;
             i += 1;
         }
         shader_program.set_int_unsafe("material.normalCount", self.normal_textures.len() as i32)?;
@@ -70,7 +77,7 @@ impl Mesh {
         // Displacement
         for texture in self.displacement_textures.iter() {
             texture.ready_texture(i as u32);
-            shader_program.set_int_unsafe(format!("material.displacement[{}]", i).as_str(), i)?;
+            shader_program.set_int_unsafe(("material.displacement", i as usize), i)?;
             i += 1;
         }
         shader_program.set_int_unsafe("material.displacementCount", self.displacement_textures.len() as i32)?;
@@ -78,11 +85,11 @@ impl Mesh {
         // Shininess
         for texture in self.shininess_textures.iter() {
             texture.ready_texture(i as u32);
-            shader_program.set_int_unsafe(format!("material.shininess[{}]", i).as_str(), i)?;
+            shader_program.set_int_unsafe(("material.shininess", i as usize), i)?;
             i += 1;
         }
         shader_program.set_int_unsafe("material.shininessCount", self.shininess_textures.len() as i32)?;
-        if self.shininess_textures.len() == 0 {
+        if self.shininess_textures.is_empty() {
             shader_program.set_float_unsafe("material.shininessFloat", self.shininess)?;
         }
 
@@ -0,0 +1,921 @@
+use std::{cell::{Cell, RefCell}, collections::{HashMap, HashSet}, ffi::CStr};
+use super::{GlBackend, GlApi, gl::{self, types::{GLenum, GLvoid, GLbitfield, GLsync}}};
+
+// A single recorded GL call. Pointer arguments are not recorded,
+// string arguments (shader source, uniform names) go in `text`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GlCall {
+    pub name: &'static str,
+    pub args: Vec<i64>,
+    pub text: Option<String>
+}
+
+const MOCK_BINARY_FORMAT: GLenum = 0x5347;
+
+// Name and (property, value) pairs
+type MockResource = (String, Vec<(GLenum, i32)>);
+
+// Backend that does no rendering, for running the crate without a GPU.
+// Hands out fake object IDs, records every call made through it,
+// and answers queries with whatever results it has been configured with
+pub struct MockGl {
+    next_id: Cell<u32>,
+    calls: RefCell<Vec<GlCall>>,
+    shader_iv: RefCell<HashMap<GLenum, i32>>,
+    program_iv: RefCell<HashMap<GLenum, i32>>,
+    shader_info_log: RefCell<String>,
+    program_info_log: RefCell<String>,
+    framebuffer_status: Cell<GLenum>,
+    uniform_locations: RefCell<HashMap<String, i32>>,
+    missing_uniforms: RefCell<HashSet<String>>,
+    // Interface -> resources for the program resource queries, shared by all programs
+    program_resources: RefCell<HashMap<GLenum, Vec<MockResource>>>,
+    integers: RefCell<HashMap<GLenum, i32>>,
+    strings: RefCell<HashMap<GLenum, String>>,
+    extensions: RefCell<Vec<String>>,
+    api: Cell<GlApi>,
+    debug_callback: Cell<(gl::types::GLDEBUGPROC, *const GLvoid)>,
+    // Memory handed out by map_named_buffer_range, u128 so it is aligned like real mappings
+    mapped_buffers: RefCell<HashMap<u32, Vec<u128>>>,
+    sync_status: Cell<GLenum>
+}
+
+impl MockGl {
+    // Shaders compile, programs link and framebuffers are complete unless told otherwise.
+    // Reports itself as a GL 4.6 context with no extensions and the minimum limits the spec allows
+    pub fn new() -> MockGl {
+        MockGl {
+            next_id: Cell::new(1),
+            calls: RefCell::new(Vec::new()),
+            shader_iv: RefCell::new(HashMap::from([(gl::COMPILE_STATUS, gl::TRUE as i32)])),
+            program_iv: RefCell::new(HashMap::from([(gl::LINK_STATUS, gl::TRUE as i32)])),
+            shader_info_log: RefCell::new(String::new()),
+            program_info_log: RefCell::new(String::new()),
+            framebuffer_status: Cell::new(gl::FRAMEBUFFER_COMPLETE),
+            uniform_locations: RefCell::new(HashMap::new()),
+            missing_uniforms: RefCell::new(HashSet::new()),
+            program_resources: RefCell::new(HashMap::new()),
+            integers: RefCell::new(HashMap::from([
+                (gl::MAJOR_VERSION, 4),
+                (gl::MINOR_VERSION, 6),
+                (gl::MAX_TEXTURE_IMAGE_UNITS, 16),
+                (gl::MAX_COMBINED_TEXTURE_IMAGE_UNITS, 80),
+                (gl::MAX_UNIFORM_BLOCK_SIZE, 16384),
+                (gl::MAX_UNIFORM_BUFFER_BINDINGS, 84),
+                (gl::MAX_COLOR_ATTACHMENTS, 8),
+                (gl::MAX_DRAW_BUFFERS, 8),
+                (gl::MAX_TEXTURE_SIZE, 16384),
+                (gl::MAX_VERTEX_ATTRIBS, 16)
+            ])),
+            strings: RefCell::new(HashMap::from([
+                (gl::VENDOR, String::from("silver_gl")),
+                (gl::RENDERER, String::from("MockGl")),
+                (gl::VERSION, String::from("4.6 (Core Profile) MockGl"))
+            ])),
+            extensions: RefCell::new(Vec::new()),
+            api: Cell::new(GlApi::Gl),
+            debug_callback: Cell::new((None, std::ptr::null())),
+            mapped_buffers: RefCell::new(HashMap::new()),
+            sync_status: Cell::new(gl::ALREADY_SIGNALED)
+        }
+    }
+
+    // Calls the installed debug callback (if any) as the driver would
+    pub fn emit_debug_message(&self, source: GLenum, type_: GLenum, id: u32, severity: GLenum, message: &str) {
+        if let (Some(callback), user_param) = self.debug_callback.get() {
+            callback(
+                source,
+                type_,
+                id,
+                severity,
+                message.len() as i32,
+                message.as_ptr() as *const gl::types::GLchar,
+                user_param as *mut GLvoid
+            );
+        }
+    }
+
+    // Applies to glGetIntegerv, which is where the version and limits come from
+    pub fn set_integer(&self, pname: GLenum, value: i32) {
+        self.integers.borrow_mut().insert(pname, value);
+    }
+
+    pub fn set_string(&self, name: GLenum, value: &str) {
+        self.strings.borrow_mut().insert(name, value.to_owned());
+    }
+
+    pub fn set_extensions(&self, extensions: &[&str]) {
+        *self.extensions.borrow_mut() = extensions.iter().map(|ext| ext.to_string()).collect();
+    }
+
+    // What glClientWaitSync returns, fences are signaled as soon as they are created by default
+    pub fn set_sync_status(&self, status: GLenum) {
+        self.sync_status.set(status);
+    }
+
+    // Only changes how the context treats it, the version has to be set separately
+    pub fn set_api(&self, api: GlApi) {
+        self.api.set(api);
+    }
+
+    pub fn set_shader_iv(&self, pname: GLenum, value: i32) {
+        self.shader_iv.borrow_mut().insert(pname, value);
+    }
+
+    pub fn set_program_iv(&self, pname: GLenum, value: i32) {
+        self.program_iv.borrow_mut().insert(pname, value);
+    }
+
+    pub fn set_shader_info_log(&self, log: &str) {
+        *self.shader_info_log.borrow_mut() = log.to_owned();
+    }
+
+    pub fn set_program_info_log(&self, log: &str) {
+        *self.program_info_log.borrow_mut() = log.to_owned();
+    }
+
+    pub fn set_framebuffer_status(&self, status: GLenum) {
+        self.framebuffer_status.set(status);
+    }
+
+    // Makes uniform and uniform block lookups for this name fail
+    pub fn set_missing_uniform(&self, name: &str) {
+        self.missing_uniforms.borrow_mut().insert(name.to_owned());
+    }
+
+    // Makes the resource show up in reflection, e.g. (gl::UNIFORM, "color", &[(gl::TYPE, gl::FLOAT_VEC3 as i32)])
+    pub fn set_program_resource(&self, interface: GLenum, name: &str, props: &[(GLenum, i32)]) {
+        self.program_resources.borrow_mut()
+            .entry(interface)
+            .or_default()
+            .push((name.to_owned(), props.to_vec()));
+    }
+
+    pub fn get_calls(&self) -> Vec<GlCall> {
+        self.calls.borrow().clone()
+    }
+
+    pub fn take_calls(&self) -> Vec<GlCall> {
+        std::mem::take(&mut *self.calls.borrow_mut())
+    }
+
+    pub fn clear_calls(&self) {
+        self.calls.borrow_mut().clear();
+    }
+
+    pub fn count(&self, name: &str) -> usize {
+        self.calls.borrow().iter().filter(|call| call.name == name).count()
+    }
+
+    pub fn find(&self, name: &str) -> Option<GlCall> {
+        self.calls.borrow().iter().find(|call| call.name == name).cloned()
+    }
+
+    fn record(&self, name: &'static str, args: &[i64]) {
+        self.calls.borrow_mut().push(GlCall { name, args: args.to_vec(), text: None });
+    }
+
+    fn record_text(&self, name: &'static str, args: &[i64], text: &CStr) {
+        self.calls.borrow_mut().push(GlCall {
+            name,
+            args: args.to_vec(),
+            text: Some(text.to_string_lossy().into_owned())
+        });
+    }
+
+    fn gen_id(&self, name: &'static str, args: &[i64]) -> u32 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+
+        let mut args = args.to_vec();
+        args.push(id as i64);
+        self.record(name, &args);
+
+        id
+    }
+
+    fn get_resources(&self, interface: GLenum) -> Vec<(String, Vec<(GLenum, i32)>)> {
+        self.program_resources.borrow().get(&interface).cloned().unwrap_or_default()
+    }
+
+    fn lookup_uniform(&self, name: &CStr) -> Option<i32> {
+        let name = name.to_string_lossy().into_owned();
+
+        if self.missing_uniforms.borrow().contains(&name) {
+            return None;
+        }
+
+        let mut locations = self.uniform_locations.borrow_mut();
+        let next = locations.len() as i32;
+
+        Some(*locations.entry(name).or_insert(next))
+    }
+}
+
+impl Default for MockGl {
+    fn default() -> Self {
+        MockGl::new()
+    }
+}
+
+impl GlBackend for MockGl {
+    fn get_api(&self) -> GlApi {
+        self.api.get()
+    }
+
+    // Records the named calls either way
+    fn emulates_dsa(&self) -> bool {
+        false
+    }
+
+    fn create_buffer(&self) -> u32 {
+        self.gen_id("CreateBuffers", &[])
+    }
+
+    fn delete_buffer(&self, buffer: u32) {
+        self.record("DeleteBuffers", &[buffer as i64]);
+    }
+
+    unsafe fn named_buffer_storage(&self, buffer: u32, size: isize, _data: *const GLvoid, flags: GLbitfield) {
+        self.record("NamedBufferStorage", &[buffer as i64, size as i64, flags as i64]);
+    }
+
+    unsafe fn named_buffer_data(&self, buffer: u32, size: isize, _data: *const GLvoid, usage: GLenum) {
+        self.record("NamedBufferData", &[buffer as i64, size as i64, usage as i64]);
+    }
+
+    unsafe fn named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, _data: *const GLvoid) {
+        self.record("NamedBufferSubData", &[buffer as i64, offset as i64, size as i64]);
+    }
+
+    // Leaves `data` as it is
+    unsafe fn get_named_buffer_sub_data(&self, buffer: u32, offset: isize, size: isize, _data: *mut GLvoid) {
+        self.record("GetNamedBufferSubData", &[buffer as i64, offset as i64, size as i64]);
+    }
+
+    fn bind_buffer(&self, target: GLenum, buffer: u32) {
+        self.record("BindBuffer", &[target as i64, buffer as i64]);
+    }
+
+    fn bind_buffer_range(&self, target: GLenum, index: u32, buffer: u32, offset: isize, size: isize) {
+        self.record("BindBufferRange", &[target as i64, index as i64, buffer as i64, offset as i64, size as i64]);
+    }
+
+    // Hands out zeroed memory that stays valid until the buffer is unmapped
+    unsafe fn map_named_buffer_range(&self, buffer: u32, offset: isize, length: isize, access: GLbitfield) -> *mut GLvoid {
+        self.record("MapNamedBufferRange", &[buffer as i64, offset as i64, length as i64, access as i64]);
+
+        let mut mapped_buffers = self.mapped_buffers.borrow_mut();
+        let memory = mapped_buffers.entry(buffer).or_default();
+        *memory = vec![0; (length as usize).div_ceil(16)];
+
+        memory.as_mut_ptr() as *mut GLvoid
+    }
+
+    fn unmap_named_buffer(&self, buffer: u32) -> bool {
+        self.record("UnmapNamedBuffer", &[buffer as i64]);
+        self.mapped_buffers.borrow_mut().remove(&buffer).is_some()
+    }
+
+    fn fence_sync(&self, condition: GLenum, flags: GLbitfield) -> GLsync {
+        self.gen_id("FenceSync", &[condition as i64, flags as i64]) as usize as GLsync
+    }
+
+    unsafe fn client_wait_sync(&self, sync: GLsync, flags: GLbitfield, timeout: u64) -> GLenum {
+        self.record("ClientWaitSync", &[sync as usize as i64, flags as i64, timeout as i64]);
+        self.sync_status.get()
+    }
+
+    unsafe fn delete_sync(&self, sync: GLsync) {
+        self.record("DeleteSync", &[sync as usize as i64]);
+    }
+
+    fn create_vertex_array(&self) -> u32 {
+        self.gen_id("CreateVertexArrays", &[])
+    }
+
+    fn delete_vertex_array(&self, array: u32) {
+        self.record("DeleteVertexArrays", &[array as i64]);
+    }
+
+    fn vertex_array_vertex_buffer(&self, array: u32, binding_index: u32, buffer: u32, offset: isize, stride: i32) {
+        self.record(
+            "VertexArrayVertexBuffer",
+            &[array as i64, binding_index as i64, buffer as i64, offset as i64, stride as i64]
+        );
+    }
+
+    fn vertex_array_element_buffer(&self, array: u32, buffer: u32) {
+        self.record("VertexArrayElementBuffer", &[array as i64, buffer as i64]);
+    }
+
+    fn enable_vertex_array_attrib(&self, array: u32, attrib_index: u32) {
+        self.record("EnableVertexArrayAttrib", &[array as i64, attrib_index as i64]);
+    }
+
+    fn vertex_array_attrib_format(
+        &self,
+        array: u32,
+        attrib_index: u32,
+        size: i32,
+        type_: GLenum,
+        normalized: bool,
+        relative_offset: u32
+    ) {
+        self.record(
+            "VertexArrayAttribFormat",
+            &[array as i64, attrib_index as i64, size as i64, type_ as i64, normalized as i64, relative_offset as i64]
+        );
+    }
+
+    fn vertex_array_attrib_binding(&self, array: u32, attrib_index: u32, binding_index: u32) {
+        self.record("VertexArrayAttribBinding", &[array as i64, attrib_index as i64, binding_index as i64]);
+    }
+
+    fn vertex_array_binding_divisor(&self, array: u32, binding_index: u32, divisor: u32) {
+        self.record("VertexArrayBindingDivisor", &[array as i64, binding_index as i64, divisor as i64]);
+    }
+
+    fn bind_vertex_array(&self, array: u32) {
+        self.record("BindVertexArray", &[array as i64]);
+    }
+
+    unsafe fn draw_elements_instanced(
+        &self,
+        mode: GLenum,
+        count: i32,
+        type_: GLenum,
+        indices: *const GLvoid,
+        instance_count: i32
+    ) {
+        // The index pointer is a byte offset into the element buffer, so it is worth recording
+        self.record(
+            "DrawElementsInstanced",
+            &[mode as i64, count as i64, type_ as i64, indices as i64, instance_count as i64]
+        );
+    }
+
+    unsafe fn multi_draw_elements_indirect(
+        &self,
+        mode: GLenum,
+        type_: GLenum,
+        indirect: *const GLvoid,
+        draw_count: i32,
+        stride: i32
+    ) {
+        self.record(
+            "MultiDrawElementsIndirect",
+            &[mode as i64, type_ as i64, indirect as i64, draw_count as i64, stride as i64]
+        );
+    }
+
+    fn patch_parameter_i(&self, pname: GLenum, value: i32) {
+        self.record("PatchParameteri", &[pname as i64, value as i64]);
+    }
+
+    fn create_shader(&self, type_: GLenum) -> u32 {
+        self.gen_id("CreateShader", &[type_ as i64])
+    }
+
+    fn delete_shader(&self, shader: u32) {
+        self.record("DeleteShader", &[shader as i64]);
+    }
+
+    fn shader_source(&self, shader: u32, source: &CStr) {
+        self.record_text("ShaderSource", &[shader as i64], source);
+    }
+
+    fn compile_shader(&self, shader: u32) {
+        self.record("CompileShader", &[shader as i64]);
+    }
+
+    fn get_shader_iv(&self, shader: u32, pname: GLenum) -> i32 {
+        self.record("GetShaderiv", &[shader as i64, pname as i64]);
+        self.shader_iv.borrow().get(&pname).copied().unwrap_or(0)
+    }
+
+    fn get_shader_info_log(&self, shader: u32) -> String {
+        self.record("GetShaderInfoLog", &[shader as i64]);
+        self.shader_info_log.borrow().clone()
+    }
+
+    fn shader_binary(&self, shader: u32, format: GLenum, binary: &[u8]) {
+        self.record("ShaderBinary", &[shader as i64, format as i64, binary.len() as i64]);
+    }
+
+    fn specialize_shader(&self, shader: u32, entry_point: &CStr, constant_indices: &[u32], _constant_values: &[u32]) {
+        self.record_text("SpecializeShader", &[shader as i64, constant_indices.len() as i64], entry_point);
+    }
+
+    fn create_program(&self) -> u32 {
+        self.gen_id("CreateProgram", &[])
+    }
+
+    fn delete_program(&self, program: u32) {
+        self.record("DeleteProgram", &[program as i64]);
+    }
+
+    fn attach_shader(&self, program: u32, shader: u32) {
+        self.record("AttachShader", &[program as i64, shader as i64]);
+    }
+
+    fn link_program(&self, program: u32) {
+        self.record("LinkProgram", &[program as i64]);
+    }
+
+    fn get_program_iv(&self, program: u32, pname: GLenum) -> i32 {
+        self.record("GetProgramiv", &[program as i64, pname as i64]);
+        self.program_iv.borrow().get(&pname).copied().unwrap_or(0)
+    }
+
+    fn get_program_info_log(&self, program: u32) -> String {
+        self.record("GetProgramInfoLog", &[program as i64]);
+        self.program_info_log.borrow().clone()
+    }
+
+    fn program_parameter_i(&self, program: u32, pname: GLenum, value: i32) {
+        self.record("ProgramParameteri", &[program as i64, pname as i64, value as i64]);
+    }
+
+    // The binary is the program ID, in a format no real driver uses
+    fn get_program_binary(&self, program: u32) -> (GLenum, Vec<u8>) {
+        self.record("GetProgramBinary", &[program as i64]);
+        (MOCK_BINARY_FORMAT, program.to_le_bytes().to_vec())
+    }
+
+    fn program_binary(&self, program: u32, format: GLenum, binary: &[u8]) {
+        self.record("ProgramBinary", &[program as i64, format as i64, binary.len() as i64]);
+    }
+
+    fn create_program_pipeline(&self) -> u32 {
+        self.gen_id("CreateProgramPipelines", &[])
+    }
+
+    fn delete_program_pipeline(&self, pipeline: u32) {
+        self.record("DeleteProgramPipelines", &[pipeline as i64]);
+    }
+
+    fn bind_program_pipeline(&self, pipeline: u32) {
+        self.record("BindProgramPipeline", &[pipeline as i64]);
+    }
+
+    fn use_program_stages(&self, pipeline: u32, stages: GLbitfield, program: u32) {
+        self.record("UseProgramStages", &[pipeline as i64, stages as i64, program as i64]);
+    }
+
+    fn active_shader_program(&self, pipeline: u32, program: u32) {
+        self.record("ActiveShaderProgram", &[pipeline as i64, program as i64]);
+    }
+
+    fn validate_program_pipeline(&self, pipeline: u32) {
+        self.record("ValidateProgramPipeline", &[pipeline as i64]);
+    }
+
+    // Answered from the program values, pipelines validate unless GL_VALIDATE_STATUS was set
+    fn get_program_pipeline_iv(&self, pipeline: u32, pname: GLenum) -> i32 {
+        self.record("GetProgramPipelineiv", &[pipeline as i64, pname as i64]);
+
+        let default = if pname == gl::VALIDATE_STATUS { gl::TRUE as i32 } else { 0 };
+        self.program_iv.borrow().get(&pname).copied().unwrap_or(default)
+    }
+
+    fn get_program_pipeline_info_log(&self, pipeline: u32) -> String {
+        self.record("GetProgramPipelineInfoLog", &[pipeline as i64]);
+        self.program_info_log.borrow().clone()
+    }
+
+    fn use_program(&self, program: u32) {
+        self.record("UseProgram", &[program as i64]);
+    }
+
+    fn get_uniform_location(&self, program: u32, name: &CStr) -> i32 {
+        self.record_text("GetUniformLocation", &[program as i64], name);
+        self.lookup_uniform(name).unwrap_or(-1)
+    }
+
+    fn uniform_1i(&self, location: i32, v0: i32) {
+        self.record("Uniform1i", &[location as i64, v0 as i64]);
+    }
+
+    // Floats are recorded by their bit pattern so calls stay comparable
+    fn uniform_1f(&self, location: i32, v0: f32) {
+        self.record("Uniform1f", &[location as i64, v0.to_bits() as i64]);
+    }
+
+    fn uniform_3f(&self, location: i32, v0: f32, v1: f32, v2: f32) {
+        self.record("Uniform3f", &[location as i64, v0.to_bits() as i64, v1.to_bits() as i64, v2.to_bits() as i64]);
+    }
+
+    fn uniform_4f(&self, location: i32, v0: f32, v1: f32, v2: f32, v3: f32) {
+        self.record(
+            "Uniform4f",
+            &[location as i64, v0.to_bits() as i64, v1.to_bits() as i64, v2.to_bits() as i64, v3.to_bits() as i64]
+        );
+    }
+
+    fn uniform_3fv(&self, location: i32, value: &[f32]) {
+        self.record("Uniform3fv", &[location as i64, (value.len() / 3) as i64]);
+    }
+
+    fn uniform_4fv(&self, location: i32, value: &[f32]) {
+        self.record("Uniform4fv", &[location as i64, (value.len() / 4) as i64]);
+    }
+
+    fn uniform_matrix_4fv(&self, location: i32, transpose: bool, value: &[f32]) {
+        self.record("UniformMatrix4fv", &[location as i64, (value.len() / 16) as i64, transpose as i64]);
+    }
+
+    // Recorded with the vector size or matrix shape in the name, e.g. Uniform3iv and UniformMatrix3x4fv
+    fn uniform_iv(&self, location: i32, components: i32, value: &[i32]) {
+        let name = ["Uniform1iv", "Uniform2iv", "Uniform3iv", "Uniform4iv"][components as usize - 1];
+        self.record(name, &[location as i64, (value.len() as i32 / components) as i64]);
+    }
+
+    fn uniform_uiv(&self, location: i32, components: i32, value: &[u32]) {
+        let name = ["Uniform1uiv", "Uniform2uiv", "Uniform3uiv", "Uniform4uiv"][components as usize - 1];
+        self.record(name, &[location as i64, (value.len() as i32 / components) as i64]);
+    }
+
+    fn uniform_fv(&self, location: i32, components: i32, value: &[f32]) {
+        let name = ["Uniform1fv", "Uniform2fv", "Uniform3fv", "Uniform4fv"][components as usize - 1];
+        self.record(name, &[location as i64, (value.len() as i32 / components) as i64]);
+    }
+
+    fn uniform_dv(&self, location: i32, components: i32, value: &[f64]) {
+        let name = ["Uniform1dv", "Uniform2dv", "Uniform3dv", "Uniform4dv"][components as usize - 1];
+        self.record(name, &[location as i64, (value.len() as i32 / components) as i64]);
+    }
+
+    fn uniform_matrix_fv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f32]) {
+        let name = [
+            ["UniformMatrix2fv", "UniformMatrix2x3fv", "UniformMatrix2x4fv"],
+            ["UniformMatrix3x2fv", "UniformMatrix3fv", "UniformMatrix3x4fv"],
+            ["UniformMatrix4x2fv", "UniformMatrix4x3fv", "UniformMatrix4fv"]
+        ][columns as usize - 2][rows as usize - 2];
+        self.record(name, &[location as i64, (value.len() as i32 / (columns * rows)) as i64, transpose as i64]);
+    }
+
+    fn uniform_matrix_dv(&self, location: i32, columns: i32, rows: i32, transpose: bool, value: &[f64]) {
+        let name = [
+            ["UniformMatrix2dv", "UniformMatrix2x3dv", "UniformMatrix2x4dv"],
+            ["UniformMatrix3x2dv", "UniformMatrix3dv", "UniformMatrix3x4dv"],
+            ["UniformMatrix4x2dv", "UniformMatrix4x3dv", "UniformMatrix4dv"]
+        ][columns as usize - 2][rows as usize - 2];
+        self.record(name, &[location as i64, (value.len() as i32 / (columns * rows)) as i64, transpose as i64]);
+    }
+
+    fn get_uniform_block_index(&self, program: u32, name: &CStr) -> u32 {
+        self.record_text("GetUniformBlockIndex", &[program as i64], name);
+        self.lookup_uniform(name).map(|index| index as u32).unwrap_or(gl::INVALID_INDEX)
+    }
+
+    fn uniform_block_binding(&self, program: u32, block_index: u32, binding: u32) {
+        self.record("UniformBlockBinding", &[program as i64, block_index as i64, binding as i64]);
+    }
+
+    fn shader_storage_block_binding(&self, program: u32, block_index: u32, binding: u32) {
+        self.record("ShaderStorageBlockBinding", &[program as i64, block_index as i64, binding as i64]);
+    }
+
+    // Resources from set_program_resource()
+    fn get_program_resource_index(&self, program: u32, interface: GLenum, name: &CStr) -> u32 {
+        self.record_text("GetProgramResourceIndex", &[program as i64, interface as i64], name);
+
+        let name = name.to_string_lossy();
+        self.get_resources(interface).iter()
+            .position(|(resource, _)| *resource == name)
+            .map_or(gl::INVALID_INDEX, |index| index as u32)
+    }
+
+    fn get_program_interface_iv(&self, program: u32, interface: GLenum, pname: GLenum) -> i32 {
+        self.record("GetProgramInterfaceiv", &[program as i64, interface as i64, pname as i64]);
+
+        match pname {
+            gl::ACTIVE_RESOURCES => self.get_resources(interface).len() as i32,
+            _ => 0
+        }
+    }
+
+    fn get_program_resource_name(&self, program: u32, interface: GLenum, index: u32) -> String {
+        self.record("GetProgramResourceName", &[program as i64, interface as i64, index as i64]);
+        self.get_resources(interface).get(index as usize).map(|(name, _)| name.clone()).unwrap_or_default()
+    }
+
+    // Properties that weren't given to set_program_resource() are -1
+    fn get_program_resource_iv(&self, program: u32, interface: GLenum, index: u32, props: &[GLenum]) -> Vec<i32> {
+        self.record("GetProgramResourceiv", &[program as i64, interface as i64, index as i64]);
+
+        let resources = self.get_resources(interface);
+        props.iter()
+            .map(|prop| {
+                resources.get(index as usize)
+                    .and_then(|(_, values)| values.iter().find(|(name, _)| name == prop))
+                    .map_or(-1, |(_, value)| *value)
+            })
+            .collect()
+    }
+
+    fn dispatch_compute(&self, num_groups_x: u32, num_groups_y: u32, num_groups_z: u32) {
+        self.record("DispatchCompute", &[num_groups_x as i64, num_groups_y as i64, num_groups_z as i64]);
+    }
+
+    fn dispatch_compute_indirect(&self, indirect: isize) {
+        self.record("DispatchComputeIndirect", &[indirect as i64]);
+    }
+
+    fn memory_barrier(&self, barriers: GLbitfield) {
+        self.record("MemoryBarrier", &[barriers as i64]);
+    }
+
+    fn bind_image_texture(
+        &self,
+        unit: u32,
+        texture: u32,
+        level: i32,
+        layered: bool,
+        layer: i32,
+        access: GLenum,
+        format: GLenum
+    ) {
+        self.record(
+            "BindImageTexture",
+            &[unit as i64, texture as i64, level as i64, layered as i64, layer as i64, access as i64, format as i64]
+        );
+    }
+
+    fn create_texture(&self, target: GLenum) -> u32 {
+        self.gen_id("CreateTextures", &[target as i64])
+    }
+
+    fn delete_texture(&self, texture: u32) {
+        self.record("DeleteTextures", &[texture as i64]);
+    }
+
+    fn texture_storage_2d(&self, texture: u32, levels: i32, internal_format: GLenum, width: i32, height: i32) {
+        self.record(
+            "TextureStorage2D",
+            &[texture as i64, levels as i64, internal_format as i64, width as i64, height as i64]
+        );
+    }
+
+    unsafe fn texture_sub_image_2d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        width: i32,
+        height: i32,
+        format: GLenum,
+        type_: GLenum,
+        _pixels: *const GLvoid
+    ) {
+        self.record(
+            "TextureSubImage2D",
+            &[
+                texture as i64, level as i64, x_offset as i64, y_offset as i64,
+                width as i64, height as i64, format as i64, type_ as i64
+            ]
+        );
+    }
+
+    unsafe fn texture_sub_image_3d(
+        &self,
+        texture: u32,
+        level: i32,
+        x_offset: i32,
+        y_offset: i32,
+        z_offset: i32,
+        width: i32,
+        height: i32,
+        depth: i32,
+        format: GLenum,
+        type_: GLenum,
+        _pixels: *const GLvoid
+    ) {
+        self.record(
+            "TextureSubImage3D",
+            &[
+                texture as i64, level as i64, x_offset as i64, y_offset as i64, z_offset as i64,
+                width as i64, height as i64, depth as i64, format as i64, type_ as i64
+            ]
+        );
+    }
+
+    unsafe fn tex_image_2d(
+        &self,
+        target: GLenum,
+        level: i32,
+        internal_format: i32,
+        width: i32,
+        height: i32,
+        border: i32,
+        format: GLenum,
+        type_: GLenum,
+        _pixels: *const GLvoid
+    ) {
+        self.record(
+            "TexImage2D",
+            &[
+                target as i64, level as i64, internal_format as i64, width as i64,
+                height as i64, border as i64, format as i64, type_ as i64
+            ]
+        );
+    }
+
+    fn generate_texture_mipmap(&self, texture: u32) {
+        self.record("GenerateTextureMipmap", &[texture as i64]);
+    }
+
+    fn texture_parameter_i(&self, texture: u32, pname: GLenum, param: i32) {
+        self.record("TextureParameteri", &[texture as i64, pname as i64, param as i64]);
+    }
+
+    fn bind_texture(&self, target: GLenum, texture: u32) {
+        self.record("BindTexture", &[target as i64, texture as i64]);
+    }
+
+    fn bind_texture_unit(&self, unit: u32, texture: u32) {
+        self.record("BindTextureUnit", &[unit as i64, texture as i64]);
+    }
+
+    fn active_texture(&self, texture: GLenum) {
+        self.record("ActiveTexture", &[texture as i64]);
+    }
+
+    // Handles are just the texture ID in the upper half, so they never collide with IDs
+    fn get_texture_handle(&self, texture: u32) -> u64 {
+        self.record("GetTextureHandleARB", &[texture as i64]);
+        (texture as u64) << 32
+    }
+
+    fn make_texture_handle_resident(&self, handle: u64) {
+        self.record("MakeTextureHandleResidentARB", &[handle as i64]);
+    }
+
+    fn make_texture_handle_non_resident(&self, handle: u64) {
+        self.record("MakeTextureHandleNonResidentARB", &[handle as i64]);
+    }
+
+    fn create_framebuffer(&self) -> u32 {
+        self.gen_id("CreateFramebuffers", &[])
+    }
+
+    fn delete_framebuffer(&self, framebuffer: u32) {
+        self.record("DeleteFramebuffers", &[framebuffer as i64]);
+    }
+
+    fn named_framebuffer_texture(&self, framebuffer: u32, attachment: GLenum, texture: u32, level: i32) {
+        self.record(
+            "NamedFramebufferTexture",
+            &[framebuffer as i64, attachment as i64, texture as i64, level as i64]
+        );
+    }
+
+    fn named_framebuffer_draw_buffers(&self, framebuffer: u32, buffers: &[GLenum]) {
+        let mut args = vec![framebuffer as i64];
+        args.extend(buffers.iter().map(|buffer| *buffer as i64));
+        self.record("NamedFramebufferDrawBuffers", &args);
+    }
+
+    fn named_framebuffer_renderbuffer(
+        &self,
+        framebuffer: u32,
+        attachment: GLenum,
+        renderbuffer_target: GLenum,
+        renderbuffer: u32
+    ) {
+        self.record(
+            "NamedFramebufferRenderbuffer",
+            &[framebuffer as i64, attachment as i64, renderbuffer_target as i64, renderbuffer as i64]
+        );
+    }
+
+    fn check_named_framebuffer_status(&self, framebuffer: u32, target: GLenum) -> GLenum {
+        self.record("CheckNamedFramebufferStatus", &[framebuffer as i64, target as i64]);
+        self.framebuffer_status.get()
+    }
+
+    fn bind_framebuffer(&self, target: GLenum, framebuffer: u32) {
+        self.record("BindFramebuffer", &[target as i64, framebuffer as i64]);
+    }
+
+    fn create_renderbuffer(&self) -> u32 {
+        self.gen_id("CreateRenderbuffers", &[])
+    }
+
+    fn delete_renderbuffer(&self, renderbuffer: u32) {
+        self.record("DeleteRenderbuffers", &[renderbuffer as i64]);
+    }
+
+    fn named_renderbuffer_storage(&self, renderbuffer: u32, internal_format: GLenum, width: i32, height: i32) {
+        self.record(
+            "NamedRenderbufferStorage",
+            &[renderbuffer as i64, internal_format as i64, width as i64, height as i64]
+        );
+    }
+
+    fn enable(&self, cap: GLenum) {
+        self.record("Enable", &[cap as i64]);
+    }
+
+    fn disable(&self, cap: GLenum) {
+        self.record("Disable", &[cap as i64]);
+    }
+
+    fn depth_func(&self, func: GLenum) {
+        self.record("DepthFunc", &[func as i64]);
+    }
+
+    fn blend_func(&self, sfactor: GLenum, dfactor: GLenum) {
+        self.record("BlendFunc", &[sfactor as i64, dfactor as i64]);
+    }
+
+    fn cull_face(&self, mode: GLenum) {
+        self.record("CullFace", &[mode as i64]);
+    }
+
+    unsafe fn debug_message_callback(&self, callback: gl::types::GLDEBUGPROC, user_param: *const GLvoid) {
+        self.record("DebugMessageCallback", &[callback.is_some() as i64]);
+        self.debug_callback.set((callback, user_param));
+    }
+
+    fn object_label(&self, identifier: GLenum, name: u32, label: &str) {
+        self.calls.borrow_mut().push(GlCall {
+            name: "ObjectLabel",
+            args: vec![identifier as i64, name as i64],
+            text: Some(label.to_owned())
+        });
+    }
+
+    fn push_debug_group(&self, source: GLenum, id: u32, message: &str) {
+        self.calls.borrow_mut().push(GlCall {
+            name: "PushDebugGroup",
+            args: vec![source as i64, id as i64],
+            text: Some(message.to_owned())
+        });
+    }
+
+    fn pop_debug_group(&self) {
+        self.record("PopDebugGroup", &[]);
+    }
+
+    fn get_integer_v(&self, pname: GLenum) -> i32 {
+        self.record("GetIntegerv", &[pname as i64]);
+
+        if pname == gl::NUM_EXTENSIONS {
+            return self.extensions.borrow().len() as i32;
+        }
+
+        self.integers.borrow().get(&pname).copied().unwrap_or(0)
+    }
+
+    fn get_string(&self, name: GLenum) -> String {
+        self.record("GetString", &[name as i64]);
+        self.strings.borrow().get(&name).cloned().unwrap_or_default()
+    }
+
+    fn get_string_i(&self, name: GLenum, index: u32) -> String {
+        self.record("GetStringi", &[name as i64, index as i64]);
+
+        match name {
+            gl::EXTENSIONS => self.extensions.borrow().get(index as usize).cloned().unwrap_or_default(),
+            _ => String::new()
+        }
+    }
+}
+
+// Shared by the tests of every module
+#[cfg(test)]
+pub(crate) mod testing {
+    use std::rc::Rc;
+    use crate::GlContext;
+    use super::{GlCall, MockGl};
+
+    // `setup` runs before the context reads the version and extensions.
+    // The calls made while creating the context are cleared
+    pub(crate) fn mock_context(setup: impl FnOnce(&MockGl)) -> (Rc<MockGl>, GlContext) {
+        let mock = Rc::new(MockGl::new());
+        setup(&mock);
+
+        let gl = GlContext::from_backend(mock.clone()).unwrap();
+        mock.clear_calls();
+
+        (mock, gl)
+    }
+
+    // For GLES tests, the lowest version the crate runs on
+    pub(crate) fn set_gles(mock: &MockGl) {
+        mock.set_api(crate::GlApi::Gles);
+        mock.set_integer(crate::gl::MAJOR_VERSION, 3);
+        mock.set_integer(crate::gl::MINOR_VERSION, 2);
+    }
+
+    pub(crate) fn get_names(calls: &[GlCall]) -> Vec<&'static str> {
+        calls.iter().map(|call| call.name).collect()
+    }
+
+    pub(crate) fn get_args(calls: &[GlCall], name: &str) -> Vec<Vec<i64>> {
+        calls.iter().filter(|call| call.name == name).map(|call| call.args.clone()).collect()
+    }
+}
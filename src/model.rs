@@ -1,7 +1,7 @@
 use cgmath::Matrix4;
 use memoffset::offset_of;
 use crate::{Buffer, DrawCommand};
-use super::{ShaderProgram, Mesh, Vertex, GlError, VertexArray, gl, model_utils::calc_vertex_tangents};
+use super::{ShaderProgram, Mesh, Vertex, GlError, VertexArray, GlContext, gl, model_utils::calc_vertex_tangents};
 
 pub trait ModelTrait {
     fn draw(&self, shader_program: &ShaderProgram) -> Result<(), GlError>;
@@ -12,7 +12,13 @@ pub trait ModelTrait {
 }
 
 pub trait ModelCreateTrait {
-    fn new(vertices: Vec<Vertex>, indices: Vec<u32>, model_transform: Vec<Matrix4<f32>>, meshes: Vec<Mesh>) -> Self;
+    fn new(
+        gl: &GlContext,
+        vertices: Vec<Vertex>,
+        indices: Vec<u32>,
+        model_transform: Vec<Matrix4<f32>>,
+        meshes: Vec<Mesh>
+    ) -> Self;
 }
 
 pub struct MultiBindModel {
@@ -21,27 +27,43 @@ pub struct MultiBindModel {
     pub vertex_buffer: Buffer<Vertex>,
     pub element_buffer: Buffer<u32>,
     pub transform_buffer: Buffer<Matrix4<f32>>,
+    label: Option<String>,
+    gl: GlContext
 }
 
 impl ModelCreateTrait for MultiBindModel {
     fn new(
+        gl: &GlContext,
         mut vertices: Vec<Vertex>,
         mut indices: Vec<u32>,
         model_transforms: Vec<Matrix4<f32>>,
         meshes: Vec<Mesh>
     ) -> Self {
+        let _span = tracing::info_span!(
+            "upload_model",
+            model = "MultiBindModel",
+            vertices = vertices.len(),
+            indices = indices.len(),
+            meshes = meshes.len(),
+            instances = model_transforms.len()
+        ).entered();
+
         let mut model = Self {
             meshes,
-            vertex_array: VertexArray::new(),
-            vertex_buffer: Buffer::new(),
-            element_buffer: Buffer::new(),
-            transform_buffer: Buffer::new()
+            vertex_array: VertexArray::new(gl),
+            vertex_buffer: Buffer::new(gl),
+            element_buffer: Buffer::new(gl),
+            transform_buffer: Buffer::new(gl),
+            label: None,
+            gl: gl.clone()
         };
 
         calc_vertex_tangents(&mut vertices, &mut indices);
         model.setup_model(vertices, indices);
         model.setup_transform_attribute(model_transforms);
 
+        tracing::debug!("model uploaded");
+
         model
     }
 }
@@ -66,12 +88,36 @@ impl MultiBindModel {
         self.vertex_array.add_attrib_divisor(&mut self.transform_buffer, 4);
         self.transform_buffer.set_data_mut(model_transforms);
     }
+
+    pub fn get_context(&self) -> &GlContext {
+        &self.gl
+    }
+
+    // Also labels the vertex array and buffers after the model
+    pub fn set_label(&mut self, label: &str) {
+        self.vertex_array.set_label(&format!("{} vertex array", label));
+        self.vertex_buffer.set_label(&format!("{} vertices", label));
+        self.element_buffer.set_label(&format!("{} indices", label));
+        self.transform_buffer.set_label(&format!("{} transforms", label));
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    // Draws the triangles as 3 vertex patches, for programs with tessellation stages
+    pub fn set_draw_patches(&mut self, draw_patches: bool) -> Result<(), GlError> {
+        self.vertex_array.set_patch_vertices(draw_patches.then_some(3))
+    }
 }
 
 // TODO: can simply draw same vertices by providing same offset in each mesh
 // TODO: find a way to make this work with different transforms
 impl ModelTrait for MultiBindModel {
     fn draw(&self, shader_program: &ShaderProgram) -> Result<(), GlError> {
+        let _group = self.gl.debug_group(self.label.as_deref().unwrap_or("MultiBindModel"));
+
         unsafe {
             self.vertex_array.bind();
 
@@ -84,10 +130,11 @@ impl ModelTrait for MultiBindModel {
                 );
     
                 // Set back to defaults once configured
-                gl::ActiveTexture(gl::TEXTURE0);
+                self.gl.active_texture(gl::TEXTURE0);
             }
 
-            gl::BindVertexArray(0);
+            // Left bound, so drawing the same model again doesn't rebind it.
+            // Binds made on get_backend() directly need GlContext::invalidate_state_cache() after
         }
 
         Ok(())
@@ -99,6 +146,9 @@ impl ModelTrait for MultiBindModel {
     fn get_meshes(&self) -> &Vec<Mesh> { &self.meshes }
 }
 
+// Meant to draw every mesh in one glMultiDrawElementsIndirect call with bindless textures, which isn't
+// implemented yet. Until then it draws like MultiBindModel, binding each mesh's textures, so it works on
+// every context and never needs GL_ARB_bindless_texture
 pub struct BindlessModel {
     pub meshes: Vec<Mesh>,
     // TODO: rename these to something more descriptive
@@ -106,23 +156,37 @@ pub struct BindlessModel {
     pub vertex_buffer: Buffer<Vertex>,
     pub element_buffer: Buffer<u32>,
     pub transform_buffer: Buffer<Matrix4<f32>>,
-    pub command_buffer: Buffer<DrawCommand>
+    pub command_buffer: Buffer<DrawCommand>,
+    label: Option<String>,
+    gl: GlContext
 }
 
 impl ModelCreateTrait for BindlessModel {
     fn new(
+        gl: &GlContext,
         mut vertices: Vec<Vertex>,
         mut indices: Vec<u32>,
         model_transforms: Vec<Matrix4<f32>>,
         meshes: Vec<Mesh>
     ) -> Self {
+        let _span = tracing::info_span!(
+            "upload_model",
+            model = "BindlessModel",
+            vertices = vertices.len(),
+            indices = indices.len(),
+            meshes = meshes.len(),
+            instances = model_transforms.len()
+        ).entered();
+
         let mut model = Self {
             meshes,
-            vertex_array: VertexArray::new(),
-            vertex_buffer: Buffer::new(),
-            element_buffer: Buffer::new(),
-            transform_buffer: Buffer::new(),
-            command_buffer: Buffer::new()
+            vertex_array: VertexArray::new(gl),
+            vertex_buffer: Buffer::new(gl),
+            element_buffer: Buffer::new(gl),
+            transform_buffer: Buffer::new(gl),
+            command_buffer: Buffer::new(gl),
+            label: None,
+            gl: gl.clone()
         };
 
         // TODO: generate draw calls and add them
@@ -132,6 +196,8 @@ impl ModelCreateTrait for BindlessModel {
         model.setup_model(vertices, indices);
         model.setup_transform_attribute(model_transforms);
 
+        tracing::debug!("model uploaded");
+
         model
     }
 }
@@ -156,20 +222,41 @@ impl BindlessModel {
         self.vertex_array.add_attrib_divisor(&mut self.transform_buffer, 4);
         self.transform_buffer.set_data_mut(model_transforms);
     }
+
+    pub fn get_context(&self) -> &GlContext {
+        &self.gl
+    }
+
+    // Also labels the vertex array and buffers after the model
+    pub fn set_label(&mut self, label: &str) {
+        self.vertex_array.set_label(&format!("{} vertex array", label));
+        self.vertex_buffer.set_label(&format!("{} vertices", label));
+        self.element_buffer.set_label(&format!("{} indices", label));
+        self.transform_buffer.set_label(&format!("{} transforms", label));
+        self.command_buffer.set_label(&format!("{} commands", label));
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    // Draws the triangles as 3 vertex patches, for programs with tessellation stages
+    pub fn set_draw_patches(&mut self, draw_patches: bool) -> Result<(), GlError> {
+        self.vertex_array.set_patch_vertices(draw_patches.then_some(3))
+    }
 }
 
 impl ModelTrait for BindlessModel {
     // TODO: work on making this work with textures so there is one draw call
     // TODO: Use bindless textures and ubos to do this in one big draw call
-    // TODO: Check if those extensions are supported, if not, just draw
-    // TODO: each mesh individually like normal.
     // TODO: https://litasa.github.io/blog/2017/09/04/OpenGL-MultiDrawIndirect-with-Individual-Textures
-    // Panics if there is no cbo present in the model
+    // Draws each mesh individually like normal until the command buffer is filled
     fn draw(&self, shader_program: &ShaderProgram) -> Result<(), GlError> {
+        let _group = self.gl.debug_group(self.label.as_deref().unwrap_or("BindlessModel"));
+
         unsafe {
             self.vertex_array.bind();
-            // TODO: Generic buffer bind function?
-            gl::BindBuffer(gl::DRAW_INDIRECT_BUFFER, self.command_buffer.get_id());
 
             for mesh in &self.meshes {
                 mesh.set_textures(shader_program)?;
@@ -180,17 +267,59 @@ impl ModelTrait for BindlessModel {
                 );
     
                 // Set back to defaults once configured
-                gl::ActiveTexture(gl::TEXTURE0);
+                self.gl.active_texture(gl::TEXTURE0);
             }
 
-            gl::BindVertexArray(0);
+            // Left bound, so drawing the same model again doesn't rebind it.
+            // Binds made on get_backend() directly need GlContext::invalidate_state_cache() after
         }
 
         Ok(())
     }
 
     fn get_transform_array_mut(&mut self) -> &mut Buffer<Matrix4<f32>> { &mut self.transform_buffer }
-    fn get_transform_array(&self) -> &Buffer<Matrix4<f32>> { &&self.transform_buffer }
+    fn get_transform_array(&self) -> &Buffer<Matrix4<f32>> { &self.transform_buffer }
     fn get_meshes_mut(&mut self) -> &mut Vec<Mesh> { &mut self.meshes }
     fn get_meshes(&self) -> &Vec<Mesh> { &self.meshes }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use cgmath::{Matrix4, SquareMatrix};
+    use crate::{ShaderCodeBundle, ShaderProgram, model_utils::create_quad};
+    use crate::mock_backend::testing::{get_args, mock_context};
+    use super::{ModelTrait, MultiBindModel};
+
+    #[test]
+    fn draw_keeps_vertex_array_bound() {
+        let (mock, gl) = mock_context(|_| ());
+
+        let bundle = ShaderCodeBundle {
+            vertex: Some(String::from("void main() {}")),
+            fragment: Some(String::from("void main() {}")),
+            ..Default::default()
+        };
+        let program = ShaderProgram::new(&gl, bundle).unwrap();
+        let model: MultiBindModel = create_quad(&gl, vec![Matrix4::identity()]);
+        let vao = model.vertex_array.get_id() as i64;
+        mock.clear_calls();
+        gl.reset_state_stats();
+
+        // Drawing the same model again doesn't rebind it
+        model.draw(&program).unwrap();
+        model.draw(&program).unwrap();
+        assert_eq!(get_args(&mock.take_calls(), "BindVertexArray"), [[vao]]);
+        assert_eq!(gl.get_state_stats().vertex_arrays, 1);
+
+        // VertexArray::draw_elements() unbinds through the cache, so the model binds again
+        model.vertex_array.draw_elements(6, 1);
+        model.draw(&program).unwrap();
+        assert_eq!(get_args(&mock.take_calls(), "BindVertexArray"), [[0], [vao]]);
+        assert_eq!(gl.get_state_stats().vertex_arrays, 2);
+
+        // Calls made on the backend directly aren't seen until the cache is invalidated
+        gl.get_backend().bind_vertex_array(0);
+        gl.invalidate_state_cache();
+        model.draw(&program).unwrap();
+        assert_eq!(get_args(&mock.take_calls(), "BindVertexArray"), [[0], [vao]]);
+    }
+}
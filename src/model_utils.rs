@@ -1,8 +1,8 @@
 use cgmath::{Matrix4, Vector3, Vector2, vec2, Zero};
 use crate::{ModelTrait, ModelCreateTrait};
-use super::{Mesh, Vertex};
+use super::{Mesh, Vertex, GlContext};
 
-pub fn create_quad<T: ModelTrait + ModelCreateTrait>(model_transforms: Vec<Matrix4<f32>>) -> T {
+pub fn create_quad<T: ModelTrait + ModelCreateTrait>(gl: &GlContext, model_transforms: Vec<Matrix4<f32>>) -> T {
     // Flat panel definition
     let vertices = vec![
         Vertex {
@@ -36,18 +36,17 @@ pub fn create_quad<T: ModelTrait + ModelCreateTrait>(model_transforms: Vec<Matri
         0, 2, 3
     ];
 
-    let model = T::new(
+    T::new(
+        gl,
         vertices,
         indices,
         model_transforms, 
         vec![Mesh::new(0, 6)]
-    );
-
-    model
+    )
 }
 
 // Calculate lines perpendicular to normals for using normal maps
-pub fn calc_vertex_tangents(vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>) {
+pub fn calc_vertex_tangents(vertices: &mut [Vertex], indices: &mut [u32]) {
     for i in 0..(indices.len() / 3) {
         let index = i * 3;
 
@@ -0,0 +1,26 @@
+use cgmath::{Matrix2, Matrix3, Matrix4, Quaternion, Vector1, Vector2, Vector3, Vector4};
+
+// Plain data that can live in GPU memory the CPU has mapped, like RingBuffer regions.
+// Unsafe because any bytes, including all zeroes, have to be a valid value (no bool, references or enums).
+// Padding is allowed, it is never read as a value
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($type_:ty),*) => {
+        $(unsafe impl Pod for $type_ {})*
+    };
+}
+
+impl_pod!(u8, i8, u16, i16, u32, i32, u64, i64, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+// cgmath types are #[repr(C)]
+unsafe impl<T: Pod> Pod for Vector1<T> {}
+unsafe impl<T: Pod> Pod for Vector2<T> {}
+unsafe impl<T: Pod> Pod for Vector3<T> {}
+unsafe impl<T: Pod> Pod for Vector4<T> {}
+unsafe impl<T: Pod> Pod for Matrix2<T> {}
+unsafe impl<T: Pod> Pod for Matrix3<T> {}
+unsafe impl<T: Pod> Pod for Matrix4<T> {}
+unsafe impl<T: Pod> Pod for Quaternion<T> {}
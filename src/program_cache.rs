@@ -0,0 +1,161 @@
+use std::{fs, io, path::{Path, PathBuf}};
+use super::{GlError, GlContext, GlFeature, ShaderCodeBundle, ShaderProgram, gl::{self, types::GLenum}};
+
+const MAGIC: &[u8; 4] = b"SGLB";
+// Magic, driver hash and binary format
+const HEADER_LEN: usize = 4 + 8 + 4;
+
+// Keeps linked programs on disk with glGetProgramBinary, so later runs can skip compiling them.
+// Entries are named after a hash of the sources, which covers defines from ShaderLoader and SPIR-V
+// specialization. They remember the driver they came from: a driver update or a rejected binary replaces the entry
+pub struct ProgramCache {
+    directory: PathBuf,
+    // Vendor, renderer and version strings, binaries only load on the driver that made them
+    driver_hash: u64,
+    // Contexts without binary formats compile every time
+    enabled: bool
+}
+
+impl ProgramCache {
+    pub fn new<P: Into<PathBuf>>(gl: &GlContext, directory: P) -> ProgramCache {
+        let enabled = gl.supports(GlFeature::ProgramBinary) && gl.get_integer_v(gl::NUM_PROGRAM_BINARY_FORMATS) > 0;
+
+        let mut driver_hash = Fnv1a::new();
+        for name in [gl::VENDOR, gl::RENDERER, gl::VERSION] {
+            driver_hash.write(gl.get_string(name).as_bytes());
+        }
+
+        ProgramCache { directory: directory.into(), driver_hash: driver_hash.finish(), enabled }
+    }
+
+    // Same as ShaderProgram::new(), but loads the program from the cache when it can.
+    // Cache errors are only logged, the program is compiled from source instead
+    pub fn create_program(&self, gl: &GlContext, shader_bundle: ShaderCodeBundle) -> Result<ShaderProgram, GlError> {
+        if !self.enabled {
+            return ShaderProgram::new(gl, shader_bundle);
+        }
+
+        let path = self.get_path(&shader_bundle);
+        let source = shader_bundle.name.clone().unwrap_or_else(|| String::from("unnamed"));
+        let mut shader_program = ShaderProgram::empty(gl, true);
+
+        match self.read_entry(&path) {
+            Ok(Some((format, binary))) => {
+                if shader_program.load_binary(&shader_bundle, format, &binary) {
+                    tracing::debug!(source = source.as_str(), path = %path.display(), "program loaded from cache");
+                    return Ok(shader_program);
+                }
+
+                tracing::info!(source = source.as_str(), path = %path.display(), "cached program was rejected by the driver");
+                if let Err(err) = fs::remove_file(&path) {
+                    tracing::warn!(source = source.as_str(), path = %path.display(), "could not delete cached program: {}", err);
+                }
+            },
+            Ok(None) => (),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+            Err(err) => tracing::warn!(source = source.as_str(), path = %path.display(), "could not read cached program: {}", err)
+        }
+
+        shader_program.compile_program(shader_bundle)?;
+
+        let (format, binary) = gl.get_program_binary(shader_program.get_id());
+        if !binary.is_empty() {
+            if let Err(err) = self.write_entry(&path, format, &binary) {
+                tracing::warn!(source = source.as_str(), path = %path.display(), "could not write cached program: {}", err);
+            }
+        }
+
+        Ok(shader_program)
+    }
+
+    // Deletes every entry, including those of other drivers
+    pub fn clear(&self) -> Result<(), GlError> {
+        match fs::read_dir(&self.directory) {
+            Ok(entries) => {
+                for entry in entries {
+                    let path = entry?.path();
+                    if path.extension().is_some_and(|extension| extension == "bin") {
+                        fs::remove_file(path)?;
+                    }
+                }
+
+                Ok(())
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into())
+        }
+    }
+
+    fn get_path(&self, shader_bundle: &ShaderCodeBundle) -> PathBuf {
+        let mut hash = Fnv1a::new();
+        for (index, (code, _)) in shader_bundle.get_vec().into_iter().enumerate() {
+            if let Some(code) = code {
+                hash.write(&[index as u8]);
+                hash.write(&code.len().to_le_bytes());
+                hash.write(code.as_bytes());
+            }
+        }
+
+        for (type_, module) in shader_bundle.spirv.iter() {
+            hash.write(type_.to_string().as_bytes());
+            hash.write(&module.code.len().to_le_bytes());
+            hash.write(&module.code);
+            hash.write(module.entry_point.as_bytes());
+            for (constant_id, value) in module.specialization_constants.iter() {
+                hash.write(&constant_id.to_le_bytes());
+                hash.write(&value.to_le_bytes());
+            }
+        }
+
+        self.directory.join(format!("{:016x}.bin", hash.finish()))
+    }
+
+    // None if the entry is from another driver or unreadable, it is then overwritten
+    fn read_entry(&self, path: &Path) -> io::Result<Option<(GLenum, Vec<u8>)>> {
+        let mut entry = fs::read(path)?;
+
+        if entry.len() <= HEADER_LEN
+            || &entry[0..4] != MAGIC
+            || u64::from_le_bytes(entry[4..12].try_into().unwrap()) != self.driver_hash {
+            return Ok(None);
+        }
+
+        let format = u32::from_le_bytes(entry[12..16].try_into().unwrap());
+        Ok(Some((format, entry.split_off(HEADER_LEN))))
+    }
+
+    // Written next to the entry and renamed, so a crash can't leave half an entry
+    fn write_entry(&self, path: &Path, format: GLenum, binary: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.directory)?;
+
+        let mut entry = Vec::with_capacity(HEADER_LEN + binary.len());
+        entry.extend_from_slice(MAGIC);
+        entry.extend_from_slice(&self.driver_hash.to_le_bytes());
+        entry.extend_from_slice(&format.to_le_bytes());
+        entry.extend_from_slice(binary);
+
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, entry)?;
+        fs::rename(temp_path, path)
+    }
+}
+
+// Unlike DefaultHasher, stays the same between Rust versions, so entries outlive a toolchain update
+struct Fnv1a(u64);
+
+impl Fnv1a {
+    fn new() -> Fnv1a {
+        Fnv1a(0xcbf29ce484222325)
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= *byte as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
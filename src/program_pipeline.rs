@@ -0,0 +1,246 @@
+use std::rc::Rc;
+use super::{GlError, GlContext, GlFeature, InterfaceVariableInfo, ObjectName, ShaderCompileType, ShaderProgram};
+use super::{UniformKey, UniformValue, get_glsl_type_name, gl};
+
+// Pipeline order, compute can only be used on its own
+const STAGES: [ShaderCompileType; 6] = [
+    ShaderCompileType::Vertex,
+    ShaderCompileType::TessControl,
+    ShaderCompileType::TessEvaluation,
+    ShaderCompileType::Geometry,
+    ShaderCompileType::Fragment,
+    ShaderCompileType::Compute
+];
+
+// Combines stages of separable programs (ShaderProgram::new_separable) without linking them together,
+// so one vertex program can be used with many fragment programs. Programs are shared through Rc.
+// A recompiled program has to be set again with use_program_stages()
+pub struct ProgramPipeline {
+    id: u32,
+    label: Option<String>,
+    // Indexed like STAGES
+    stages: [Option<Rc<ShaderProgram>>; 6],
+    gl: GlContext
+}
+
+impl ProgramPipeline {
+    pub fn new(gl: &GlContext) -> Result<ProgramPipeline, GlError> {
+        gl.require(GlFeature::SeparateShaderObjects)?;
+
+        Ok(ProgramPipeline {
+            id: gl.create_program_pipeline(),
+            label: None,
+            stages: Default::default(),
+            gl: gl.clone()
+        })
+    }
+
+    // Uses every stage the program has, replacing the programs previously used for them
+    pub fn use_program_stages(&mut self, program: Rc<ShaderProgram>) -> Result<(), GlError> {
+        if !program.is_separable() {
+            return Err(GlError::InvalidPipeline(
+                self.get_object_name(),
+                format!("Program {} was not created with ShaderProgram::new_separable()", program.get_object_name())
+            ));
+        }
+
+        let mut stage_bits = 0;
+        for type_ in program.get_stages() {
+            if let Some(index) = get_index(type_) {
+                stage_bits |= type_.get_stage_bit();
+                self.stages[index] = Some(program.clone());
+            }
+        }
+
+        self.gl.use_program_stages(self.id, stage_bits, program.get_id());
+
+        Ok(())
+    }
+
+    pub fn remove_stage(&mut self, type_: ShaderCompileType) -> Result<(), GlError> {
+        let index = get_index(&type_).ok_or_else(|| GlError::InvalidPipeline(
+            self.get_object_name(),
+            format!("{} is not a pipeline stage", type_)
+        ))?;
+
+        self.gl.use_program_stages(self.id, type_.get_stage_bit(), 0);
+        self.stages[index] = None;
+
+        Ok(())
+    }
+
+    // None for ShaderCompileType::Program, which isn't a stage
+    pub fn get_program(&self, type_: ShaderCompileType) -> Option<&Rc<ShaderProgram>> {
+        self.stages[get_index(&type_)?].as_ref()
+    }
+
+    // A bound program takes priority over the pipeline, so none is
+    pub fn bind(&self) {
+        self.gl.use_program(0);
+        self.gl.bind_program_pipeline(self.id);
+    }
+
+    // Binds the pipeline and makes the program of `type_` the one glUniform calls go to,
+    // so any ShaderProgram setter can be used on the returned program
+    pub fn set_active_program(&self, type_: ShaderCompileType) -> Result<&ShaderProgram, GlError> {
+        let program = self.get_program(type_.clone()).ok_or_else(|| GlError::InvalidPipeline(
+            self.get_object_name(),
+            format!("{} stage is not set", type_)
+        ))?;
+
+        self.bind();
+        self.gl.active_shader_program(self.id, program.get_id());
+
+        Ok(program)
+    }
+
+    // Sets a uniform on the program used for one stage
+    pub fn set<U: UniformKey, T: UniformValue + ?Sized>(
+        &self,
+        type_: ShaderCompileType,
+        uniform: U,
+        value: &T
+    ) -> Result<(), GlError> {
+        self.set_active_program(type_)?.set(uniform, value)
+    }
+
+    // Checks that each stage's inputs are written by the stage before it, with the same type,
+    // then asks the driver to validate the pipeline against the current state.
+    // Stages are matched by location when the input has one, otherwise by name
+    pub fn validate(&self) -> Result<(), GlError> {
+        // Compute has no interface with the other stages
+        let stages: Vec<(&ShaderCompileType, &Rc<ShaderProgram>)> = STAGES[..5].iter()
+            .zip(self.stages.iter())
+            .filter_map(|(type_, program)| program.as_ref().map(|program| (type_, program)))
+            .collect();
+
+        for pair in stages.windows(2) {
+            let ((producer_type, producer), (consumer_type, consumer)) = (pair[0], pair[1]);
+
+            // Stages linked into the same program were checked by the linker
+            if !Rc::ptr_eq(producer, consumer) {
+                self.check_interface(producer_type, producer, consumer_type, consumer)?;
+            }
+        }
+
+        self.gl.validate_program_pipeline(self.id);
+        if self.gl.get_program_pipeline_iv(self.id, gl::VALIDATE_STATUS) != gl::TRUE as i32 {
+            return Err(GlError::InvalidPipeline(self.get_object_name(), self.gl.get_program_pipeline_info_log(self.id)));
+        }
+
+        Ok(())
+    }
+
+    fn check_interface(
+        &self,
+        producer_type: &ShaderCompileType,
+        producer: &ShaderProgram,
+        consumer_type: &ShaderCompileType,
+        consumer: &ShaderProgram
+    ) -> Result<(), GlError> {
+        let outputs = &producer.get_reflection().outputs;
+
+        for input in consumer.get_reflection().inputs.iter().filter(|input| !input.name.starts_with("gl_")) {
+            let output = match input.location {
+                -1 => outputs.iter().find(|output| get_base_name(&output.name) == get_base_name(&input.name)),
+                location => outputs.iter().find(|output| output.location == location)
+            };
+
+            let error = match output {
+                None => format!(
+                    "{} input {} is not written by the {} stage",
+                    consumer_type, get_variable_name(input), producer_type
+                ),
+                // Inputs of tessellation and geometry stages are arrays of what the stage before writes
+                Some(output) if output.type_ != input.type_ => format!(
+                    "{} input {} is {} but the {} output {} is {}",
+                    consumer_type, get_variable_name(input), get_type_name(input),
+                    producer_type, get_variable_name(output), get_type_name(output)
+                ),
+                Some(_) => continue
+            };
+
+            return Err(GlError::InvalidPipeline(self.get_object_name(), error));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::PROGRAM_PIPELINE, self.id, label);
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    fn get_object_name(&self) -> ObjectName {
+        ObjectName::new(self.id, &self.label)
+    }
+}
+
+impl Drop for ProgramPipeline {
+    fn drop(&mut self) {
+        self.gl.delete_program_pipeline(self.id);
+    }
+}
+
+fn get_index(type_: &ShaderCompileType) -> Option<usize> {
+    STAGES.iter().position(|stage| stage == type_)
+}
+
+// Per-vertex arrays are reported as name[0]
+fn get_base_name(name: &str) -> &str {
+    name.strip_suffix("[0]").unwrap_or(name)
+}
+
+fn get_variable_name(variable: &InterfaceVariableInfo) -> String {
+    match variable.location {
+        -1 => format!("'{}'", get_base_name(&variable.name)),
+        location => format!("'{}' (location {})", get_base_name(&variable.name), location)
+    }
+}
+
+fn get_type_name(variable: &InterfaceVariableInfo) -> String {
+    get_glsl_type_name(variable.type_).map_or_else(|| format!("GL type 0x{:X}", variable.type_), str::to_owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::{GlError, ShaderCodeBundle, ShaderCompileType, ShaderProgram, gl, mock_backend::testing::mock_context};
+    use super::ProgramPipeline;
+
+    #[test]
+    fn program_pipeline_has_no_program_stage() {
+        let (mock, gl) = mock_context(|_| ());
+
+        let bundle = ShaderCodeBundle {
+            vertex: Some(String::from("void main() {}")),
+            ..Default::default()
+        };
+        let program = Rc::new(ShaderProgram::new_separable(&gl, bundle).unwrap());
+        let mut pipeline = ProgramPipeline::new(&gl).unwrap();
+        pipeline.use_program_stages(program).unwrap();
+        mock.clear_calls();
+
+        // Program used to share the vertex slot
+        assert!(pipeline.get_program(ShaderCompileType::Vertex).is_some());
+        assert!(pipeline.get_program(ShaderCompileType::Program).is_none());
+        assert!(matches!(pipeline.remove_stage(ShaderCompileType::Program), Err(GlError::InvalidPipeline(..))));
+        assert!(pipeline.get_program(ShaderCompileType::Vertex).is_some());
+        assert_eq!(mock.count("UseProgramStages"), 0);
+
+        pipeline.remove_stage(ShaderCompileType::Vertex).unwrap();
+        assert!(pipeline.get_program(ShaderCompileType::Vertex).is_none());
+        assert_eq!(
+            mock.find("UseProgramStages").unwrap().args,
+            [pipeline.get_id() as i64, gl::VERTEX_SHADER_BIT as i64, 0]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use super::{GlError, GlBackend, ShaderProgram, gl::{self, types::GLenum}};
+
+// Active uniform, as reported by the driver when the program was linked
+#[derive(Debug, Clone)]
+pub struct UniformInfo {
+    // Arrays of basic types end with [0]
+    pub name: String,
+    // -1 for uniforms inside a block
+    pub location: i32,
+    // GL_FLOAT_VEC3, GL_SAMPLER_2D, etc.
+    pub type_: GLenum,
+    // 1 if not an array
+    pub array_size: i32,
+    // -1 for uniforms outside of a block
+    pub block_index: i32,
+    // Byte offset inside the block, -1 outside of one
+    pub offset: i32
+}
+
+#[derive(Debug, Clone)]
+pub struct UniformBlockInfo {
+    pub name: String,
+    pub index: u32,
+    pub binding: i32,
+    pub data_size: i32
+}
+
+// Input of the program's first stage or output of its last one
+#[derive(Debug, Clone)]
+pub struct InterfaceVariableInfo {
+    pub name: String,
+    // -1 without layout(location)
+    pub location: i32,
+    pub type_: GLenum,
+    pub array_size: i32
+}
+
+// Everything the program exposes through uniforms and stage interfaces, queried once at link time
+#[derive(Debug, Clone, Default)]
+pub struct ProgramReflection {
+    pub uniforms: Vec<UniformInfo>,
+    pub uniform_blocks: Vec<UniformBlockInfo>,
+    pub inputs: Vec<InterfaceVariableInfo>,
+    pub outputs: Vec<InterfaceVariableInfo>
+}
+
+impl ProgramReflection {
+    pub(crate) fn query(backend: &dyn GlBackend, program: u32) -> ProgramReflection {
+        let uniform_count = backend.get_program_interface_iv(program, gl::UNIFORM, gl::ACTIVE_RESOURCES);
+        let uniform_props = [gl::TYPE, gl::ARRAY_SIZE, gl::LOCATION, gl::BLOCK_INDEX, gl::OFFSET];
+
+        let uniforms = (0..uniform_count.max(0) as u32)
+            .map(|index| {
+                let values = backend.get_program_resource_iv(program, gl::UNIFORM, index, &uniform_props);
+
+                UniformInfo {
+                    name: backend.get_program_resource_name(program, gl::UNIFORM, index),
+                    location: values[2],
+                    type_: values[0] as GLenum,
+                    array_size: values[1],
+                    block_index: values[3],
+                    offset: values[4]
+                }
+            })
+            .collect();
+
+        let block_count = backend.get_program_interface_iv(program, gl::UNIFORM_BLOCK, gl::ACTIVE_RESOURCES);
+        let block_props = [gl::BUFFER_BINDING, gl::BUFFER_DATA_SIZE];
+
+        let uniform_blocks = (0..block_count.max(0) as u32)
+            .map(|index| {
+                let values = backend.get_program_resource_iv(program, gl::UNIFORM_BLOCK, index, &block_props);
+
+                UniformBlockInfo {
+                    name: backend.get_program_resource_name(program, gl::UNIFORM_BLOCK, index),
+                    index,
+                    binding: values[0],
+                    data_size: values[1]
+                }
+            })
+            .collect();
+
+        ProgramReflection {
+            uniforms,
+            uniform_blocks,
+            inputs: query_interface(backend, program, gl::PROGRAM_INPUT),
+            outputs: query_interface(backend, program, gl::PROGRAM_OUTPUT)
+        }
+    }
+
+    // Arrays can be found with or without [0]
+    pub fn get_uniform(&self, name: &str) -> Option<&UniformInfo> {
+        self.uniforms.iter().find(|uniform| {
+            uniform.name == name || uniform.name.strip_suffix("[0]") == Some(name)
+        })
+    }
+
+    pub fn get_uniform_block(&self, name: &str) -> Option<&UniformBlockInfo> {
+        self.uniform_blocks.iter().find(|block| block.name == name)
+    }
+}
+
+fn query_interface(backend: &dyn GlBackend, program: u32, interface: GLenum) -> Vec<InterfaceVariableInfo> {
+    let count = backend.get_program_interface_iv(program, interface, gl::ACTIVE_RESOURCES);
+    let props = [gl::TYPE, gl::ARRAY_SIZE, gl::LOCATION];
+
+    (0..count.max(0) as u32)
+        .map(|index| {
+            let values = backend.get_program_resource_iv(program, interface, index, &props);
+
+            InterfaceVariableInfo {
+                name: backend.get_program_resource_name(program, interface, index),
+                location: values[2],
+                type_: values[0] as GLenum,
+                array_size: values[1]
+            }
+        })
+        .collect()
+}
+
+// Gives every ShaderProgram a key, so handles can't be used on the wrong program
+static NEXT_PROGRAM_KEY: AtomicU32 = AtomicU32::new(1);
+
+pub(crate) fn next_program_key() -> u32 {
+    NEXT_PROGRAM_KEY.fetch_add(1, Ordering::Relaxed)
+}
+
+// Pre-resolved uniform from ShaderProgram::get_uniform(), setting through it does no string work.
+// Stays valid when the program is recompiled (hot reload), but only for the program it came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Uniform {
+    pub(crate) program_key: u32,
+    pub(crate) slot: usize,
+    pub(crate) element: usize
+}
+
+impl Uniform {
+    // Element of a uniform array, the index is relative to the element the handle points at
+    pub fn at(self, index: usize) -> Uniform {
+        Uniform { element: self.element + index, ..self }
+    }
+}
+
+// What the ShaderProgram setters accept: a name, a (name, array index) pair or a Uniform.
+// Names are looked up in the program's location cache, so they are only sent to GL the first time
+pub trait UniformKey {
+    fn to_uniform(&self, shader_program: &ShaderProgram) -> Result<Uniform, GlError>;
+}
+
+impl UniformKey for &str {
+    fn to_uniform(&self, shader_program: &ShaderProgram) -> Result<Uniform, GlError> {
+        Ok(shader_program.find_uniform(self))
+    }
+}
+
+impl UniformKey for (&str, usize) {
+    fn to_uniform(&self, shader_program: &ShaderProgram) -> Result<Uniform, GlError> {
+        Ok(shader_program.find_uniform(self.0).at(self.1))
+    }
+}
+
+impl UniformKey for Uniform {
+    fn to_uniform(&self, shader_program: &ShaderProgram) -> Result<Uniform, GlError> {
+        if self.program_key != shader_program.get_key() {
+            return Err(GlError::UniformFromOtherProgram(shader_program.get_object_name()));
+        }
+
+        Ok(*self)
+    }
+}
@@ -1,32 +1,32 @@
-use super::gl;
+use super::{GlContext, gl};
 
 pub struct RenderBuffer {
-    id: u32
+    id: u32,
+    label: Option<String>,
+    gl: GlContext
 }
 
 impl RenderBuffer {
     // Requires framebuffer to be bound
-    pub fn new(width: i32, height: i32) -> RenderBuffer {
-        let mut renderbuffer = RenderBuffer {
-            id: 0
+    pub fn new(gl: &GlContext, width: i32, height: i32) -> RenderBuffer {
+        let renderbuffer = RenderBuffer {
+            id: gl.create_renderbuffer(),
+            label: None,
+            gl: gl.clone()
         };
 
-        unsafe {
-            // Create renderbuffer
-            gl::CreateRenderbuffers(1, &mut renderbuffer.id);
-            gl::NamedRenderbufferStorage(
-                renderbuffer.id,
-                gl::DEPTH24_STENCIL8,
-                width,
-                height
-            );
-        }
+        renderbuffer.gl.named_renderbuffer_storage(
+            renderbuffer.id,
+            gl::DEPTH24_STENCIL8,
+            width,
+            height
+        );
 
         renderbuffer
     }
 
     pub unsafe fn resize(&self, width: i32, height: i32) {
-        gl::NamedRenderbufferStorage(
+        self.gl.named_renderbuffer_storage(
             self.id,
             gl::DEPTH24_STENCIL8,
             width,
@@ -37,12 +37,19 @@ impl RenderBuffer {
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::RENDERBUFFER, self.id, label);
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
 }
 
 impl Drop for RenderBuffer {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteRenderbuffers(1, &self.id);
-        }
+        self.gl.delete_renderbuffer(self.id);
     }
 }
\ No newline at end of file
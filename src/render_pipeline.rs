@@ -1,6 +1,6 @@
 use std::rc::Rc;
 
-use super::{GlError, Framebuffer, Texture, Mesh};
+use super::{GlError, GlContext, Framebuffer, Texture, Mesh};
 
 pub trait RenderPipeline {
     fn bind(&self);
@@ -16,6 +16,26 @@ pub trait RenderPipeline {
     fn link_push(&mut self, texture: Rc<Texture>) -> Result<(), GlError>;
     fn unlink(&mut self);
 
+    // Like the models, pipelines with no label are grouped under their type name
+    fn get_label(&self) -> Option<&str> {
+        None
+    }
+
+    // Same as draw(), but grouped under the pipeline's label in frame debuggers
+    fn draw_in_group(&mut self, gl: &GlContext) -> Result<(), GlError> {
+        let label = match self.get_label() {
+            Some(label) => label.to_owned(),
+            None => {
+                // Path and generics are left out, e.g. "DeferredPipeline"
+                let name = std::any::type_name::<Self>().split('<').next().unwrap_or_default();
+                name.rsplit("::").next().unwrap_or_default().to_owned()
+            }
+        };
+
+        let _group = gl.debug_group(&label);
+        self.draw()
+    }
+
     // These are here to simplify and expand usage of render pipeline
 
     // render_pipeline output -> self input
@@ -27,9 +47,79 @@ pub trait RenderPipeline {
     }
     fn link_to_mesh(&mut self, mesh: &Mesh) -> Result<(), GlError> {
         for texture in mesh.diffuse_textures.iter() {
-            self.link_push(Rc::clone(&texture))?;
+            self.link_push(Rc::clone(texture))?;
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::{Framebuffer, GlError, Texture, mock_backend::testing::{get_names, mock_context}};
+    use super::RenderPipeline;
+
+    struct BlurPipeline {
+        framebuffer: Framebuffer,
+        label: Option<String>
+    }
+
+    impl RenderPipeline for BlurPipeline {
+        fn bind(&self) {}
+
+        fn draw(&mut self) -> Result<(), GlError> {
+            self.framebuffer.check_status()
+        }
+
+        fn get_height(&self) -> (i32, i32) {
+            (0, 0)
+        }
+
+        fn set_size(&mut self, _width: i32, _height: i32) -> Result<(), GlError> {
+            Ok(())
+        }
+
+        fn get_link(&self) -> Result<Vec<Rc<Texture>>, GlError> {
+            Ok(self.framebuffer.get_link())
+        }
+
+        fn link_to(&mut self, _output: Vec<Rc<Texture>>) -> Result<(), GlError> {
+            Ok(())
+        }
+
+        fn link_push(&mut self, _texture: Rc<Texture>) -> Result<(), GlError> {
+            Ok(())
+        }
+
+        fn unlink(&mut self) {}
+
+        fn get_label(&self) -> Option<&str> {
+            self.label.as_deref()
+        }
+    }
+
+    #[test]
+    fn render_pipeline_draws_in_group() {
+        let (mock, gl) = mock_context(|_| ());
+
+        let mut pipeline = BlurPipeline {
+            framebuffer: Framebuffer::new(&gl, 800, 600, 1, false).unwrap(),
+            label: None
+        };
+        mock.clear_calls();
+
+        // draw() itself stays ungrouped
+        pipeline.draw().unwrap();
+        assert_eq!(get_names(&mock.take_calls()), ["CheckNamedFramebufferStatus"]);
+
+        pipeline.draw_in_group(&gl).unwrap();
+        let calls = mock.take_calls();
+        assert_eq!(get_names(&calls), ["PushDebugGroup", "CheckNamedFramebufferStatus", "PopDebugGroup"]);
+        assert_eq!(calls[0].text.as_deref(), Some("BlurPipeline"));
+
+        pipeline.label = Some(String::from("Bloom"));
+        pipeline.draw_in_group(&gl).unwrap();
+        assert_eq!(mock.find("PushDebugGroup").unwrap().text.as_deref(), Some("Bloom"));
+    }
+}
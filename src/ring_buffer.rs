@@ -0,0 +1,329 @@
+use std::time::Duration;
+use super::{GlError, GlContext, GlFeature, ObjectName, Pod, gl::{self, types::{GLenum, GLsync}}};
+
+// Buffer written through a persistent, coherent mapping instead of glBufferSubData, split into regions
+// that are used in turn, e.g. one per frame. Moving to the next region fences the commands issued so far,
+// and a region is only handed out again once the GPU has passed its fence. So writes never change data
+// a draw in flight is reading, and only wait when the CPU gets more than `regions - 1` frames ahead
+pub struct RingBuffer<T: Pod> {
+    id: u32,
+    data: *mut T,
+    region_len: usize,
+    // Bytes between the starts of regions, aligned so each region can be bound as a uniform or storage buffer
+    region_size: usize,
+    // Null for regions the GPU isn't using
+    fences: Vec<GLsync>,
+    current: usize,
+    // Whether the current region was given out for writing, only those regions are fenced
+    handed_out: bool,
+    timeout: Duration,
+    stalls: u32,
+    label: Option<String>,
+    gl: GlContext
+}
+
+impl<T: Pod> RingBuffer<T> {
+    // `region_len` is in elements of T. Three regions let the CPU work on a frame while the GPU draws the two before it.
+    // Both have to be at least 1
+    pub fn new(gl: &GlContext, region_len: usize, regions: usize) -> Result<RingBuffer<T>, GlError> {
+        gl.require(GlFeature::PersistentMapping)?;
+
+        if region_len == 0 || regions == 0 || std::mem::size_of::<T>() == 0 {
+            return Err(GlError::InvalidBufferSize(
+                format!("a ring buffer needs non-empty regions, got {} regions of {} elements", regions, region_len)
+            ));
+        }
+
+        let mut align = std::mem::align_of::<T>().max(gl.get_integer_v(gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT) as usize);
+        if gl.supports(GlFeature::ShaderStorageBuffer) {
+            align = align.max(gl.get_integer_v(gl::SHADER_STORAGE_BUFFER_OFFSET_ALIGNMENT) as usize);
+        }
+
+        let region_size = region_len.checked_mul(std::mem::size_of::<T>())
+            .and_then(|bytes| bytes.checked_next_multiple_of(align));
+        let size = region_size
+            .and_then(|region_size| region_size.checked_mul(regions))
+            .and_then(|size| isize::try_from(size).ok());
+
+        let (Some(region_size), Some(size)) = (region_size, size) else {
+            return Err(GlError::InvalidBufferSize(
+                format!("{} regions of {} elements don't fit in a buffer", regions, region_len)
+            ));
+        };
+        let flags = gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT;
+
+        let id = gl.create_buffer();
+        // Zeroed so the mapping never holds uninitialised bytes
+        let zeroes = vec![0u8; size as usize];
+
+        let data = unsafe {
+            gl.named_buffer_storage(id, size, zeroes.as_ptr() as *const _, flags);
+            gl.map_named_buffer_range(id, 0, size, flags) as *mut T
+        };
+
+        if data.is_null() || !data.is_aligned() {
+            gl.delete_buffer(id);
+            return Err(GlError::BufferMapFailed(ObjectName::new(id, &None)));
+        }
+
+        Ok(RingBuffer {
+            id,
+            data,
+            region_len,
+            region_size,
+            fences: vec![std::ptr::null(); regions],
+            current: 0,
+            handed_out: false,
+            timeout: Duration::from_secs(1),
+            stalls: 0,
+            label: None,
+            gl: gl.clone()
+        })
+    }
+
+    // Fences the current region after the commands that use it, then waits until the GPU is done with the
+    // next one and makes it current. Call once per frame, before writing. The first call hands out the first
+    // region, unless get_region_mut() already did. If the wait times out, the current region stays the same
+    // and the call can be made again
+    pub fn next_region(&mut self) -> Result<&mut [T], GlError> {
+        if self.handed_out {
+            let previous = std::mem::replace(&mut self.fences[self.current], self.gl.fence_sync(gl::SYNC_GPU_COMMANDS_COMPLETE, 0));
+            if !previous.is_null() {
+                unsafe { self.gl.delete_sync(previous) };
+            }
+
+            let next = (self.current + 1) % self.fences.len();
+            self.wait(next)?;
+            self.current = next;
+        }
+
+        Ok(self.get_region_mut())
+    }
+
+    fn wait(&mut self, region: usize) -> Result<(), GlError> {
+        let fence = self.fences[region];
+        if fence.is_null() {
+            return Ok(());
+        }
+
+        let mut status = unsafe { self.gl.client_wait_sync(fence, 0, 0) };
+        if status == gl::TIMEOUT_EXPIRED {
+            self.stalls += 1;
+            // Without the flush, the fence might never reach the GPU
+            status = unsafe { self.gl.client_wait_sync(fence, gl::SYNC_FLUSH_COMMANDS_BIT, self.timeout.as_nanos() as u64) };
+        }
+
+        let error = match status {
+            gl::ALREADY_SIGNALED | gl::CONDITION_SATISFIED => None,
+            // The fence is kept so the next call waits for it again
+            gl::TIMEOUT_EXPIRED => return Err(self.wait_error(status)),
+            _ => Some(self.wait_error(status))
+        };
+
+        unsafe { self.gl.delete_sync(fence) };
+        self.fences[region] = std::ptr::null();
+
+        error.map_or(Ok(()), Err)
+    }
+
+    fn wait_error(&self, status: GLenum) -> GlError {
+        let reason = match status {
+            gl::TIMEOUT_EXPIRED => format!("timed out after {:?}", self.timeout),
+            _ => String::from("glClientWaitSync failed")
+        };
+
+        GlError::FenceWaitFailed(ObjectName::new(self.id, &self.label), reason)
+    }
+
+    // The region to write this frame. The GPU sees writes to it in commands issued after them.
+    // The mapping is only meant for writing, reading from it is slow
+    pub fn get_region_mut(&mut self) -> &mut [T] {
+        self.handed_out = true;
+
+        // The mapping lives as long as the buffer, regions don't overlap and
+        // the GPU is done with this one, so nothing else can access it
+        unsafe {
+            std::slice::from_raw_parts_mut(self.data.byte_add(self.current * self.region_size), self.region_len)
+        }
+    }
+
+    // Binds the current region to an indexed target, like GL_UNIFORM_BUFFER
+    pub fn bind_region(&self, target: GLenum, index: u32) {
+        self.gl.bind_buffer_range(
+            target,
+            index,
+            self.id,
+            self.get_region_offset() as isize,
+            (self.region_len * std::mem::size_of::<T>()) as isize
+        );
+    }
+
+    // In bytes, for vertex buffer, draw and binding offsets
+    pub fn get_region_offset(&self) -> usize {
+        self.current * self.region_size
+    }
+
+    pub fn get_current_region(&self) -> usize {
+        self.current
+    }
+
+    pub fn get_region_len(&self) -> usize {
+        self.region_len
+    }
+
+    pub fn get_regions(&self) -> usize {
+        self.fences.len()
+    }
+
+    // How long next_region() waits for the GPU before giving up, 1 second by default
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    // How many next_region() calls had to wait for the GPU, if this keeps growing more regions are needed
+    pub fn get_stalls(&self) -> u32 {
+        self.stalls
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::BUFFER, self.id, label);
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+impl<T: Pod> Drop for RingBuffer<T> {
+    fn drop(&mut self) {
+        for fence in self.fences.iter().filter(|fence| !fence.is_null()) {
+            unsafe { self.gl.delete_sync(*fence) };
+        }
+
+        self.gl.unmap_named_buffer(self.id);
+        self.gl.delete_buffer(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use crate::{GlContext, GlError, MockGl, gl, mock_backend::testing::{get_names, mock_context}};
+    use super::RingBuffer;
+
+    fn ring_buffer_context() -> (Rc<MockGl>, GlContext) {
+        mock_context(|mock| {
+            mock.set_integer(gl::UNIFORM_BUFFER_OFFSET_ALIGNMENT, 256);
+            mock.set_integer(gl::SHADER_STORAGE_BUFFER_OFFSET_ALIGNMENT, 64);
+        })
+    }
+
+    #[test]
+    fn ring_buffer_rotates_regions() {
+        let (mock, gl) = ring_buffer_context();
+        let flags = (gl::MAP_WRITE_BIT | gl::MAP_PERSISTENT_BIT | gl::MAP_COHERENT_BIT) as i64;
+
+        // 40 bytes per region, padded to the uniform buffer alignment
+        let mut ring = RingBuffer::<f32>::new(&gl, 10, 3).unwrap();
+        let id = ring.get_id() as i64;
+        assert_eq!(mock.find("NamedBufferStorage").unwrap().args, [id, 768, flags]);
+        assert_eq!(mock.find("MapNamedBufferRange").unwrap().args, [id, 0, 768, flags]);
+        mock.clear_calls();
+
+        let mut fences = Vec::new();
+        for (frame, expected) in [0, 1, 2, 0, 1].into_iter().enumerate() {
+            let region = ring.next_region().unwrap();
+            assert_eq!(region.len(), 10);
+            region[9] = expected as f32;
+
+            assert_eq!(ring.get_current_region(), expected);
+            assert_eq!(ring.get_region_offset(), expected * 256);
+
+            let calls = mock.take_calls();
+            match frame {
+                // Nothing was written before the first region, so there is nothing to fence
+                0 => assert!(calls.is_empty()),
+                // Regions only wait for a fence once they come around again
+                1 | 2 => assert_eq!(get_names(&calls), ["FenceSync"]),
+                _ => {
+                    let fence = fences[fences.len() - 2];
+                    assert_eq!(get_names(&calls), ["FenceSync", "ClientWaitSync", "DeleteSync"]);
+                    assert_eq!(calls[1].args, [fence, 0, 0]);
+                    assert_eq!(calls[2].args, [fence]);
+                }
+            }
+            if frame > 0 {
+                fences.push(calls[0].args[2]);
+            }
+        }
+        assert_eq!(ring.get_stalls(), 0);
+
+        ring.bind_region(gl::UNIFORM_BUFFER, 2);
+        assert_eq!(mock.find("BindBufferRange").unwrap().args, [gl::UNIFORM_BUFFER as i64, 2, id, 256, 40]);
+
+        // The fences of regions 2 and 0 are still waiting
+        drop(ring);
+        let calls = mock.take_calls();
+        assert_eq!(get_names(&calls), ["BindBufferRange", "DeleteSync", "DeleteSync", "UnmapNamedBuffer", "DeleteBuffers"]);
+        assert_eq!([calls[1].args[0], calls[2].args[0]], [fences[3], fences[2]]);
+    }
+
+    #[test]
+    fn ring_buffer_keeps_fence_after_timeout() {
+        let (mock, gl) = ring_buffer_context();
+
+        let mut ring = RingBuffer::<[f32; 4]>::new(&gl, 4, 2).unwrap();
+        ring.next_region().unwrap();
+        ring.next_region().unwrap();
+        let fence = mock.find("FenceSync").unwrap().args[2];
+        mock.clear_calls();
+
+        mock.set_sync_status(gl::TIMEOUT_EXPIRED);
+        assert!(matches!(ring.next_region(), Err(GlError::FenceWaitFailed(..))));
+        assert_eq!(ring.get_current_region(), 1);
+        assert_eq!(ring.get_stalls(), 1);
+
+        let calls = mock.take_calls();
+        assert_eq!(get_names(&calls), ["FenceSync", "ClientWaitSync", "ClientWaitSync"]);
+        assert_eq!(calls[2].args, [fence, gl::SYNC_FLUSH_COMMANDS_BIT as i64, 1_000_000_000]);
+
+        // Once the GPU catches up, the same fence is waited for and only then deleted
+        mock.set_sync_status(gl::CONDITION_SATISFIED);
+        ring.next_region().unwrap();
+        assert_eq!(ring.get_current_region(), 0);
+
+        let calls = mock.take_calls();
+        assert_eq!(get_names(&calls), ["FenceSync", "DeleteSync", "ClientWaitSync", "DeleteSync"]);
+        assert_eq!(calls[2].args, [fence, 0, 0]);
+        assert_eq!(calls[3].args, [fence]);
+    }
+
+    #[test]
+    fn ring_buffer_fences_region_written_before_next_region() {
+        let (mock, gl) = ring_buffer_context();
+
+        let mut ring = RingBuffer::<f32>::new(&gl, 4, 2).unwrap();
+        ring.get_region_mut()[0] = 1.0;
+        mock.clear_calls();
+
+        ring.next_region().unwrap();
+        assert_eq!(ring.get_current_region(), 1);
+        assert_eq!(get_names(&mock.take_calls()), ["FenceSync"]);
+    }
+
+    #[test]
+    fn ring_buffer_rejects_bad_sizes() {
+        let (mock, gl) = ring_buffer_context();
+
+        assert!(matches!(RingBuffer::<f32>::new(&gl, 0, 3), Err(GlError::InvalidBufferSize(_))));
+        assert!(matches!(RingBuffer::<f32>::new(&gl, 16, 0), Err(GlError::InvalidBufferSize(_))));
+        assert!(matches!(RingBuffer::<f32>::new(&gl, usize::MAX / 2, 3), Err(GlError::InvalidBufferSize(_))));
+        assert!(matches!(RingBuffer::<f32>::new(&gl, 1 << 40, 1 << 24), Err(GlError::InvalidBufferSize(_))));
+        assert_eq!(mock.count("CreateBuffers"), 0);
+    }
+}
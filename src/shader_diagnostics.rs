@@ -0,0 +1,394 @@
+use std::{fmt::Display, path::PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiagnosticSeverity {
+    Error,
+    Warning,
+    Note
+}
+
+impl Display for DiagnosticSeverity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            DiagnosticSeverity::Error => "error",
+            DiagnosticSeverity::Warning => "warning",
+            DiagnosticSeverity::Note => "note"
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+// One message from a compile or link log
+#[derive(Debug, Clone)]
+pub struct ShaderDiagnostic {
+    pub severity: DiagnosticSeverity,
+    // GLSL source string number, the index in ShaderCodeBundle::source_files
+    pub source_string: u32,
+    // None when the bundle wasn't loaded from files
+    pub file: Option<PathBuf>,
+    // None for messages about the whole shader or program, e.g. most link errors
+    pub line: Option<u32>,
+    // Only Mesa reports columns, starting at 1
+    pub column: Option<u32>,
+    pub message: String,
+    // The line the diagnostic points at, taken from the code that was compiled
+    pub source_line: Option<String>
+}
+
+impl ShaderDiagnostic {
+    fn new(severity: DiagnosticSeverity, message: &str) -> ShaderDiagnostic {
+        ShaderDiagnostic {
+            severity,
+            source_string: 0,
+            file: None,
+            line: None,
+            column: None,
+            message: message.to_owned(),
+            source_line: None
+        }
+    }
+
+    // Where the caret goes, as (start, length) in chars. The column when known, otherwise the first
+    // word of the line quoted in the message, otherwise the start of the code on the line
+    fn get_highlight(&self, source_line: &str) -> (usize, usize) {
+        if let Some(column) = self.column.filter(|column| *column > 0) {
+            return ((column - 1) as usize, 1);
+        }
+
+        let start = get_quoted_words(&self.message)
+            .find_map(|word| find_word(source_line, word).map(|start| (start, word.chars().count())));
+
+        start.unwrap_or_else(|| (source_line.chars().take_while(|c| c.is_whitespace()).count(), 1))
+    }
+}
+
+// Rendered like a compiler error:
+// shaders/lit.frag:12:5: error: 'foo' : undeclared identifier
+//    12 |     vec3 color = foo;
+//       |                  ^~~
+impl Display for ShaderDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let line = match self.line {
+            Some(line) => line,
+            None => return write!(f, "{}: {}", self.severity, self.message)
+        };
+
+        match &self.file {
+            Some(file) => write!(f, "{}:{}", file.display(), line)?,
+            None => write!(f, "{}:{}", self.source_string, line)?
+        }
+        if let Some(column) = self.column {
+            write!(f, ":{}", column)?;
+        }
+        write!(f, ": {}: {}", self.severity, self.message)?;
+
+        if let Some(source_line) = &self.source_line {
+            let (start, length) = self.get_highlight(source_line);
+            let gutter = line.to_string().len().max(4);
+
+            // Tabs are kept so the caret lines up however they are displayed
+            let indent: String = source_line.chars()
+                .chain(std::iter::repeat(' '))
+                .take(start)
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+
+            write!(f, "\n{:>gutter$} | {}", line, source_line)?;
+            write!(f, "\n{:>gutter$} | {}^{}", "", indent, "~".repeat(length - 1))?;
+        }
+
+        Ok(())
+    }
+}
+
+// The info log of a failed compile or link, with the messages it could be parsed into
+#[derive(Debug, Clone, Default)]
+pub struct ShaderCompileLog {
+    raw: String,
+    diagnostics: Vec<ShaderDiagnostic>
+}
+
+impl ShaderCompileLog {
+    // Understands the Mesa "0:12(5): error: ...", NVIDIA "0(12) : error C1008: ..." and
+    // AMD/Intel "ERROR: 0:12: ..." formats. `code` is what was compiled, for the source lines
+    pub fn parse(log: &str, source_files: &[PathBuf], code: Option<&str>) -> ShaderCompileLog {
+        let mut diagnostics: Vec<ShaderDiagnostic> = Vec::new();
+
+        for line in log.lines().map(str::trim_end).filter(|line| !line.trim().is_empty()) {
+            match parse_line(line) {
+                Some(mut diagnostic) => {
+                    if let Some(line) = diagnostic.line {
+                        diagnostic.file = source_files.get(diagnostic.source_string as usize).cloned();
+                        diagnostic.source_line = code
+                            .and_then(|code| find_source_line(code, diagnostic.source_string, line))
+                            .map(str::to_owned);
+
+                        if let (Some(column), Some(source_line)) = (diagnostic.column, &diagnostic.source_line) {
+                            diagnostic.column = Some(map_preprocessed_column(source_line, column));
+                        }
+                    }
+
+                    diagnostics.push(diagnostic);
+                },
+                // AMD ends its logs with "ERROR: 1 compilation errors.  No code generated."
+                None if is_summary(line) => (),
+                // Messages can go over several lines
+                None => match diagnostics.last_mut() {
+                    Some(diagnostic) => {
+                        diagnostic.message.push('\n');
+                        diagnostic.message.push_str(line.trim());
+                    },
+                    None => diagnostics.push(ShaderDiagnostic::new(DiagnosticSeverity::Note, line.trim()))
+                }
+            }
+        }
+
+        ShaderCompileLog { raw: log.to_owned(), diagnostics }
+    }
+
+    // The log as the driver wrote it
+    pub fn get_raw(&self) -> &str {
+        &self.raw
+    }
+
+    // Empty if nothing in the log was recognised, Display then shows the raw log
+    pub fn get_diagnostics(&self) -> &[ShaderDiagnostic] {
+        &self.diagnostics
+    }
+
+    pub fn get_errors(&self) -> impl Iterator<Item = &ShaderDiagnostic> {
+        self.diagnostics.iter().filter(|diagnostic| diagnostic.severity == DiagnosticSeverity::Error)
+    }
+}
+
+// For errors raised by the crate rather than the driver
+impl From<String> for ShaderCompileLog {
+    fn from(message: String) -> Self {
+        ShaderCompileLog { raw: message, diagnostics: Vec::new() }
+    }
+}
+
+impl Display for ShaderCompileLog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.diagnostics.is_empty() {
+            return write!(f, "{}", self.raw.trim_end());
+        }
+
+        let diagnostics: Vec<String> = self.diagnostics.iter().map(|diagnostic| diagnostic.to_string()).collect();
+        write!(f, "{}", diagnostics.join("\n"))
+    }
+}
+
+fn parse_line(line: &str) -> Option<ShaderDiagnostic> {
+    let line = line.trim_start();
+
+    // AMD/Intel "ERROR: 0:12: message", or a message without a location like Mesa link errors "error: message"
+    if let Some((severity, rest)) = strip_severity(line) {
+        let location = parse_number(rest.trim_start())
+            .and_then(|(source, rest)| rest.strip_prefix(':').and_then(parse_number).map(|(line, rest)| (source, line, rest)))
+            .and_then(|(source, line, rest)| rest.strip_prefix(':').map(|message| (source, line, message)));
+
+        return match location {
+            Some((source, line, message)) => {
+                let mut diagnostic = ShaderDiagnostic::new(severity, message.trim());
+                diagnostic.source_string = source;
+                diagnostic.line = Some(line);
+                Some(diagnostic)
+            },
+            None if is_summary(rest) => None,
+            None => Some(ShaderDiagnostic::new(severity, rest.trim()))
+        };
+    }
+
+    let (source, rest) = parse_number(line)?;
+
+    let (line, column, rest) = if let Some(rest) = rest.strip_prefix(':') {
+        // Mesa: "0:12(5): error: message"
+        let (line, rest) = parse_number(rest)?;
+        let (column, rest) = match rest.strip_prefix('(').and_then(parse_number) {
+            Some((column, rest)) => (Some(column), rest.strip_prefix(')')?),
+            None => (None, rest)
+        };
+
+        (line, column, rest)
+    } else {
+        // NVIDIA: "0(12) : error C1008: message"
+        let (line, rest) = parse_number(rest.strip_prefix('(')?)?;
+
+        (line, None, rest.strip_prefix(')')?)
+    };
+
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let (severity, message) = match strip_severity(rest) {
+        Some(found) => found,
+        // NVIDIA puts a code between the severity and the colon
+        None => {
+            let (severity, rest) = rest.split_once(' ')?;
+            let (code, message) = rest.split_once(':')?;
+            if !is_error_code(code) {
+                return None;
+            }
+
+            (parse_severity(severity)?, message)
+        }
+    };
+
+    let mut diagnostic = ShaderDiagnostic::new(severity, message.trim());
+    diagnostic.source_string = source;
+    diagnostic.line = Some(line);
+    diagnostic.column = column;
+
+    Some(diagnostic)
+}
+
+// Returns the text after "error:", "warning:" and such
+fn strip_severity(text: &str) -> Option<(DiagnosticSeverity, &str)> {
+    let (severity, rest) = text.split_once(':')?;
+
+    Some((parse_severity(severity)?, rest))
+}
+
+fn parse_severity(word: &str) -> Option<DiagnosticSeverity> {
+    match word {
+        "error" | "ERROR" => Some(DiagnosticSeverity::Error),
+        "warning" | "WARNING" => Some(DiagnosticSeverity::Warning),
+        "note" | "info" | "INFO" => Some(DiagnosticSeverity::Note),
+        _ => None
+    }
+}
+
+fn parse_number(text: &str) -> Option<(u32, &str)> {
+    let end = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let number = text[..end].parse().ok()?;
+
+    Some((number, &text[end..]))
+}
+
+fn is_error_code(code: &str) -> bool {
+    let code = code.trim();
+    code.len() > 1 && code.starts_with(|c: char| c.is_ascii_uppercase()) && code[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+fn is_summary(line: &str) -> bool {
+    line.contains("compilation errors") || line.contains("No code generated")
+}
+
+// Words in '', "", `' or `` quotes, which most drivers put around identifiers
+fn get_quoted_words(message: &str) -> impl Iterator<Item = &str> {
+    message.split(['\'', '"', '`'])
+        .skip(1)
+        .step_by(2)
+        .filter(|word| !word.is_empty() && word.chars().all(|c| c.is_alphanumeric() || c == '_'))
+}
+
+// Char index of `word` in the line, not as part of a longer identifier
+fn find_word(line: &str, word: &str) -> Option<usize> {
+    let is_identifier = |c: char| c.is_alphanumeric() || c == '_';
+
+    line.match_indices(word)
+        .find(|(start, _)| {
+            let before = line[..*start].chars().next_back();
+            let after = line[start + word.len()..].chars().next();
+            !before.is_some_and(is_identifier) && !after.is_some_and(is_identifier)
+        })
+        .map(|(start, _)| line[..start].chars().count())
+}
+
+// Mesa counts columns in the preprocessed line, where each run of whitespace is one space
+fn map_preprocessed_column(source_line: &str, column: u32) -> u32 {
+    let mut preprocessed = 1;
+    let mut previous_whitespace = false;
+
+    for (index, c) in source_line.chars().enumerate() {
+        let whitespace = c.is_whitespace();
+        if whitespace && previous_whitespace {
+            continue;
+        }
+        if preprocessed == column {
+            return index as u32 + 1;
+        }
+
+        preprocessed += 1;
+        previous_whitespace = whitespace;
+    }
+
+    column
+}
+
+// Follows #line directives, like the ones from ShaderLoader, to find the code line the driver numbered `line`
+fn find_source_line(code: &str, source_string: u32, line: u32) -> Option<&str> {
+    let (mut current_source, mut current_line) = (0, 1);
+
+    for code_line in code.lines() {
+        if let Some(directive) = code_line.trim_start().strip_prefix("#line") {
+            let mut numbers = directive.split_whitespace().map(|number| number.parse::<u32>());
+
+            // #line sets the number of the line after it
+            if let Some(Ok(next_line)) = numbers.next() {
+                current_line = next_line;
+                if let Some(Ok(source)) = numbers.next() {
+                    current_source = source;
+                }
+                continue;
+            }
+        }
+
+        if current_source == source_string && current_line == line {
+            return Some(code_line);
+        }
+        current_line += 1;
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+    use super::{DiagnosticSeverity, ShaderCompileLog};
+
+    #[test]
+    fn parse_maps_vendor_logs_to_files() {
+        // What ShaderLoader makes of lit.frag including common.glsl on its second line
+        let code = concat!(
+            "#version 330 core\n",
+            "#line 2 0\n",
+            "#line 1 1\n",
+            "uniform vec3 tint;\n",
+            "#line 3 0\n",
+            "void main() {\n",
+            "    vec3 color = foo;\n",
+            "}\n"
+        );
+        let source_files = vec![PathBuf::from("shaders/lit.frag"), PathBuf::from("shaders/common.glsl")];
+
+        let cases = [
+            // Mesa, the column counts the leading whitespace as one space
+            ("0:4(15): error: `foo' undeclared", DiagnosticSeverity::Error, Some("shaders/lit.frag"), Some(4), Some(18),
+                "`foo' undeclared", Some("    vec3 color = foo;")),
+            // NVIDIA
+            ("1(1) : warning C7022: unused uniform 'tint'", DiagnosticSeverity::Warning, Some("shaders/common.glsl"), Some(1),
+                None, "unused uniform 'tint'", Some("uniform vec3 tint;")),
+            // AMD
+            ("ERROR: 0:3: 'main' : syntax error", DiagnosticSeverity::Error, Some("shaders/lit.frag"), Some(3), None,
+                "'main' : syntax error", Some("void main() {")),
+            // Nothing recognised, kept as a note with no location
+            ("Internal compiler failure", DiagnosticSeverity::Note, None, None, None, "Internal compiler failure", None)
+        ];
+
+        for (log, severity, file, line, column, message, source_line) in cases {
+            let parsed = ShaderCompileLog::parse(log, &source_files, Some(code));
+            let [diagnostic] = parsed.get_diagnostics() else {
+                panic!("{} gave {:?}", log, parsed.get_diagnostics());
+            };
+
+            assert_eq!(diagnostic.severity, severity, "{}", log);
+            assert_eq!(diagnostic.file.as_deref(), file.map(std::path::Path::new), "{}", log);
+            assert_eq!(diagnostic.line, line, "{}", log);
+            assert_eq!(diagnostic.column, column, "{}", log);
+            assert_eq!(diagnostic.message, message, "{}", log);
+            assert_eq!(diagnostic.source_line.as_deref(), source_line, "{}", log);
+        }
+    }
+}
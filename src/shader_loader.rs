@@ -0,0 +1,280 @@
+use std::{fs, path::{Path, PathBuf}};
+use super::{GlError, ShaderCodeBundle, ShaderCompileType};
+
+// Reads shader stages from disk, resolving #include "file" directives and adding #defines after #version.
+// Every file gets a GLSL source string number through #line directives, which is its index in
+// ShaderCodeBundle::source_files, so compile errors can be mapped back to the file and line they came from
+#[derive(Default)]
+pub struct ShaderLoader {
+    // Tried in order when an include isn't found next to the file including it
+    pub search_paths: Vec<PathBuf>,
+    // (name, value) pairs, value can be empty
+    pub defines: Vec<(String, String)>
+}
+
+impl ShaderLoader {
+    pub fn new() -> ShaderLoader {
+        ShaderLoader::default()
+    }
+
+    pub fn add_search_path<P: AsRef<Path>>(&mut self, path: P) {
+        self.search_paths.push(path.as_ref().to_owned());
+    }
+
+    pub fn define(&mut self, name: &str, value: &str) {
+        self.defines.push((name.to_owned(), value.to_owned()));
+    }
+
+    // Sets the stage's code in the bundle, the files that were read are added to its source files
+    pub fn load<P: AsRef<Path>>(
+        &self,
+        bundle: &mut ShaderCodeBundle,
+        type_: ShaderCompileType,
+        path: P
+    ) -> Result<(), GlError> {
+        let path = path.as_ref();
+        let mut code = String::new();
+
+        self.process_file(path, true, &mut bundle.source_files, &mut Vec::new(), &mut code)?;
+
+        match bundle.get_stage_mut(&type_) {
+            Some(stage) => *stage = Some(code),
+            None => return Err(GlError::ShaderLoadError(path.to_owned(), format!("{} is not a shader stage", type_)))
+        }
+
+        tracing::debug!(path = %path.display(), shader_type = %type_, "loaded shader source");
+
+        Ok(())
+    }
+
+    // `stack` holds the files currently being included, to catch cycles
+    fn process_file(
+        &self,
+        path: &Path,
+        is_root: bool,
+        source_files: &mut Vec<PathBuf>,
+        stack: &mut Vec<PathBuf>,
+        output: &mut String
+    ) -> Result<(), GlError> {
+        let load_error = |err: std::io::Error| GlError::ShaderLoadError(path.to_owned(), err.to_string());
+        let canonical = fs::canonicalize(path).map_err(load_error)?;
+
+        if stack.contains(&canonical) {
+            let cycle: Vec<String> = stack.iter()
+                .skip_while(|file| **file != canonical)
+                .chain(std::iter::once(&canonical))
+                .map(|file| file.display().to_string())
+                .collect();
+
+            return Err(GlError::ShaderLoadError(path.to_owned(), format!("Include cycle: {}", cycle.join(" -> "))));
+        }
+
+        let source = fs::read_to_string(&canonical).map_err(load_error)?;
+        let index = match source_files.iter().position(|file| *file == canonical) {
+            Some(index) => index,
+            None => {
+                source_files.push(canonical.clone());
+                source_files.len() - 1
+            }
+        };
+
+        stack.push(canonical.clone());
+
+        let lines: Vec<&str> = source.lines().collect();
+        let mut start = 0;
+
+        // #version has to come first, so defines and #line go after it
+        if is_root {
+            if let Some(version) = lines.iter().position(|line| line.trim_start().starts_with("#version")) {
+                for line in &lines[..=version] {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+                start = version + 1;
+            }
+
+            for (name, value) in &self.defines {
+                output.push_str(&format!("#define {} {}\n", name, value));
+            }
+        }
+
+        // #line sets the number of the line after it
+        output.push_str(&format!("#line {} {}\n", start + 1, index));
+
+        for (number, line) in lines.iter().enumerate().skip(start) {
+            let number = number + 1;
+
+            match parse_include(line) {
+                Some(Ok(name)) => {
+                    let include_path = self.resolve_include(&canonical, name).ok_or_else(|| GlError::ShaderLoadError(
+                        path.to_owned(),
+                        format!("'{}' included on line {} was not found", name, number)
+                    ))?;
+
+                    self.process_file(&include_path, false, source_files, stack, output)?;
+                    output.push_str(&format!("#line {} {}\n", number + 1, index));
+                },
+                Some(Err(operand)) => return Err(GlError::ShaderLoadError(
+                    path.to_owned(),
+                    format!("Line {} should be #include \"file\" or #include <file>, found #include {}", number, operand)
+                )),
+                None => {
+                    output.push_str(line);
+                    output.push('\n');
+                }
+            }
+        }
+
+        stack.pop();
+
+        Ok(())
+    }
+
+    fn resolve_include(&self, including_file: &Path, name: &str) -> Option<PathBuf> {
+        let relative = including_file.parent().map(|dir| dir.join(name));
+
+        relative.into_iter()
+            .chain(self.search_paths.iter().map(|dir| dir.join(name)))
+            .find(|path| path.is_file())
+    }
+}
+
+// None when the line isn't an #include directive, Err with the operand when it isn't a quoted file name.
+// "#includefoo" is some other directive and is left to the GLSL compiler
+fn parse_include(line: &str) -> Option<Result<&str, &str>> {
+    let rest = line.trim_start().strip_prefix("#include")?;
+
+    if !rest.is_empty() && !rest.starts_with(|c: char| c.is_whitespace() || c == '"' || c == '<') {
+        return None;
+    }
+
+    let operand = rest.trim();
+    let name = operand.strip_prefix('"').and_then(|name| name.strip_suffix('"'))
+        .or_else(|| operand.strip_prefix('<').and_then(|name| name.strip_suffix('>')));
+
+    match name {
+        Some(name) if !name.is_empty() && !name.contains(['"', '<', '>']) => Some(Ok(name)),
+        _ => Some(Err(operand))
+    }
+}
+
+// Adds defines after #version in code that is already loaded, keeping its line numbers
+pub(crate) fn insert_defines(source: &str, defines: &[(String, String)]) -> String {
+    let lines: Vec<&str> = source.lines().collect();
+    let start = lines.iter().position(|line| line.trim_start().starts_with("#version")).map_or(0, |version| version + 1);
+    let mut output = String::with_capacity(source.len());
+
+    for line in &lines[..start] {
+        output.push_str(line);
+        output.push('\n');
+    }
+
+    for (name, value) in defines {
+        output.push_str(&format!("#define {} {}\n", name, value));
+    }
+
+    // Without a source string number, the current one is kept
+    output.push_str(&format!("#line {}\n", start + 1));
+
+    for line in &lines[start..] {
+        output.push_str(line);
+        output.push('\n');
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+    use crate::{GlError, ShaderCodeBundle, ShaderCompileType};
+    use super::ShaderLoader;
+
+    // A fresh directory under the system temp dir holding the given files
+    fn shader_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("silver_gl_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        for (file, source) in files {
+            let path = dir.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, source).unwrap();
+        }
+
+        fs::canonicalize(dir).unwrap()
+    }
+
+    fn load_error(loader: &ShaderLoader, path: PathBuf) -> String {
+        match loader.load(&mut ShaderCodeBundle::default(), ShaderCompileType::Fragment, path) {
+            Err(GlError::ShaderLoadError(_, error)) => error,
+            result => panic!("expected a load error, got {:?}", result)
+        }
+    }
+
+    #[test]
+    fn load_resolves_nested_includes() {
+        let dir = shader_dir("nested", &[
+            ("main.frag", "#version 330 core\n#include \"common.glsl\"\nvoid main() {}\n"),
+            ("common.glsl", "#include <math.glsl>\nfloat a;\n"),
+            ("lib/math.glsl", "float pi;\n")
+        ]);
+
+        let mut loader = ShaderLoader::new();
+        loader.add_search_path(dir.join("lib"));
+        loader.define("QUALITY", "2");
+
+        let mut bundle = ShaderCodeBundle::default();
+        loader.load(&mut bundle, ShaderCompileType::Fragment, dir.join("main.frag")).unwrap();
+
+        // Defines go after #version, each file is a source string numbered by its index in source_files,
+        // and #line picks up the including file again after each include
+        assert_eq!(bundle.fragment.as_deref(), Some(concat!(
+            "#version 330 core\n",
+            "#define QUALITY 2\n",
+            "#line 2 0\n",
+            "#line 1 1\n",
+            "#line 1 2\n",
+            "float pi;\n",
+            "#line 2 1\n",
+            "float a;\n",
+            "#line 3 0\n",
+            "void main() {}\n"
+        )));
+        assert_eq!(bundle.source_files, vec![dir.join("main.frag"), dir.join("common.glsl"), dir.join("lib/math.glsl")]);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_reports_bad_includes() {
+        let dir = shader_dir("bad_includes", &[
+            ("a.glsl", "#include \"b.glsl\"\n"),
+            ("b.glsl", "float b;\n#include \"a.glsl\"\n"),
+            ("unquoted.glsl", "float c;\n#include common.glsl\n"),
+            ("unterminated.glsl", "#include \"common.glsl\n"),
+            ("missing.glsl", "#include \"common.glsl\"\n"),
+            ("other.glsl", "#includefoo\n")
+        ]);
+        let loader = ShaderLoader::new();
+
+        let cycle = format!("Include cycle: {} -> {} -> {}",
+            dir.join("a.glsl").display(), dir.join("b.glsl").display(), dir.join("a.glsl").display());
+        assert_eq!(load_error(&loader, dir.join("a.glsl")), cycle);
+        assert_eq!(
+            load_error(&loader, dir.join("unquoted.glsl")),
+            "Line 2 should be #include \"file\" or #include <file>, found #include common.glsl"
+        );
+        assert_eq!(
+            load_error(&loader, dir.join("unterminated.glsl")),
+            "Line 1 should be #include \"file\" or #include <file>, found #include \"common.glsl"
+        );
+        assert_eq!(load_error(&loader, dir.join("missing.glsl")), "'common.glsl' included on line 1 was not found");
+
+        // Not an #include, left for the compiler to reject
+        let mut bundle = ShaderCodeBundle::default();
+        loader.load(&mut bundle, ShaderCompileType::Fragment, dir.join("other.glsl")).unwrap();
+        assert_eq!(bundle.fragment.as_deref(), Some("#line 1 0\n#includefoo\n"));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,154 +1,375 @@
-use std::{ffi::CString, fmt::Display};
-use std::ptr;
-use cgmath::{Vector3, Array, Matrix4, Matrix, Vector4};
-use super::{error::GlError, gl};
+use std::{cell::RefCell, collections::HashMap, ffi::CString, fmt::Display, path::PathBuf};
+use cgmath::{Vector3, Matrix4, Vector4};
+use super::{error::GlError, BlockLayoutInfo, BufferBlockType, GlApi, GlContext, GlFeature, ObjectName, ShaderCompileLog, SpirvModule, gl::{self, types::GLenum}};
+use super::uniform_value::UniformValue;
+use super::reflection::{ProgramReflection, Uniform, UniformKey, next_program_key};
+
+// Cached locations of a uniform, one per array element. Uniform handles index into these
+struct UniformSlot {
+    name: String,
+    locations: Vec<i32>,
+    // GL type if found in the reflection, otherwise array elements are queried by name when first used
+    type_: Option<GLenum>
+}
 
-pub struct ShaderProgram { id: u32 }
+pub struct ShaderProgram {
+    id: u32,
+    label: Option<String>,
+    // From the bundle being compiled, to point compile errors at the right file
+    source_files: Vec<PathBuf>,
+    // Blocks bound with bind_to_ubo() and bind_to_ssbo(), re-applied when the program is recompiled
+    buffer_blocks: RefCell<Vec<(BufferBlockType, String)>>,
+    reflection: ProgramReflection,
+    // Identifies the program in Uniform handles
+    key: u32,
+    // Kept across recompiles so handles stay valid
+    uniform_slots: RefCell<Vec<UniformSlot>>,
+    slot_names: RefCell<HashMap<String, usize>>,
+    // Set for programs from a ProgramCache, so the driver keeps what glGetProgramBinary needs
+    binary_retrievable: bool,
+    // Can be used in a ProgramPipeline, which takes `stages` from it
+    separable: bool,
+    stages: Vec<ShaderCompileType>,
+    gl: GlContext
+}
 
 impl ShaderProgram {
-    pub fn new(shader_bundle: ShaderCodeBundle) -> Result<ShaderProgram, GlError> {
-        let mut shader_program = ShaderProgram { id: 0 };
+    pub fn new(gl: &GlContext, shader_bundle: ShaderCodeBundle) -> Result<ShaderProgram, GlError> {
+        let mut shader_program = ShaderProgram::empty(gl, false);
+
+        shader_program.compile_program(shader_bundle)?;
+
+        Ok(shader_program)
+    }
+
+    // Only has the stages in the bundle, other stages come from other programs in a ProgramPipeline
+    pub fn new_separable(gl: &GlContext, shader_bundle: ShaderCodeBundle) -> Result<ShaderProgram, GlError> {
+        gl.require(GlFeature::SeparateShaderObjects)?;
+
+        let mut shader_program = ShaderProgram::empty(gl, false);
+        shader_program.separable = true;
 
         shader_program.compile_program(shader_bundle)?;
 
         Ok(shader_program)
     }
 
+    // Has no GL program until one is compiled or loaded
+    pub(crate) fn empty(gl: &GlContext, binary_retrievable: bool) -> ShaderProgram {
+        ShaderProgram {
+            id: 0,
+            label: None,
+            source_files: Vec::new(),
+            buffer_blocks: RefCell::new(Vec::new()),
+            reflection: ProgramReflection::default(),
+            key: next_program_key(),
+            uniform_slots: RefCell::new(Vec::new()),
+            slot_names: RefCell::new(HashMap::new()),
+            binary_retrievable,
+            separable: false,
+            stages: Vec::new(),
+            gl: gl.clone()
+        }
+    }
+
+    // Can be called again on a compiled program to replace it. The new program is only swapped in once it
+    // has linked, on failure the previous one (if any) stays in use and the error is returned
     pub fn compile_program(&mut self, shader_bundle: ShaderCodeBundle) -> Result<(), GlError> {
-        let mut shader_ids = Vec::new();
+        let previous_label = self.label.clone();
+        let previous_files = std::mem::replace(&mut self.source_files, shader_bundle.source_files.clone());
 
-        for (code, type_) in shader_bundle.get_vec() {
-            if let Some(code) = code {
-                shader_ids.push(ShaderProgram::compile_shader(code, type_)?);
+        if shader_bundle.name.is_some() {
+            self.label = shader_bundle.name.clone();
+        }
+
+        match self.link_program(&shader_bundle) {
+            Ok(shader_program_id) => {
+                self.stages = shader_bundle.get_stages();
+                self.swap_program(shader_program_id);
+
+                Ok(())
+            },
+            Err(err) => {
+                self.label = previous_label;
+                self.source_files = previous_files;
+
+                Err(err)
             }
         }
+    }
 
-        let shader_program_id;
+    // Loads a binary from glGetProgramBinary in place of compiling the bundle.
+    // Returns false and keeps the current program if the driver rejects it
+    pub(crate) fn load_binary(&mut self, shader_bundle: &ShaderCodeBundle, format: GLenum, binary: &[u8]) -> bool {
+        let shader_program_id = self.gl.create_program();
+        if self.binary_retrievable {
+            self.gl.program_parameter_i(shader_program_id, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as i32);
+        }
+        if self.separable {
+            self.gl.program_parameter_i(shader_program_id, gl::PROGRAM_SEPARABLE, gl::TRUE as i32);
+        }
+        self.gl.program_binary(shader_program_id, format, binary);
 
-        unsafe {
-            shader_program_id = gl::CreateProgram();
+        if self.gl.get_program_iv(shader_program_id, gl::LINK_STATUS) == 0 {
+            self.gl.delete_program(shader_program_id);
+            return false;
+        }
+
+        if shader_bundle.name.is_some() {
+            self.label = shader_bundle.name.clone();
+        }
+        self.source_files = shader_bundle.source_files.clone();
+
+        if let Some(label) = &self.label {
+            self.gl.label_object(gl::PROGRAM, shader_program_id, label);
+        }
+        self.stages = shader_bundle.get_stages();
+        self.swap_program(shader_program_id);
+
+        true
+    }
+
+    // Replaces the GL program with a linked one, keeping uniform handles and block bindings
+    fn swap_program(&mut self, shader_program_id: u32) {
+        if self.id != 0 {
+            self.gl.delete_program(self.id);
+        }
+        self.id = shader_program_id;
+        self.reflection = ProgramReflection::query(&*self.gl, self.id);
+
+        // Locations can move when the program changes
+        let mut uniform_slots = self.uniform_slots.take();
+        for slot in uniform_slots.iter_mut() {
+            (slot.locations, slot.type_) = self.resolve_locations(&slot.name);
+        }
+        self.uniform_slots.replace(uniform_slots);
+
+        // Blocks bound on the previous program, kept even if missing from this one
+        let buffer_blocks = self.buffer_blocks.take();
+        for (type_, name) in buffer_blocks.iter() {
+            let result = match type_ {
+                BufferBlockType::Uniform => self.bind_to_ubo(name),
+                BufferBlockType::ShaderStorage => self.bind_to_ssbo(name)
+            };
+
+            if let Err(err) = result {
+                tracing::warn!(block = name.as_str(), "{}", err);
+            }
+        }
+        self.buffer_blocks.replace(buffer_blocks);
+    }
 
-            println!("DEBUG::SHADER::PROGRAM::ATTACHING_SHADERS");
+    // Deletes everything it created if compiling or linking fails
+    fn link_program(&self, shader_bundle: &ShaderCodeBundle) -> Result<u32, GlError> {
+        let span = tracing::info_span!(
+            "compile_program",
+            source = self.get_source_name(),
+            program_id = tracing::field::Empty
+        ).entered();
 
+        let mut shader_ids = Vec::new();
+        let delete_shaders = |shader_ids: &Vec<u32>| {
             for id in shader_ids.iter() {
-                gl::AttachShader(shader_program_id, *id);
+                self.gl.delete_shader(*id);
             }
+        };
 
-            println!("DEBUG::SHADER::PROGRAM::COMPILING_PROGRAM");
+        if !shader_bundle.spirv.is_empty() {
+            self.gl.require(GlFeature::Spirv)?;
 
-            gl::LinkProgram(shader_program_id);
-            ShaderProgram::check_compile_errors(shader_program_id, ShaderCompileType::Program)?;
+            if shader_bundle.get_vec().iter().any(|(code, _)| code.is_some()) {
+                return Err(GlError::ShaderCompileError(
+                    ShaderCompileType::Program,
+                    ObjectName::new(0, &self.label),
+                    String::from("A program can't have both GLSL and SPIR-V stages").into()
+                ));
+            }
+        }
 
-            println!("DEBUG::SHADER::PROGRAM::COMPILATION_COMPLETE");
+        for (code, type_) in shader_bundle.get_vec() {
+            if let Some(code) = code {
+                match self.compile_shader(code, type_) {
+                    Ok(id) => shader_ids.push(id),
+                    Err(err) => {
+                        delete_shaders(&shader_ids);
+                        return Err(err);
+                    }
+                }
+            }
+        }
 
-            for id in shader_ids.iter() {
-                gl::DeleteShader(*id);
+        for (type_, module) in shader_bundle.spirv.iter() {
+            match self.specialize_shader(module, type_.clone()) {
+                Ok(id) => shader_ids.push(id),
+                Err(err) => {
+                    delete_shaders(&shader_ids);
+                    return Err(err);
+                }
             }
         }
 
-        self.id = shader_program_id;
+        let shader_program_id = self.gl.create_program();
+        span.record("program_id", shader_program_id);
 
-        Ok(())
-    }
+        if self.binary_retrievable {
+            self.gl.program_parameter_i(shader_program_id, gl::PROGRAM_BINARY_RETRIEVABLE_HINT, gl::TRUE as i32);
+        }
+        if self.separable {
+            self.gl.program_parameter_i(shader_program_id, gl::PROGRAM_SEPARABLE, gl::TRUE as i32);
+        }
 
-    pub fn compile_shader(code: &str, type_: ShaderCompileType) -> Result<u32, GlError> {
-        // let mut shader_file = File::open(path)?;
-        // let mut shader_code = String::new();
+        if let Some(label) = &self.label {
+            self.gl.label_object(gl::PROGRAM, shader_program_id, label);
+        }
+
+        tracing::debug!(shader_count = shader_ids.len(), "attaching shaders");
+
+        for id in shader_ids.iter() {
+            self.gl.attach_shader(shader_program_id, *id);
+        }
+
+        tracing::debug!("linking program");
+
+        self.gl.link_program(shader_program_id);
+        let result = self.check_compile_errors(shader_program_id, ShaderCompileType::Program);
+        delete_shaders(&shader_ids);
+
+        if let Err(err) = result {
+            self.gl.delete_program(shader_program_id);
+            return Err(err);
+        }
+
+        tracing::debug!("program linked");
 
-        // println!("DEBUG::SHADER::{}::READING_FILE: {}", type_, path);
+        Ok(shader_program_id)
+    }
 
-        // shader_file.read_to_string(&mut shader_code)?;
+    pub fn compile_shader(&self, code: &str, type_: ShaderCompileType) -> Result<u32, GlError> {
+        let span = tracing::info_span!(
+            "compile_shader",
+            shader_type = %type_,
+            source = self.get_source_name(),
+            shader_id = tracing::field::Empty
+        ).entered();
 
         let shader_code = CString::new(code.as_bytes())?;
-        let shader_type = match type_ {
-            ShaderCompileType::Vertex => gl::VERTEX_SHADER,
-            ShaderCompileType::Geometry => gl::GEOMETRY_SHADER,
-            ShaderCompileType::Fragment => gl::FRAGMENT_SHADER,
-            _ => gl::VERTEX_SHADER // Default to vertex shader just in case
-        };
-        let shader;
+        let shader = self.create_shader(&type_)?;
+        span.record("shader_id", shader);
 
-        unsafe {
-            shader = gl::CreateShader(shader_type);
+        self.gl.shader_source(shader, &shader_code);
 
-            gl::ShaderSource(shader, 1, &shader_code.as_ptr(), ptr::null());
+        tracing::debug!("compiling shader");
+
+        self.gl.compile_shader(shader);
+        if let Err(err) = self.check_status(shader, type_.clone(), Some(code)) {
+            self.gl.delete_shader(shader);
+            return Err(err);
+        }
 
-            println!("DEBUG::SHADER::{}::COMPILING_SHADER", type_);
+        tracing::debug!("shader compiled");
+
+        Ok(shader)
+    }
 
-            gl::CompileShader(shader);
-            ShaderProgram::check_compile_errors(shader, type_.clone())?;
+    // Specialization errors are reported like compile errors
+    pub fn specialize_shader(&self, module: &SpirvModule, type_: ShaderCompileType) -> Result<u32, GlError> {
+        let span = tracing::info_span!(
+            "specialize_shader",
+            shader_type = %type_,
+            source = self.get_source_name(),
+            entry_point = module.entry_point.as_str(),
+            shader_id = tracing::field::Empty
+        ).entered();
+
+        let entry_point = CString::new(module.entry_point.as_bytes())?;
+        let (constant_indices, constant_values): (Vec<u32>, Vec<u32>) =
+            module.specialization_constants.iter().copied().unzip();
+
+        let shader = self.create_shader(&type_)?;
+        span.record("shader_id", shader);
+
+        self.gl.shader_binary(shader, gl::SHADER_BINARY_FORMAT_SPIR_V, &module.code);
+
+        tracing::debug!(constant_count = constant_indices.len(), "specializing shader");
+
+        self.gl.specialize_shader(shader, &entry_point, &constant_indices, &constant_values);
+        if let Err(err) = self.check_compile_errors(shader, type_.clone()) {
+            self.gl.delete_shader(shader);
+
+            // Some drivers leave the log empty, e.g. for a missing entry point
+            return Err(match err {
+                GlError::ShaderCompileError(type_, id, log) if log.get_raw().trim().is_empty() => GlError::ShaderCompileError(
+                    type_,
+                    id,
+                    format!("Could not specialize entry point '{}'", module.entry_point).into()
+                ),
+                err => err
+            });
         }
 
-        println!("DEBUG::SHADER::{}::COMPILATION_COMPLETE", type_);
+        tracing::debug!("shader specialized");
 
         Ok(shader)
     }
 
-    pub unsafe fn check_compile_errors(id: u32, type_: ShaderCompileType) -> Result<(), GlError> {
-        let mut success = gl::FALSE as gl::types::GLint;
-        match type_ {
-            ShaderCompileType::Program => gl::GetProgramiv(id, gl::LINK_STATUS, &mut success),
-            _ => gl::GetShaderiv(id, gl::COMPILE_STATUS, &mut success)
+    fn create_shader(&self, type_: &ShaderCompileType) -> Result<u32, GlError> {
+        let shader_type = match type_ {
+            ShaderCompileType::Vertex => gl::VERTEX_SHADER,
+            ShaderCompileType::TessControl => gl::TESS_CONTROL_SHADER,
+            ShaderCompileType::TessEvaluation => gl::TESS_EVALUATION_SHADER,
+            ShaderCompileType::Geometry => gl::GEOMETRY_SHADER,
+            ShaderCompileType::Fragment => gl::FRAGMENT_SHADER,
+            ShaderCompileType::Compute => gl::COMPUTE_SHADER,
+            ShaderCompileType::Program => return Err(GlError::ShaderCompileError(
+                type_.clone(),
+                ObjectName::new(0, &self.label),
+                String::from("Program is not a shader stage").into()
+            ))
+        };
+        let shader = self.gl.create_shader(shader_type);
+
+        if let Some(label) = &self.label {
+            self.gl.label_object(gl::SHADER, shader, &format!("{} {}", label, type_));
         }
 
-        let error;
+        Ok(shader)
+    }
+
+    pub fn check_compile_errors(&self, id: u32, type_: ShaderCompileType) -> Result<(), GlError> {
+        self.check_status(id, type_, None)
+    }
+
+    // `code` is the compiled shader's source, so diagnostics can show the lines they point at
+    fn check_status(&self, id: u32, type_: ShaderCompileType, code: Option<&str>) -> Result<(), GlError> {
+        let success = match type_ {
+            ShaderCompileType::Program => self.gl.get_program_iv(id, gl::LINK_STATUS),
+            _ => self.gl.get_shader_iv(id, gl::COMPILE_STATUS)
+        };
 
         if success != gl::TRUE as gl::types::GLint {
-            let mut len: gl::types::GLint = 0;
-
-            match type_ {
-                ShaderCompileType::Program => {
-                    gl::GetProgramiv(id, gl::INFO_LOG_LENGTH, &mut len);
-    
-                    error = {
-                        let mut buffer: Vec<u8> = Vec::with_capacity(len as usize + 1);
-                        buffer.extend([b' '].iter().cycle().take(len as usize));
-                        CString::from_vec_unchecked(buffer)
-                    };
-        
-                    gl::GetProgramInfoLog(
-                        id,
-                        len,
-                        ptr::null_mut(),
-                        error.as_ptr() as *mut gl::types::GLchar
-                    );
-                },
-                _ => {
-                    gl::GetShaderiv(id, gl::INFO_LOG_LENGTH, &mut len);
-    
-                    error = {
-                        let mut buffer: Vec<u8> = Vec::with_capacity(len as usize + 1);
-                        buffer.extend([b' '].iter().cycle().take(len as usize));
-                        CString::from_vec_unchecked(buffer)
-                    };
-        
-                    gl::GetShaderInfoLog(
-                        id,
-                        len,
-                        ptr::null_mut(),
-                        error.as_ptr() as *mut gl::types::GLchar
-                    );
-                }
-            }
+            let error = match type_ {
+                ShaderCompileType::Program => self.gl.get_program_info_log(id),
+                _ => self.gl.get_shader_info_log(id)
+            };
+            let log = ShaderCompileLog::parse(&error, &self.source_files, code);
 
-            // into_string() shouldn't error since it's directly from OpenGL
-            return Err(GlError::ShaderCompileError(type_, id, error.into_string().unwrap()))
+            return Err(GlError::ShaderCompileError(type_, ObjectName::new(id, &self.label), log))
         }
 
         Ok(())
     }
 
     pub fn use_program(&self) {
-        unsafe { gl::UseProgram(self.id) } // Cannot error as program always exists by this point
+        self.gl.use_program(self.id) // Cannot error as program always exists by this point
     }
 
     // Safe functions check whetehr the uniform exists, and passes an error
-    pub unsafe fn set_uniform<F: Fn(i32)>(&self, name: &str, uniform_func: F) -> Result<(), GlError> {
-        let cstr = CString::new(name)?;
-        let location = gl::GetUniformLocation(self.id, cstr.as_ptr());
+    pub unsafe fn set_uniform<U: UniformKey, F: Fn(i32)>(&self, uniform: U, uniform_func: F) -> Result<(), GlError> {
+        let uniform = uniform.to_uniform(self)?;
+        let location = self.get_uniform_location(uniform);
 
         if location == -1 {
-            return Err(GlError::UniformNotFound(name.to_owned(), self.id));
+            return Err(GlError::UniformNotFound(self.get_uniform_name(uniform), self.get_object_name()));
         }
 
         uniform_func(location);
@@ -156,132 +377,393 @@ impl ShaderProgram {
         Ok(())
     }
 
-    pub fn set_bool(&self, name: &str, value: bool) -> Result<(), GlError> {
+    // Any GLSL uniform type, and arrays of them from slices starting at the element `uniform` points at.
+    // In debug builds, the value is checked against the type the program was linked with
+    pub fn set<U: UniformKey, T: UniformValue + ?Sized>(&self, uniform: U, value: &T) -> Result<(), GlError> {
+        self.require_value_feature::<T>()?;
+        let uniform = uniform.to_uniform(self)?;
+
+        #[cfg(debug_assertions)]
+        self.check_type(uniform, value)?;
+
         unsafe {
-            self.set_uniform(name, |location| gl::Uniform1i(location, value as gl::types::GLint))
+            self.set_uniform(uniform, |location| value.upload(&*self.gl, location))
         }
     }
 
-    pub fn set_int(&self, name: &str, value: i32) -> Result<(), GlError> {
-        unsafe {
-            self.set_uniform(name, |location| gl::Uniform1i(location, value as gl::types::GLint))
-        }
+    pub fn set_bool<U: UniformKey>(&self, uniform: U, value: bool) -> Result<(), GlError> {
+        self.set(uniform, &value)
     }
 
-    pub fn set_float(&self, name: &str, value: f32) -> Result<(), GlError> {
-        unsafe {
-            self.set_uniform(name, |location| gl::Uniform1f(location, value as gl::types::GLfloat))
-        }
+    pub fn set_int<U: UniformKey>(&self, uniform: U, value: i32) -> Result<(), GlError> {
+        self.set(uniform, &value)
     }
 
-    pub fn set_vector_3(&self, name: &str, value: &Vector3<f32>) -> Result<(), GlError> {
-        unsafe {
-            self.set_uniform(name, |location| gl::Uniform3fv(location, 1, value.as_ptr()))
-        }
+    pub fn set_float<U: UniformKey>(&self, uniform: U, value: f32) -> Result<(), GlError> {
+        self.set(uniform, &value)
     }
 
-    pub fn set_vec3(&self, name: &str, x: f32, y: f32, z: f32) -> Result<(), GlError> {
-        unsafe {
-            self.set_uniform(name, |location| gl::Uniform3f(location, x, y, z))
-        }
+    pub fn set_vector_3<U: UniformKey>(&self, uniform: U, value: &Vector3<f32>) -> Result<(), GlError> {
+        self.set(uniform, value)
     }
 
-    pub fn set_vector_4(&self, name: &str, value: &Vector4<f32>) -> Result<(), GlError> {
-        unsafe {
-            self.set_uniform(name, |location| gl::Uniform4fv(location, 1, value.as_ptr()))
-        }
+    pub fn set_vec3<U: UniformKey>(&self, uniform: U, x: f32, y: f32, z: f32) -> Result<(), GlError> {
+        self.set(uniform, &[x, y, z])
     }
 
-    pub fn set_vec4(&self, name: &str, w: f32, x: f32, y: f32, z: f32) -> Result<(), GlError> {
-        unsafe {
-            self.set_uniform(name, |location| gl::Uniform4f(location, w, x, y, z))
-        }
+    pub fn set_vector_4<U: UniformKey>(&self, uniform: U, value: &Vector4<f32>) -> Result<(), GlError> {
+        self.set(uniform, value)
     }
 
-    pub fn set_mat4(&self, name: &str, value: &Matrix4<f32>) -> Result<(), GlError> {
-        unsafe {
-            self.set_uniform(name, |location| gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr()))
-        }
+    pub fn set_vec4<U: UniformKey>(&self, uniform: U, w: f32, x: f32, y: f32, z: f32) -> Result<(), GlError> {
+        self.set(uniform, &[w, x, y, z])
+    }
+
+    pub fn set_mat4<U: UniformKey>(&self, uniform: U, value: &Matrix4<f32>) -> Result<(), GlError> {
+        self.set(uniform, value)
     }
 
     // Unsafe versions do not check whether the uniforms are present
-    pub unsafe fn set_uniform_unsafe<F: Fn(i32)>(&self, name: &str, uniform_func: F) -> Result<(), GlError> {
-        let cstr = CString::new(name)?;
-        let location = gl::GetUniformLocation(self.id, cstr.as_ptr());
+    pub unsafe fn set_uniform_unsafe<U: UniformKey, F: Fn(i32)>(&self, uniform: U, uniform_func: F) -> Result<(), GlError> {
+        let location = self.get_uniform_location(uniform.to_uniform(self)?);
 
         uniform_func(location);
 
         Ok(())
     }
 
-    pub unsafe fn set_bool_unsafe(&self, name: &str, value: bool) -> Result<(), GlError> {
-        self.set_uniform_unsafe(name, |location| gl::Uniform1i(location, value as gl::types::GLint))
+    // Still refuses values the context can't set, like doubles on GLES
+    pub unsafe fn set_unsafe<U: UniformKey, T: UniformValue + ?Sized>(&self, uniform: U, value: &T) -> Result<(), GlError> {
+        self.require_value_feature::<T>()?;
+        self.set_uniform_unsafe(uniform, |location| value.upload(&*self.gl, location))
     }
 
-    pub unsafe fn set_int_unsafe(&self, name: &str, value: i32) -> Result<(), GlError> {
-        self.set_uniform_unsafe(name, |location| gl::Uniform1i(location, value as gl::types::GLint))
+    fn require_value_feature<T: UniformValue + ?Sized>(&self) -> Result<(), GlError> {
+        match T::get_feature() {
+            Some(feature) => self.gl.require(feature),
+            None => Ok(())
+        }
+    }
+
+    pub unsafe fn set_bool_unsafe<U: UniformKey>(&self, uniform: U, value: bool) -> Result<(), GlError> {
+        self.set_unsafe(uniform, &value)
+    }
+
+    pub unsafe fn set_int_unsafe<U: UniformKey>(&self, uniform: U, value: i32) -> Result<(), GlError> {
+        self.set_unsafe(uniform, &value)
+    }
+
+    pub unsafe fn set_float_unsafe<U: UniformKey>(&self, uniform: U, value: f32) -> Result<(), GlError> {
+        self.set_unsafe(uniform, &value)
+    }
+
+    pub unsafe fn set_vector_3_unsafe<U: UniformKey>(&self, uniform: U, value: &Vector3<f32>) -> Result<(), GlError> {
+        self.set_unsafe(uniform, value)
+    }
+
+    pub unsafe fn set_vec3_unsafe<U: UniformKey>(&self, uniform: U, x: f32, y: f32, z: f32) -> Result<(), GlError> {
+        self.set_unsafe(uniform, &[x, y, z])
+    }
+
+    pub unsafe fn set_vector_4_unsafe<U: UniformKey>(&self, uniform: U, value: &Vector4<f32>) -> Result<(), GlError> {
+        self.set_unsafe(uniform, value)
+    }
+
+    pub unsafe fn set_vec4_unsafe<U: UniformKey>(&self, uniform: U, w: f32, x: f32, y: f32, z: f32) -> Result<(), GlError> {
+        self.set_unsafe(uniform, &[w, x, y, z])
+    }
+
+    pub unsafe fn set_mat4_unsafe<U: UniformKey>(&self, uniform: U, value: &Matrix4<f32>) -> Result<(), GlError> {
+        self.set_unsafe(uniform, value)
+    }
+
+    // Uniforms missing from the reflection can't be checked, and missing ones are reported by set_uniform()
+    #[cfg(debug_assertions)]
+    fn check_type<T: UniformValue + ?Sized>(&self, uniform: Uniform, value: &T) -> Result<(), GlError> {
+        let (type_, array_size) = {
+            let uniform_slots = self.uniform_slots.borrow();
+            let slot = &uniform_slots[uniform.slot];
+
+            match slot.type_ {
+                Some(type_) if !slot.locations.is_empty() => (type_, slot.locations.len()),
+                _ => return Ok(())
+            }
+        };
+
+        let fits = uniform.element + value.get_len() <= array_size;
+        if T::matches_type(type_) && fits {
+            return Ok(());
+        }
+
+        let glsl_type = match (super::uniform_value::get_glsl_type_name(type_), array_size) {
+            (Some(name), 1) => name.to_owned(),
+            (Some(name), size) => format!("{}[{}]", name, size),
+            (None, _) => format!("GL type 0x{:X}", type_)
+        };
+        let rust_type = match value.get_len() {
+            1 => std::any::type_name::<T>().to_owned(),
+            len => format!("{} ({} elements)", std::any::type_name::<T>(), len)
+        };
+
+        Err(GlError::UniformTypeMismatch(self.get_uniform_name(uniform), glsl_type, rust_type, self.get_object_name()))
     }
 
-    pub unsafe fn set_float_unsafe(&self, name: &str, value: f32) -> Result<(), GlError> {
-        self.set_uniform_unsafe(name, |location| gl::Uniform1f(location, value as gl::types::GLfloat))
+    pub fn get_reflection(&self) -> &ProgramReflection {
+        &self.reflection
     }
 
-    pub unsafe fn set_vector_3_unsafe(&self, name: &str, value: &Vector3<f32>) -> Result<(), GlError> {
-        self.set_uniform_unsafe(name, |location| gl::Uniform3fv(location, 1, value.as_ptr()))
+    // Resolves a uniform once so it can be set without any string work.
+    // Array elements are either "name[2]" or get_uniform("name")?.at(2)
+    pub fn get_uniform(&self, name: &str) -> Result<Uniform, GlError> {
+        let uniform = self.find_uniform(name);
+
+        if self.get_uniform_location(uniform) == -1 {
+            return Err(GlError::UniformNotFound(name.to_owned(), self.get_object_name()));
+        }
+
+        Ok(uniform)
     }
 
-    pub unsafe fn set_vec3_unsafe(&self, name: &str, x: f32, y: f32, z: f32) -> Result<(), GlError> {
-        self.set_uniform_unsafe(name, |location| gl::Uniform3f(location, x, y, z))
+    // Also returns handles to inactive uniforms, those are cached too so they are only looked up once
+    pub(crate) fn find_uniform(&self, name: &str) -> Uniform {
+        let (base_name, element) = split_array_index(name);
+
+        if let Some(slot) = self.slot_names.borrow().get(base_name) {
+            return Uniform { program_key: self.key, slot: *slot, element };
+        }
+
+        let (locations, type_) = self.resolve_locations(base_name);
+        let mut uniform_slots = self.uniform_slots.borrow_mut();
+        uniform_slots.push(UniformSlot { name: base_name.to_owned(), locations, type_ });
+        self.slot_names.borrow_mut().insert(base_name.to_owned(), uniform_slots.len() - 1);
+
+        Uniform { program_key: self.key, slot: uniform_slots.len() - 1, element }
     }
 
-    pub unsafe fn set_vector_4_unsafe(&self, name: &str, value: &Vector4<f32>) -> Result<(), GlError> {
-        self.set_uniform_unsafe(name, |location| gl::Uniform4fv(location, 1, value.as_ptr()))
+    // -1 if the uniform is inactive, the handle has to come from this program
+    fn get_uniform_location(&self, uniform: Uniform) -> i32 {
+        let mut uniform_slots = self.uniform_slots.borrow_mut();
+        let slot = &mut uniform_slots[uniform.slot];
+
+        if uniform.element >= slot.locations.len() && slot.type_.is_none() {
+            for element in slot.locations.len()..=uniform.element {
+                let location = CString::new(format!("{}[{}]", slot.name, element))
+                    .map(|cstr| self.gl.get_uniform_location(self.id, &cstr))
+                    .unwrap_or(-1);
+                slot.locations.push(location);
+            }
+        }
+
+        slot.locations.get(uniform.element).copied().unwrap_or(-1)
     }
 
-    pub unsafe fn set_vec4_unsafe(&self, name: &str, w: f32, x: f32, y: f32, z: f32) -> Result<(), GlError> {
-        self.set_uniform_unsafe(name, |location| gl::Uniform4f(location, w, x, y, z))
+    fn get_uniform_name(&self, uniform: Uniform) -> String {
+        let uniform_slots = self.uniform_slots.borrow();
+
+        match uniform.element {
+            0 => uniform_slots[uniform.slot].name.clone(),
+            element => format!("{}[{}]", uniform_slots[uniform.slot].name, element)
+        }
     }
 
-    pub unsafe fn set_mat4_unsafe(&self, name: &str, value: &Matrix4<f32>) -> Result<(), GlError> {
-        self.set_uniform_unsafe(name, |location| gl::UniformMatrix4fv(location, 1, gl::FALSE, value.as_ptr()))
+    // Every element of an array is resolved up front, uniforms missing from the reflection are asked
+    // for directly in case the backend can't reflect
+    fn resolve_locations(&self, name: &str) -> (Vec<i32>, Option<GLenum>) {
+        match self.reflection.get_uniform(name) {
+            // Uniforms in a block have no location
+            Some(info) if info.location == -1 => (Vec::new(), Some(info.type_)),
+            Some(info) => {
+                let locations = (0..info.array_size.max(1) as usize)
+                    .map(|element| match element {
+                        0 => info.location,
+                        _ => CString::new(format!("{}[{}]", name, element))
+                            .map(|cstr| self.gl.get_uniform_location(self.id, &cstr))
+                            .unwrap_or(-1)
+                    })
+                    .collect();
+
+                (locations, Some(info.type_))
+            },
+            None => {
+                let location = CString::new(name)
+                    .map(|cstr| self.gl.get_uniform_location(self.id, &cstr))
+                    .unwrap_or(-1);
+
+                (if location == -1 { Vec::new() } else { vec![location] }, None)
+            }
+        }
     }
 
+    // Binds the uniform block to the binding the context gives blocks called `name`, where UniformBuffer puts its buffer
     pub fn bind_to_ubo(&self, name: &str) -> Result<(), GlError> {
-        let cstr = CString::new(name)?;
+        let uniform_block_index = match self.reflection.get_uniform_block(name) {
+            Some(block) => block.index,
+            None => self.gl.get_uniform_block_index(self.id, &CString::new(name)?)
+        };
 
-        unsafe {
-            let uniform_block_index = gl::GetUniformBlockIndex(self.id, cstr.as_ptr());
-            
-            if uniform_block_index == gl::INVALID_INDEX {
-                return Err(GlError::UniformInvalidIndex(name.to_owned(), self.id));
+        if uniform_block_index == gl::INVALID_INDEX {
+            return Err(GlError::UniformInvalidIndex(name.to_owned(), ObjectName::new(self.id, &self.label)));
+        }
+
+        let binding = self.gl.get_block_binding(BufferBlockType::Uniform, name)?;
+        self.gl.uniform_block_binding(self.id, uniform_block_index, binding);
+
+        self.add_buffer_block(BufferBlockType::Uniform, name);
+
+        Ok(())
+    }
+
+    // Compares the uniform block `name` as it was linked with a Rust layout, e.g. BlockLayoutInfo::std140::<T>().
+    // Members are checked in the order of their offsets, the first one the layout doesn't have at the same
+    // offset is returned. Blocks with an instance name have members named "Block.member", the prefix is ignored
+    pub fn check_block_layout(&self, name: &str, layout: &BlockLayoutInfo) -> Result<(), GlError> {
+        let block = self.reflection.get_uniform_block(name)
+            .ok_or_else(|| GlError::UniformInvalidIndex(name.to_owned(), self.get_object_name()))?;
+
+        // Arrays of blocks are reflected as "Block[0]", their members as "Block.member"
+        let prefix = format!("{}.", name.split('[').next().unwrap_or(name));
+        let mut members: Vec<_> = self.reflection.uniforms.iter()
+            .filter(|uniform| uniform.block_index == block.index as i32)
+            .collect();
+        members.sort_by_key(|uniform| uniform.offset);
+
+        for member in members {
+            let member_name = member.name.strip_prefix(&prefix).unwrap_or(&member.name);
+            let expected = layout.get_member(member_name).map(|expected| expected.offset);
+
+            if expected != Some(member.offset as usize) {
+                return Err(GlError::BlockMemberMismatch(
+                    name.to_owned(),
+                    member_name.to_owned(),
+                    expected,
+                    member.offset as usize,
+                    self.get_object_name()
+                ));
             }
+        }
+
+        // Not every driver rounds the size up to a multiple of 16
+        let size = block.data_size as usize;
+        if layout.size < size || layout.size > size.div_ceil(16) * 16 {
+            return Err(GlError::BlockSizeMismatch(name.to_owned(), layout.size, block.data_size as usize, self.get_object_name()));
+        }
 
-            gl::UniformBlockBinding(self.id, uniform_block_index, 0);
+        Ok(())
+    }
+
+    // Same as bind_to_ubo() for a shader storage block, used by StorageBuffer.
+    // GLES can't change the binding of storage blocks, so there the binding set with layout(binding = N)
+    // in the shader is given to the name instead, which fails if another block already has it
+    pub fn bind_to_ssbo(&self, name: &str) -> Result<(), GlError> {
+        self.gl.require(GlFeature::ShaderStorageBuffer)?;
+
+        let block_index = self.gl.get_program_resource_index(self.id, gl::SHADER_STORAGE_BLOCK, &CString::new(name)?);
+        if block_index == gl::INVALID_INDEX {
+            return Err(GlError::StorageBlockNotFound(name.to_owned(), ObjectName::new(self.id, &self.label)));
+        }
+
+        match self.gl.get_api() {
+            GlApi::Gl => {
+                let binding = self.gl.get_block_binding(BufferBlockType::ShaderStorage, name)?;
+                self.gl.shader_storage_block_binding(self.id, block_index, binding);
+            },
+            GlApi::Gles => {
+                let binding = self.gl.get_program_resource_iv(self.id, gl::SHADER_STORAGE_BLOCK, block_index, &[gl::BUFFER_BINDING])[0];
+                self.gl.set_block_binding(BufferBlockType::ShaderStorage, name, binding as u32)?;
+            }
         }
 
+        self.add_buffer_block(BufferBlockType::ShaderStorage, name);
+
         Ok(())
     }
 
+    fn add_buffer_block(&self, type_: BufferBlockType, name: &str) {
+        let mut buffer_blocks = self.buffer_blocks.borrow_mut();
+        if !buffer_blocks.iter().any(|(block_type, block)| *block_type == type_ && block == name) {
+            buffer_blocks.push((type_, name.to_owned()));
+        }
+    }
+
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    pub fn get_context(&self) -> &GlContext {
+        &self.gl
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::PROGRAM, self.id, label);
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+
+    pub fn is_separable(&self) -> bool {
+        self.separable
+    }
+
+    // In pipeline order
+    pub fn get_stages(&self) -> &[ShaderCompileType] {
+        &self.stages
+    }
+
+    pub(crate) fn get_key(&self) -> u32 {
+        self.key
+    }
+
+    pub(crate) fn get_object_name(&self) -> ObjectName {
+        ObjectName::new(self.id, &self.label)
+    }
+
+    // Name shown in tracing spans, from the bundle that was compiled
+    fn get_source_name(&self) -> &str {
+        self.label.as_deref().unwrap_or("unnamed")
+    }
 }
 
 impl Drop for ShaderProgram {
     fn drop(&mut self) {
-        unsafe {
-            gl::UseProgram(0);
-            gl::DeleteProgram(self.id);
-        }
+        self.gl.use_program(0);
+        self.gl.delete_program(self.id);
     }
 }
 
-#[derive(Debug, Clone)]
+// "lights[2]" is element 2 of "lights", anything else is element 0
+fn split_array_index(name: &str) -> (&str, usize) {
+    name.strip_suffix(']')
+        .and_then(|name| name.rsplit_once('['))
+        .and_then(|(base_name, index)| Some((base_name, index.parse().ok()?)))
+        .unwrap_or((name, 0))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ShaderCompileType {
     Program,
     Vertex,
     Fragment,
-    Geometry
+    Geometry,
+    Compute,
+    TessControl,
+    TessEvaluation
+}
+
+impl ShaderCompileType {
+    // For glUseProgramStages, 0 for Program
+    pub fn get_stage_bit(&self) -> gl::types::GLbitfield {
+        match self {
+            ShaderCompileType::Program => 0,
+            ShaderCompileType::Vertex => gl::VERTEX_SHADER_BIT,
+            ShaderCompileType::TessControl => gl::TESS_CONTROL_SHADER_BIT,
+            ShaderCompileType::TessEvaluation => gl::TESS_EVALUATION_SHADER_BIT,
+            ShaderCompileType::Geometry => gl::GEOMETRY_SHADER_BIT,
+            ShaderCompileType::Fragment => gl::FRAGMENT_SHADER_BIT,
+            ShaderCompileType::Compute => gl::COMPUTE_SHADER_BIT
+        }
+    }
 }
 
 impl Display for ShaderCompileType {
@@ -290,26 +772,148 @@ impl Display for ShaderCompileType {
             ShaderCompileType::Program => "Program",
             ShaderCompileType::Vertex => "Vertex",
             ShaderCompileType::Geometry => "Geometry",
-            ShaderCompileType::Fragment => "Fragment"
+            ShaderCompileType::Fragment => "Fragment",
+            ShaderCompileType::Compute => "Compute",
+            ShaderCompileType::TessControl => "Tessellation control",
+            ShaderCompileType::TessEvaluation => "Tessellation evaluation"
         };
 
         write!(f, "{}", str)
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct ShaderCodeBundle {
+    // Used as the program's debug label
+    pub name: Option<String>,
     pub vertex: Option<String>,
+    // Both tessellation stages are needed to draw patches, see VertexArray::set_patch_vertices()
+    pub tess_control: Option<String>,
+    pub tess_evaluation: Option<String>,
     pub geometry: Option<String>,
-    pub fragment: Option<String>
+    pub fragment: Option<String>,
+    // Only for ComputeProgram, which can't have any other stage
+    pub compute: Option<String>,
+    // SPIR-V stages, used instead of the GLSL ones above (a program can't mix both)
+    pub spirv: Vec<(ShaderCompileType, SpirvModule)>,
+    // Files the code was loaded from by ShaderLoader, indexed by GLSL source string number
+    pub source_files: Vec<PathBuf>
 }
 
 impl ShaderCodeBundle {
     pub fn get_vec(&self) -> Vec<(&Option<String>, ShaderCompileType)> {
         vec![
             (&self.vertex, ShaderCompileType::Vertex),
+            (&self.tess_control, ShaderCompileType::TessControl),
+            (&self.tess_evaluation, ShaderCompileType::TessEvaluation),
             (&self.geometry, ShaderCompileType::Geometry),
-            (&self.fragment, ShaderCompileType::Fragment)
+            (&self.fragment, ShaderCompileType::Fragment),
+            (&self.compute, ShaderCompileType::Compute)
         ]
     }
-}
\ No newline at end of file
+
+    // GLSL or SPIR-V, in pipeline order
+    pub fn get_stages(&self) -> Vec<ShaderCompileType> {
+        self.get_vec().into_iter()
+            .filter(|(code, type_)| code.is_some() || self.spirv.iter().any(|(spirv_type, _)| spirv_type == type_))
+            .map(|(_, type_)| type_)
+            .collect()
+    }
+
+    // None for ShaderCompileType::Program, which isn't a stage
+    pub fn get_stage_mut(&mut self, type_: &ShaderCompileType) -> Option<&mut Option<String>> {
+        match type_ {
+            ShaderCompileType::Vertex => Some(&mut self.vertex),
+            ShaderCompileType::TessControl => Some(&mut self.tess_control),
+            ShaderCompileType::TessEvaluation => Some(&mut self.tess_evaluation),
+            ShaderCompileType::Geometry => Some(&mut self.geometry),
+            ShaderCompileType::Fragment => Some(&mut self.fragment),
+            ShaderCompileType::Compute => Some(&mut self.compute),
+            ShaderCompileType::Program => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Matrix4;
+    use crate::{BlockLayoutInfo, GlContext, GlError, GlFeature, gl, mock_backend::testing::{mock_context, set_gles}};
+    use super::{ShaderCodeBundle, ShaderProgram};
+
+    fn create_program(gl: &GlContext) -> ShaderProgram {
+        let bundle = ShaderCodeBundle {
+            vertex: Some(String::from("void main() {}")),
+            fragment: Some(String::from("void main() {}")),
+            ..Default::default()
+        };
+
+        ShaderProgram::new(gl, bundle).unwrap()
+    }
+
+    #[test]
+    fn gles_rejects_doubles() {
+        let (mock, gl) = mock_context(set_gles);
+        let program = create_program(&gl);
+        mock.clear_calls();
+
+        // The GLES backend can't set these, so they never reach it
+        assert!(matches!(
+            program.set("scale", &1.0f64),
+            Err(GlError::FeatureNotSupported(GlFeature::DoublePrecision))
+        ));
+        assert!(matches!(
+            program.set("transform", &Matrix4::<f64>::from_scale(1.0)),
+            Err(GlError::FeatureNotSupported(GlFeature::DoublePrecision))
+        ));
+        assert!(matches!(
+            unsafe { program.set_unsafe("scale", &[1.0f64, 2.0][..]) },
+            Err(GlError::FeatureNotSupported(GlFeature::DoublePrecision))
+        ));
+        assert_eq!(mock.count("Uniform1dv") + mock.count("UniformMatrix4dv"), 0);
+
+        program.set("scale", &1.0f32).unwrap();
+        assert_eq!(mock.count("Uniform1fv"), 1);
+    }
+
+    #[test]
+    fn check_block_layout_compares_offsets_and_size() {
+        // layout(std140) uniform Light { vec3 position; float intensity; vec4 color; } light;
+        let (_, gl) = mock_context(|mock| {
+            mock.set_program_resource(gl::UNIFORM_BLOCK, "Light", &[(gl::BUFFER_BINDING, 0), (gl::BUFFER_DATA_SIZE, 32)]);
+            mock.set_program_resource(gl::UNIFORM, "Light.color", &[(gl::BLOCK_INDEX, 0), (gl::OFFSET, 16)]);
+            mock.set_program_resource(gl::UNIFORM, "Light.position", &[(gl::BLOCK_INDEX, 0), (gl::OFFSET, 0)]);
+            mock.set_program_resource(gl::UNIFORM, "Light.intensity", &[(gl::BLOCK_INDEX, 0), (gl::OFFSET, 12)]);
+            mock.set_program_resource(gl::UNIFORM, "tint", &[(gl::BLOCK_INDEX, -1), (gl::OFFSET, -1)]);
+        });
+        let program = create_program(&gl);
+
+        let layout = |size: usize, members: &[(&str, usize)]| {
+            let mut layout = BlockLayoutInfo::new(size);
+            for (name, offset) in members {
+                layout.add_member(name, *offset);
+            }
+            layout
+        };
+
+        program.check_block_layout("Light", &layout(32, &[("position", 0), ("intensity", 12), ("color", 16)])).unwrap();
+
+        // A vec3 in Rust without the padding std140 adds after it
+        let error = program.check_block_layout("Light", &layout(32, &[("position", 0), ("intensity", 12), ("color", 20)]))
+            .unwrap_err();
+        assert!(matches!(
+            &error,
+            GlError::BlockMemberMismatch(block, member, Some(20), 16, _) if block == "Light" && member == "color"
+        ));
+        let message = error.to_string();
+        assert!(message.contains("'Light'") && message.contains("'color' is at offset 16 in the shader but 20 in Rust"), "{}", message);
+
+        assert!(matches!(
+            program.check_block_layout("Light", &layout(32, &[("position", 0), ("color", 16)])),
+            Err(GlError::BlockMemberMismatch(_, member, None, 12, _)) if member == "intensity"
+        ));
+        assert!(matches!(
+            program.check_block_layout("Light", &layout(48, &[("position", 0), ("intensity", 12), ("color", 16)])),
+            Err(GlError::BlockSizeMismatch(block, 48, 32, _)) if block == "Light"
+        ));
+    }
+}
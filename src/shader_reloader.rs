@@ -0,0 +1,73 @@
+use std::{fs, path::{Path, PathBuf}, time::SystemTime};
+use super::{GlError, GlContext, ShaderCodeBundle, ShaderCompileType, ShaderLoader, ShaderProgram};
+
+// Recompiles a program when any of its source files (includes too) change on disk, found by polling
+// modification times. Call poll() regularly, e.g. once a frame:
+// a broken edit leaves the last working program in place and returns the error
+pub struct ShaderReloader {
+    pub loader: ShaderLoader,
+    name: Option<String>,
+    stages: Vec<(ShaderCompileType, PathBuf)>,
+    // Every file read by the last load, even if it failed
+    watched: Vec<(PathBuf, Option<SystemTime>)>
+}
+
+impl ShaderReloader {
+    pub fn new(loader: ShaderLoader, name: Option<String>) -> ShaderReloader {
+        ShaderReloader { loader, name, stages: Vec::new(), watched: Vec::new() }
+    }
+
+    pub fn add_stage<P: Into<PathBuf>>(&mut self, type_: ShaderCompileType, path: P) {
+        self.stages.push((type_, path.into()));
+    }
+
+    pub fn create_program(&mut self, gl: &GlContext) -> Result<ShaderProgram, GlError> {
+        let bundle = self.load()?;
+
+        ShaderProgram::new(gl, bundle)
+    }
+
+    // Returns whether the program was replaced
+    pub fn poll(&mut self, shader_program: &mut ShaderProgram) -> Result<bool, GlError> {
+        let changed = self.watched.iter().any(|(path, modified)| get_modified(path) != *modified);
+        if !changed {
+            return Ok(false);
+        }
+
+        tracing::info!(source = self.name.as_deref().unwrap_or("unnamed"), "shader sources changed, reloading");
+
+        // The times are updated first, so a broken edit is only reported once
+        let bundle = self.load()?;
+        shader_program.compile_program(bundle)?;
+
+        Ok(true)
+    }
+
+    fn load(&mut self) -> Result<ShaderCodeBundle, GlError> {
+        let mut bundle = ShaderCodeBundle { name: self.name.clone(), ..Default::default() };
+        let mut result = Ok(());
+
+        for (type_, path) in self.stages.iter() {
+            result = self.loader.load(&mut bundle, type_.clone(), path);
+            if result.is_err() { break; }
+        }
+
+        // The stage files are watched too, in case one couldn't be read
+        let mut watched: Vec<PathBuf> = self.stages.iter().map(|(_, path)| path.clone()).collect();
+        watched.extend(bundle.source_files.iter().cloned());
+
+        self.watched = watched.into_iter()
+            .map(|path| {
+                let modified = get_modified(&path);
+                (path, modified)
+            })
+            .collect();
+
+        result.map(|_| bundle)
+    }
+}
+
+// None if the file can't be read, so it showing up again counts as a change
+fn get_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,295 @@
+use std::{collections::{BTreeMap, HashMap}, fmt::Display};
+use super::{GlError, GlContext, ProgramCache, ShaderCodeBundle, ShaderProgram, shader_loader::insert_defines};
+
+// A set of defines picking one permutation out of ShaderVariants
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct ShaderVariant {
+    // None for flags, which are defined as 1
+    defines: BTreeMap<String, Option<String>>
+}
+
+impl ShaderVariant {
+    pub fn new() -> ShaderVariant {
+        ShaderVariant::default()
+    }
+
+    // Chained versions of enable() and set()
+    pub fn with(mut self, flag: &str) -> ShaderVariant {
+        self.enable(flag);
+        self
+    }
+
+    pub fn with_value(mut self, option: &str, value: &str) -> ShaderVariant {
+        self.set(option, value);
+        self
+    }
+
+    pub fn enable(&mut self, flag: &str) {
+        self.defines.insert(flag.to_owned(), None);
+    }
+
+    pub fn set(&mut self, option: &str, value: &str) {
+        self.defines.insert(option.to_owned(), Some(value.to_owned()));
+    }
+
+    pub fn disable(&mut self, name: &str) {
+        self.defines.remove(name);
+    }
+
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.defines.contains_key(name)
+    }
+
+    fn get_defines(&self) -> Vec<(String, String)> {
+        self.defines.iter()
+            .map(|(name, value)| (name.clone(), value.clone().unwrap_or_else(|| String::from("1"))))
+            .collect()
+    }
+}
+
+impl Display for ShaderVariant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.defines.is_empty() {
+            return write!(f, "default");
+        }
+
+        let defines: Vec<String> = self.defines.iter()
+            .map(|(name, value)| match value {
+                Some(value) => format!("{}={}", name, value),
+                None => name.clone()
+            })
+            .collect();
+
+        write!(f, "{}", defines.join(" "))
+    }
+}
+
+// Compiles permutations of one GLSL bundle on first use, each with a different set of defines added after
+// #version. Defines have to be declared first, as flags or as options with a list of values, so typos and
+// combinations that were ruled out are reported before anything is compiled
+pub struct ShaderVariants {
+    base: ShaderCodeBundle,
+    flags: Vec<String>,
+    // Any value is accepted when the list is empty
+    options: Vec<(String, Vec<String>)>,
+    // At most one define of each group can be enabled
+    exclusive: Vec<Vec<String>>,
+    // (define, define it needs)
+    requirements: Vec<(String, String)>,
+    cache: Option<ProgramCache>,
+    programs: HashMap<ShaderVariant, ShaderProgram>,
+    gl: GlContext
+}
+
+impl ShaderVariants {
+    pub fn new(gl: &GlContext, base: ShaderCodeBundle) -> ShaderVariants {
+        ShaderVariants {
+            base,
+            flags: Vec::new(),
+            options: Vec::new(),
+            exclusive: Vec::new(),
+            requirements: Vec::new(),
+            cache: None,
+            programs: HashMap::new(),
+            gl: gl.clone()
+        }
+    }
+
+    pub fn add_flag(&mut self, name: &str) {
+        self.flags.push(name.to_owned());
+    }
+
+    pub fn add_option(&mut self, name: &str, values: &[&str]) {
+        self.options.push((name.to_owned(), values.iter().map(|value| value.to_string()).collect()));
+    }
+
+    pub fn add_exclusive(&mut self, names: &[&str]) {
+        self.exclusive.push(names.iter().map(|name| name.to_string()).collect());
+    }
+
+    pub fn add_requirement(&mut self, name: &str, required: &str) {
+        self.requirements.push((name.to_owned(), required.to_owned()));
+    }
+
+    // Variants compiled from now on go through the cache
+    pub fn set_program_cache(&mut self, cache: ProgramCache) {
+        self.cache = Some(cache);
+    }
+
+    // Compiles the variant the first time it is asked for. Failed compiles aren't kept, so they are retried
+    pub fn get(&mut self, variant: &ShaderVariant) -> Result<&ShaderProgram, GlError> {
+        if !self.programs.contains_key(variant) {
+            self.validate(variant)?;
+
+            let program = self.compile(variant)?;
+            self.programs.insert(variant.clone(), program);
+        }
+
+        Ok(&self.programs[variant])
+    }
+
+    pub fn validate(&self, variant: &ShaderVariant) -> Result<(), GlError> {
+        let invalid = |error: String| Err(GlError::InvalidShaderVariant(variant.to_string(), error));
+
+        if !self.base.spirv.is_empty() && !variant.defines.is_empty() {
+            return invalid(String::from("Defines can't be added to SPIR-V stages"));
+        }
+
+        for (name, value) in variant.defines.iter() {
+            let is_flag = self.flags.contains(name);
+            let option = self.options.iter().find(|(option, _)| option == name);
+
+            match (value, is_flag, option) {
+                (None, true, _) => (),
+                (Some(_), true, _) => return invalid(format!("{} is a flag and takes no value", name)),
+                (None, false, Some(_)) => return invalid(format!("{} needs a value", name)),
+                (Some(value), false, Some((_, values))) => {
+                    if !values.is_empty() && !values.contains(value) {
+                        return invalid(format!("{} can't be {}, it can be {}", name, value, values.join(", ")));
+                    }
+                },
+                (_, false, None) => return invalid(format!("{} was not declared", name))
+            }
+        }
+
+        for group in self.exclusive.iter() {
+            let enabled: Vec<&str> = group.iter()
+                .filter(|name| variant.is_enabled(name))
+                .map(|name| name.as_str())
+                .collect();
+
+            if enabled.len() > 1 {
+                return invalid(format!("{} can't be enabled together", enabled.join(" and ")));
+            }
+        }
+
+        for (name, required) in self.requirements.iter() {
+            if variant.is_enabled(name) && !variant.is_enabled(required) {
+                return invalid(format!("{} requires {}", name, required));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_programs(&self) -> impl Iterator<Item = (&ShaderVariant, &ShaderProgram)> {
+        self.programs.iter()
+    }
+
+    pub fn get_programs_mut(&mut self) -> impl Iterator<Item = (&ShaderVariant, &mut ShaderProgram)> {
+        self.programs.iter_mut()
+    }
+
+    // Deletes every compiled variant, they are compiled again when next asked for
+    pub fn clear(&mut self) {
+        self.programs.clear();
+    }
+
+    // Replaces the code of every variant, e.g. after the sources changed on disk
+    pub fn set_base(&mut self, base: ShaderCodeBundle) {
+        self.base = base;
+        self.clear();
+    }
+
+    fn compile(&self, variant: &ShaderVariant) -> Result<ShaderProgram, GlError> {
+        let defines = variant.get_defines();
+        let mut bundle = self.base.clone();
+
+        bundle.name = Some(format!("{} [{}]", self.base.name.as_deref().unwrap_or("unnamed"), variant));
+        for (code, type_) in self.base.get_vec() {
+            if let (Some(code), Some(stage)) = (code, bundle.get_stage_mut(&type_)) {
+                *stage = Some(insert_defines(code, &defines));
+            }
+        }
+
+        tracing::debug!(source = bundle.name.as_deref(), "compiling shader variant");
+
+        match &self.cache {
+            Some(cache) => cache.create_program(&self.gl, bundle),
+            None => ShaderProgram::new(&self.gl, bundle)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GlError, ShaderCodeBundle, gl, mock_backend::testing::mock_context};
+    use super::{ShaderVariant, ShaderVariants};
+
+    fn create_variants(gl: &crate::GlContext) -> ShaderVariants {
+        let bundle = ShaderCodeBundle {
+            name: Some(String::from("lit")),
+            vertex: Some(String::from("#version 330 core\nvoid main() {}")),
+            fragment: Some(String::from("#version 330 core\nvoid main() {}")),
+            ..Default::default()
+        };
+
+        let mut variants = ShaderVariants::new(gl, bundle);
+        variants.add_flag("NORMAL_MAP");
+        variants.add_flag("SKINNING");
+        variants.add_flag("INSTANCED");
+        variants.add_option("SHADOW_CASCADES", &["1", "2", "4"]);
+        variants.add_option("LIGHTS", &[]);
+        variants.add_exclusive(&["SKINNING", "INSTANCED"]);
+        variants.add_requirement("NORMAL_MAP", "LIGHTS");
+        variants
+    }
+
+    #[test]
+    fn validate_reports_invalid_variants() {
+        let (_, gl) = mock_context(|_| ());
+        let variants = create_variants(&gl);
+
+        let cases = [
+            (ShaderVariant::new().with("PARALLAX"), "PARALLAX was not declared"),
+            (ShaderVariant::new().with_value("SKINNING", "1"), "SKINNING is a flag and takes no value"),
+            (ShaderVariant::new().with("SHADOW_CASCADES"), "SHADOW_CASCADES needs a value"),
+            (ShaderVariant::new().with_value("SHADOW_CASCADES", "3"), "SHADOW_CASCADES can't be 3, it can be 1, 2, 4"),
+            (ShaderVariant::new().with("SKINNING").with("INSTANCED"), "SKINNING and INSTANCED can't be enabled together"),
+            (ShaderVariant::new().with("NORMAL_MAP"), "NORMAL_MAP requires LIGHTS")
+        ];
+
+        for (variant, expected) in cases {
+            match variants.validate(&variant) {
+                Err(GlError::InvalidShaderVariant(name, error)) => {
+                    assert_eq!(name, variant.to_string());
+                    assert_eq!(error, expected);
+                },
+                result => panic!("{} gave {:?}", variant, result)
+            }
+        }
+
+        // Options with no list take any value
+        let variant = ShaderVariant::new().with("NORMAL_MAP").with_value("LIGHTS", "16").with("SKINNING");
+        variants.validate(&variant).unwrap();
+        assert_eq!(variant.to_string(), "LIGHTS=16 NORMAL_MAP SKINNING");
+        assert_eq!(ShaderVariant::new().to_string(), "default");
+    }
+
+    #[test]
+    fn get_compiles_each_variant_once() {
+        let (mock, gl) = mock_context(|_| ());
+        let mut variants = create_variants(&gl);
+        let variant = ShaderVariant::new().with("INSTANCED").with_value("SHADOW_CASCADES", "2");
+
+        variants.get(&variant).unwrap();
+        assert_eq!(mock.count("LinkProgram"), 1);
+        // Defines go after #version, then #line puts the line numbers back
+        let source = mock.find("ShaderSource").unwrap().text.unwrap();
+        assert_eq!(source, "#version 330 core\n#define INSTANCED 1\n#define SHADOW_CASCADES 2\n#line 2\nvoid main() {}\n");
+
+        variants.get(&variant).unwrap();
+        assert_eq!(mock.count("LinkProgram"), 1);
+
+        // Failed compiles are tried again
+        let other = ShaderVariant::new().with("SKINNING");
+        mock.set_shader_iv(gl::COMPILE_STATUS, gl::FALSE as i32);
+        assert!(matches!(variants.get(&other), Err(GlError::ShaderCompileError(..))));
+        mock.set_shader_iv(gl::COMPILE_STATUS, gl::TRUE as i32);
+        variants.get(&other).unwrap();
+        assert_eq!(variants.get_programs().count(), 2);
+
+        variants.clear();
+        assert_eq!(variants.get_programs().count(), 0);
+    }
+}
@@ -8,15 +8,15 @@ pub struct Skybox {
 
 impl Skybox {
     pub fn draw(&self, shader_program: &ShaderProgram) -> Result<(), GlError> {
-        unsafe {
-            // Change depth func so test values pass when they are equal to the buffer's content
-            gl::DepthFunc(gl::LEQUAL);
+        let gl = self.model.get_context();
 
-            shader_program.use_program();
-            self.model.draw(shader_program)?;
+        // Change depth func so test values pass when they are equal to the buffer's content
+        gl.depth_func(gl::LEQUAL);
 
-            gl::DepthFunc(gl::LESS);
-        }
+        shader_program.use_program();
+        self.model.draw(shader_program)?;
+
+        gl.depth_func(gl::LESS);
 
         Ok(())
     }
@@ -0,0 +1,38 @@
+use std::{fs, path::Path};
+use super::GlError;
+
+const SPIRV_MAGIC: u32 = 0x07230203;
+
+// Compiled SPIR-V for one stage, loaded with glShaderBinary and glSpecializeShader (GL 4.6 or GL_ARB_gl_spirv).
+// Added to ShaderCodeBundle::spirv in place of GLSL source
+#[derive(Debug, Clone)]
+pub struct SpirvModule {
+    pub code: Vec<u8>,
+    pub entry_point: String,
+    // (constant_id, value). Floats are passed with to_bits(), bools as 0 or 1
+    pub specialization_constants: Vec<(u32, u32)>
+}
+
+impl SpirvModule {
+    // Uses "main" as the entry point
+    pub fn new(code: Vec<u8>) -> SpirvModule {
+        SpirvModule { code, entry_point: String::from("main"), specialization_constants: Vec::new() }
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<SpirvModule, GlError> {
+        let path = path.as_ref();
+        let code = fs::read(path).map_err(|err| GlError::ShaderLoadError(path.to_owned(), err.to_string()))?;
+
+        let magic = code.get(0..4).map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()));
+        if magic != Some(SPIRV_MAGIC) || code.len() % 4 != 0 {
+            return Err(GlError::ShaderLoadError(path.to_owned(), String::from("Not a SPIR-V module")));
+        }
+
+        Ok(SpirvModule::new(code))
+    }
+
+    pub fn specialize(&mut self, constant_id: u32, value: u32) {
+        self.specialization_constants.retain(|(id, _)| *id != constant_id);
+        self.specialization_constants.push((constant_id, value));
+    }
+}
@@ -0,0 +1,307 @@
+use std::{cell::{Cell, RefCell}, collections::HashMap};
+use super::gl::{self, types::GLenum};
+
+// How many GL calls the state cache skipped because they would have changed nothing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StateCacheStats {
+    pub programs: u64,
+    pub program_pipelines: u64,
+    pub vertex_arrays: u64,
+    pub textures: u64,
+    pub active_textures: u64,
+    pub framebuffers: u64,
+    pub capabilities: u64,
+    pub depth_funcs: u64,
+    pub blend_funcs: u64,
+    pub cull_faces: u64,
+    pub patch_vertices: u64
+}
+
+impl StateCacheStats {
+    pub fn total(&self) -> u64 {
+        self.programs + self.program_pipelines + self.vertex_arrays + self.textures + self.active_textures
+            + self.framebuffers + self.capabilities + self.depth_funcs + self.blend_funcs + self.cull_faces + self.patch_vertices
+    }
+}
+
+// Last known value of the GL state the crate changes, owned by the context.
+// None means unknown (never set or invalidated), in which case the call always goes through.
+// Every method returns whether the GL call still has to be made
+#[derive(Default)]
+pub(crate) struct StateCache {
+    program: Cell<Option<u32>>,
+    program_pipeline: Cell<Option<u32>>,
+    vertex_array: Cell<Option<u32>>,
+    // Unit index, not the GL_TEXTUREi enum
+    active_texture: Cell<Option<u32>>,
+    // (unit, target) -> texture
+    textures: RefCell<HashMap<(u32, GLenum), u32>>,
+    // Needed to know which target glBindTextureUnit binds to
+    texture_targets: RefCell<HashMap<u32, GLenum>>,
+    draw_framebuffer: Cell<Option<u32>>,
+    read_framebuffer: Cell<Option<u32>>,
+    capabilities: RefCell<HashMap<GLenum, bool>>,
+    depth_func: Cell<Option<GLenum>>,
+    blend_func: Cell<Option<(GLenum, GLenum)>>,
+    cull_face: Cell<Option<GLenum>>,
+    patch_vertices: Cell<Option<i32>>,
+    stats: Cell<StateCacheStats>
+}
+
+impl StateCache {
+    pub(crate) fn get_stats(&self) -> StateCacheStats {
+        self.stats.get()
+    }
+
+    pub(crate) fn reset_stats(&self) {
+        self.stats.set(StateCacheStats::default());
+    }
+
+    // Forgets all bindings and state, the counters and known texture targets are kept
+    pub(crate) fn invalidate(&self) {
+        self.program.set(None);
+        self.program_pipeline.set(None);
+        self.vertex_array.set(None);
+        self.active_texture.set(None);
+        self.textures.borrow_mut().clear();
+        self.draw_framebuffer.set(None);
+        self.read_framebuffer.set(None);
+        self.capabilities.borrow_mut().clear();
+        self.depth_func.set(None);
+        self.blend_func.set(None);
+        self.cull_face.set(None);
+        self.patch_vertices.set(None);
+    }
+
+    fn update<T: PartialEq + Copy>(&self, cell: &Cell<Option<T>>, value: T, count: fn(&mut StateCacheStats)) -> bool {
+        if cell.get() == Some(value) {
+            self.skipped(count);
+            false
+        } else {
+            cell.set(Some(value));
+            true
+        }
+    }
+
+    fn skipped(&self, count: fn(&mut StateCacheStats)) {
+        let mut stats = self.stats.get();
+        count(&mut stats);
+        self.stats.set(stats);
+    }
+
+    pub(crate) fn use_program(&self, program: u32) -> bool {
+        self.update(&self.program, program, |stats| stats.programs += 1)
+    }
+
+    pub(crate) fn bind_program_pipeline(&self, pipeline: u32) -> bool {
+        self.update(&self.program_pipeline, pipeline, |stats| stats.program_pipelines += 1)
+    }
+
+    // Deleting a bound pipeline reverts the binding to 0
+    pub(crate) fn delete_program_pipeline(&self, pipeline: u32) {
+        if self.program_pipeline.get() == Some(pipeline) {
+            self.program_pipeline.set(Some(0));
+        }
+    }
+
+    pub(crate) fn bind_vertex_array(&self, array: u32) -> bool {
+        self.update(&self.vertex_array, array, |stats| stats.vertex_arrays += 1)
+    }
+
+    // Deleting a bound vertex array reverts the binding to 0
+    pub(crate) fn delete_vertex_array(&self, array: u32) {
+        if self.vertex_array.get() == Some(array) {
+            self.vertex_array.set(Some(0));
+        }
+    }
+
+    pub(crate) fn active_texture(&self, texture: GLenum) -> bool {
+        self.update(&self.active_texture, texture - gl::TEXTURE0, |stats| stats.active_textures += 1)
+    }
+
+    pub(crate) fn create_texture(&self, texture: u32, target: GLenum) {
+        self.texture_targets.borrow_mut().insert(texture, target);
+    }
+
+    pub(crate) fn bind_texture(&self, target: GLenum, texture: u32) -> bool {
+        match self.active_texture.get() {
+            Some(unit) => self.bind_unit_target(unit, target, texture),
+            None => {
+                // Could have been any unit
+                self.textures.borrow_mut().retain(|(_, bound_target), _| *bound_target != target);
+                true
+            }
+        }
+    }
+
+    pub(crate) fn bind_texture_unit(&self, unit: u32, texture: u32) -> bool {
+        let target = self.texture_targets.borrow().get(&texture).copied();
+
+        match target {
+            Some(target) => self.bind_unit_target(unit, target, texture),
+            None => {
+                // Binding 0 (or a texture from outside the crate) can affect every target on the unit
+                self.textures.borrow_mut().retain(|(bound_unit, _), _| *bound_unit != unit);
+                true
+            }
+        }
+    }
+
+    fn bind_unit_target(&self, unit: u32, target: GLenum, texture: u32) -> bool {
+        let mut textures = self.textures.borrow_mut();
+
+        if textures.get(&(unit, target)) == Some(&texture) {
+            drop(textures);
+            self.skipped(|stats| stats.textures += 1);
+            false
+        } else {
+            textures.insert((unit, target), texture);
+            true
+        }
+    }
+
+    // Deleting a texture unbinds it from every unit
+    pub(crate) fn delete_texture(&self, texture: u32) {
+        self.texture_targets.borrow_mut().remove(&texture);
+
+        for bound in self.textures.borrow_mut().values_mut() {
+            if *bound == texture {
+                *bound = 0;
+            }
+        }
+    }
+
+    pub(crate) fn bind_framebuffer(&self, target: GLenum, framebuffer: u32) -> bool {
+        let (draw, read) = match target {
+            gl::DRAW_FRAMEBUFFER => (true, false),
+            gl::READ_FRAMEBUFFER => (false, true),
+            _ => (true, true)
+        };
+
+        let unchanged = (!draw || self.draw_framebuffer.get() == Some(framebuffer))
+            && (!read || self.read_framebuffer.get() == Some(framebuffer));
+
+        if unchanged {
+            self.skipped(|stats| stats.framebuffers += 1);
+            return false;
+        }
+
+        if draw { self.draw_framebuffer.set(Some(framebuffer)); }
+        if read { self.read_framebuffer.set(Some(framebuffer)); }
+
+        true
+    }
+
+    // Deleting a bound framebuffer reverts the binding to 0
+    pub(crate) fn delete_framebuffer(&self, framebuffer: u32) {
+        if self.draw_framebuffer.get() == Some(framebuffer) {
+            self.draw_framebuffer.set(Some(0));
+        }
+        if self.read_framebuffer.get() == Some(framebuffer) {
+            self.read_framebuffer.set(Some(0));
+        }
+    }
+
+    pub(crate) fn set_capability(&self, cap: GLenum, enabled: bool) -> bool {
+        let previous = self.capabilities.borrow_mut().insert(cap, enabled);
+
+        if previous == Some(enabled) {
+            self.skipped(|stats| stats.capabilities += 1);
+            false
+        } else {
+            true
+        }
+    }
+
+    pub(crate) fn depth_func(&self, func: GLenum) -> bool {
+        self.update(&self.depth_func, func, |stats| stats.depth_funcs += 1)
+    }
+
+    pub(crate) fn blend_func(&self, sfactor: GLenum, dfactor: GLenum) -> bool {
+        self.update(&self.blend_func, (sfactor, dfactor), |stats| stats.blend_funcs += 1)
+    }
+
+    pub(crate) fn cull_face(&self, mode: GLenum) -> bool {
+        self.update(&self.cull_face, mode, |stats| stats.cull_faces += 1)
+    }
+
+    pub(crate) fn patch_vertices(&self, vertices: i32) -> bool {
+        self.update(&self.patch_vertices, vertices, |stats| stats.patch_vertices += 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gl;
+    use super::StateCache;
+
+    #[test]
+    fn repeated_binds_are_skipped() {
+        let cache = StateCache::default();
+
+        assert!(cache.use_program(3));
+        assert!(!cache.use_program(3));
+        assert!(cache.use_program(4));
+        assert!(cache.bind_vertex_array(1));
+        assert!(!cache.bind_vertex_array(1));
+        assert!(cache.set_capability(gl::DEPTH_TEST, true));
+        assert!(!cache.set_capability(gl::DEPTH_TEST, true));
+        assert!(cache.set_capability(gl::DEPTH_TEST, false));
+
+        let stats = cache.get_stats();
+        assert_eq!((stats.programs, stats.vertex_arrays, stats.capabilities), (1, 1, 1));
+        assert_eq!(stats.total(), 3);
+
+        // Deleting the bound vertex array binds 0
+        cache.delete_vertex_array(1);
+        assert!(!cache.bind_vertex_array(0));
+
+        // The counters survive invalidation, the bindings don't
+        cache.invalidate();
+        assert!(cache.use_program(4));
+        assert_eq!(cache.get_stats().total(), 4);
+        cache.reset_stats();
+        assert_eq!(cache.get_stats().total(), 0);
+    }
+
+    #[test]
+    fn textures_are_cached_per_unit_and_target() {
+        let cache = StateCache::default();
+        cache.create_texture(5, gl::TEXTURE_2D);
+        cache.create_texture(6, gl::TEXTURE_CUBE_MAP);
+
+        assert!(cache.bind_texture_unit(0, 5));
+        assert!(!cache.bind_texture_unit(0, 5));
+        assert!(cache.bind_texture_unit(0, 6));
+        // Different target on the same unit, so 5 is still bound
+        assert!(!cache.bind_texture_unit(0, 5));
+
+        // glBindTexture uses the active unit, which is unknown until set
+        assert!(cache.bind_texture(gl::TEXTURE_2D, 5));
+        assert!(cache.active_texture(gl::TEXTURE0));
+        assert!(cache.bind_texture(gl::TEXTURE_2D, 5));
+        assert!(!cache.bind_texture(gl::TEXTURE_2D, 5));
+
+        // Unknown textures (and 0) may change any target on the unit
+        assert!(cache.bind_texture_unit(0, 0));
+        assert!(cache.bind_texture_unit(0, 6));
+
+        cache.delete_texture(6);
+        assert!(!cache.bind_texture(gl::TEXTURE_CUBE_MAP, 0));
+    }
+
+    #[test]
+    fn framebuffer_targets_are_tracked_separately() {
+        let cache = StateCache::default();
+
+        assert!(cache.bind_framebuffer(gl::FRAMEBUFFER, 2));
+        assert!(!cache.bind_framebuffer(gl::DRAW_FRAMEBUFFER, 2));
+        assert!(!cache.bind_framebuffer(gl::READ_FRAMEBUFFER, 2));
+        assert!(cache.bind_framebuffer(gl::READ_FRAMEBUFFER, 3));
+        assert!(cache.bind_framebuffer(gl::FRAMEBUFFER, 2));
+
+        cache.delete_framebuffer(2);
+        assert!(!cache.bind_framebuffer(gl::FRAMEBUFFER, 0));
+        assert_eq!(cache.get_stats().framebuffers, 3);
+    }
+}
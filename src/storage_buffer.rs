@@ -0,0 +1,174 @@
+use std::marker::PhantomData;
+use super::ShaderProgram;
+use super::{AsStd430, BufferBlockType, GlError, GlContext, GlFeature, Std430Field, gl};
+
+// Buffer behind a shader storage block. Like UniformBuffer it can have no type or hold a struct,
+// deriving AsStd430 for std430 blocks, but shaders can write to it as well, and it can be read back
+pub struct StorageBuffer<T = ()> {
+    id: u32,
+    name: String,
+    buffer_size: isize,
+    binding: u32,
+    label: Option<String>,
+    gl: GlContext,
+    marker: PhantomData<T>
+}
+
+impl StorageBuffer {
+    pub fn new(
+        gl: &GlContext,
+        shader_programs: Vec<&ShaderProgram>,
+        name: &str,
+        buffer_size: isize
+    ) -> Result<StorageBuffer, GlError> {
+        StorageBuffer::create(gl, shader_programs, name, buffer_size)
+    }
+
+    pub unsafe fn write_data<T>(&self, data: *const gl::types::GLvoid, offset: u32) {
+        self.gl.named_buffer_sub_data(self.id, offset as isize, std::mem::size_of::<T>() as isize, data);
+    }
+
+    // Shader writes are only visible after GlContext::memory_barrier(MemoryBarrier::BUFFER_UPDATE)
+    pub unsafe fn read_data<T>(&self, data: *mut gl::types::GLvoid, offset: u32) {
+        self.gl.get_named_buffer_sub_data(self.id, offset as isize, std::mem::size_of::<T>() as isize, data);
+    }
+}
+
+impl<T: AsStd430> StorageBuffer<T> {
+    // The buffer is the size of T's std430 layout. Unlike UniformBuffer::new_typed() the block isn't checked
+    // against the programs: storage block members are reflected through GL_BUFFER_VARIABLE, which
+    // ProgramReflection doesn't query, and a block ending in an unsized array has no fixed size to compare.
+    // Declare the block layout(std430) and use #[std430(offset = N)] to pin offsets at compile time
+    pub fn new_typed(
+        gl: &GlContext,
+        shader_programs: Vec<&ShaderProgram>,
+        name: &str
+    ) -> Result<StorageBuffer<T>, GlError> {
+        StorageBuffer::create(gl, shader_programs, name, std::mem::size_of::<T::Output>() as isize)
+    }
+
+    pub fn set(&self, value: &T) {
+        let data = value.as_std430();
+
+        unsafe {
+            self.gl.named_buffer_sub_data(self.id, 0, std::mem::size_of::<T::Output>() as isize, &data as *const T::Output as *const _);
+        }
+    }
+
+    pub fn set_field<F: AsStd430>(&self, field: Std430Field<T, F>, value: &F) {
+        let data = value.as_std430();
+
+        unsafe {
+            self.gl.named_buffer_sub_data(
+                self.id,
+                field.get_offset() as isize,
+                std::mem::size_of::<F::Output>() as isize,
+                &data as *const F::Output as *const _
+            );
+        }
+    }
+
+    // Reads the whole block back in its std430 form.
+    // Shader writes are only visible after GlContext::memory_barrier(MemoryBarrier::BUFFER_UPDATE)
+    pub fn get(&self) -> T::Output {
+        let mut data = std::mem::MaybeUninit::<T::Output>::zeroed();
+
+        // AsStd430 guarantees any bytes are a valid Output
+        unsafe {
+            self.gl.get_named_buffer_sub_data(self.id, 0, std::mem::size_of::<T::Output>() as isize, data.as_mut_ptr() as *mut _);
+            data.assume_init()
+        }
+    }
+
+    pub fn get_field<F: AsStd430>(&self, field: Std430Field<T, F>) -> F::Output {
+        let mut data = std::mem::MaybeUninit::<F::Output>::zeroed();
+
+        unsafe {
+            self.gl.get_named_buffer_sub_data(
+                self.id,
+                field.get_offset() as isize,
+                std::mem::size_of::<F::Output>() as isize,
+                data.as_mut_ptr() as *mut _
+            );
+            data.assume_init()
+        }
+    }
+}
+
+impl<T> StorageBuffer<T> {
+    fn create(
+        gl: &GlContext,
+        shader_programs: Vec<&ShaderProgram>,
+        name: &str,
+        buffer_size: isize
+    ) -> Result<StorageBuffer<T>, GlError> {
+        gl.require(GlFeature::ShaderStorageBuffer)?;
+        gl.check_limit(
+            "GL_MAX_SHADER_STORAGE_BLOCK_SIZE",
+            buffer_size as i32,
+            gl.get_integer_v(gl::MAX_SHADER_STORAGE_BLOCK_SIZE)
+        )?;
+
+        // Programs go first, on GLES they decide the binding
+        for shader_program in shader_programs.iter() {
+            shader_program.bind_to_ssbo(name)?;
+        }
+
+        let mut storage_buffer = StorageBuffer {
+            id: 0,
+            name: String::from(name),
+            buffer_size,
+            binding: gl.get_block_binding(BufferBlockType::ShaderStorage, name)?,
+            label: None,
+            gl: gl.clone(),
+            marker: PhantomData
+        };
+
+        storage_buffer.create_ssbo();
+
+        Ok(storage_buffer)
+    }
+
+    pub fn register_shader_program(&self, shader_program: &ShaderProgram) -> Result<(), GlError> {
+        shader_program.bind_to_ssbo(self.name.as_str())
+    }
+
+    fn create_ssbo(&mut self) {
+        self.id = self.gl.create_buffer();
+
+        unsafe {
+            self.gl.named_buffer_data(self.id, self.buffer_size, std::ptr::null(), gl::DYNAMIC_COPY);
+        }
+
+        self.bind_ssbo();
+    }
+
+    // Binds the buffer to its block's binding point, e.g. after something else was bound there
+    pub fn bind_ssbo(&self) {
+        self.gl.bind_buffer_range(gl::SHADER_STORAGE_BUFFER, self.binding, self.id, 0, self.buffer_size);
+    }
+
+    // Shared by every program bound to the block
+    pub fn get_binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::BUFFER, self.id, label);
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
+}
+
+impl<T> Drop for StorageBuffer<T> {
+    fn drop(&mut self) {
+        self.gl.delete_buffer(self.id);
+    }
+}
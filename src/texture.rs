@@ -1,23 +1,31 @@
-use super::{GlError, GlImage, gl};
+use std::cell::RefCell;
+use super::{GlError, GlImage, GlContext, GlFeature, ObjectName, gl};
 
 pub struct Texture {
     id: u32,
     target: gl::types::GLenum,
-    can_resize: bool
+    // Needed to bind the texture as an image
+    internal_format: gl::types::GLenum,
+    can_resize: bool,
+    label: RefCell<Option<String>>,
+    gl: GlContext
 }
 
 impl Texture {
-    pub fn from_2d(image: GlImage) -> Texture {
+    pub fn from_2d(gl: &GlContext, image: GlImage) -> Texture {
         let mut texture = Texture {
             id: 0,
             target: gl::TEXTURE_2D,
-            can_resize: false
+            internal_format: image.internal_format,
+            can_resize: false,
+            label: RefCell::new(None),
+            gl: gl.clone()
         };
     
         unsafe {
-            gl::CreateTextures(texture.target, 1, &mut texture.id);
+            texture.id = texture.gl.create_texture(texture.target);
             
-            gl::TextureStorage2D(
+            texture.gl.texture_storage_2d(
                 texture.id,
                 1,
                 image.internal_format,
@@ -25,7 +33,7 @@ impl Texture {
                 image.height
             );
 
-            gl::TextureSubImage2D(
+            texture.gl.texture_sub_image_2d(
                 texture.id,
                 0,
                 0,
@@ -37,27 +45,30 @@ impl Texture {
                 image.bytes.as_ptr() as *const gl::types::GLvoid
             );
             
-            gl::GenerateTextureMipmap(texture.id);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            texture.gl.generate_texture_mipmap(texture.id);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR_MIPMAP_LINEAR as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
         }
 
         texture
     }
 
-    pub fn from_2d_ui(image: GlImage) -> Texture {
+    pub fn from_2d_ui(gl: &GlContext, image: GlImage) -> Texture {
         let mut texture = Texture {
             id: 0,
             target: gl::TEXTURE_2D,
-            can_resize: false
+            internal_format: image.internal_format,
+            can_resize: false,
+            label: RefCell::new(None),
+            gl: gl.clone()
         };
     
         unsafe {
-            gl::CreateTextures(texture.target, 1, &mut texture.id);
+            texture.id = texture.gl.create_texture(texture.target);
             
-            gl::TextureStorage2D(
+            texture.gl.texture_storage_2d(
                 texture.id,
                 1,
                 image.internal_format,
@@ -65,7 +76,7 @@ impl Texture {
                 image.height
             );
 
-            gl::TextureSubImage2D(
+            texture.gl.texture_sub_image_2d(
                 texture.id,
                 0,
                 0,
@@ -77,29 +88,32 @@ impl Texture {
                 image.bytes.as_ptr() as *const gl::types::GLvoid
             );
             
-            gl::GenerateTextureMipmap(texture.id);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            texture.gl.generate_texture_mipmap(texture.id);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
         }
 
         texture
     }
 
-    pub fn from_cubemap(image: GlImage) -> Texture {
+    pub fn from_cubemap(gl: &GlContext, image: GlImage) -> Texture {
         let mut texture = Texture {
             id: 0,
             target: gl::TEXTURE_CUBE_MAP,
-            can_resize: false
+            internal_format: image.internal_format,
+            can_resize: false,
+            label: RefCell::new(None),
+            gl: gl.clone()
         };
 
         let square_size = image.height / 3;
 
         unsafe {
-            gl::CreateTextures(texture.target, 1, &mut texture.id);
+            texture.id = texture.gl.create_texture(texture.target);
 
-            gl::TextureStorage2D(
+            texture.gl.texture_storage_2d(
                 texture.id,
                 1,
                 image.internal_format,
@@ -119,12 +133,12 @@ impl Texture {
                     _ => panic!() // Should not be possible
                 };
 
-                gl::TextureSubImage3D(
+                texture.gl.texture_sub_image_3d(
                     texture.id,
                     0,
                     0,
                     0,
-                    i as i32,
+                    i,
                     square_size,
                     square_size,
                     1,
@@ -137,30 +151,34 @@ impl Texture {
                 );
             }
 
-            gl::TextureParameteri(texture.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_WRAP_R, gl::CLAMP_TO_EDGE as i32);
         }
 
         texture
     }
 
-    // Doesn't need GlError since this only generates gl callback errors
-    pub fn new_mut(width: i32, height: i32) -> Texture {
+    // Doesn't need GlError since this only generates gl callback errors,
+    // which end up in GlContext::take_debug_errors() when debug output is enabled
+    pub fn new_mut(gl: &GlContext, width: i32, height: i32) -> Texture {
         let mut texture = Texture {
             id: 0,
             target: gl::TEXTURE_2D,
-            can_resize: true
+            internal_format: gl::RGBA16F,
+            can_resize: true,
+            label: RefCell::new(None),
+            gl: gl.clone()
         };
 
         unsafe {
             // Create empty texture
             // Does not use DFA so that the texture can be resized
-            gl::CreateTextures(texture.target, 1, &mut texture.id);
-            gl::BindTexture(texture.target, texture.id);
-            gl::TexImage2D(
+            texture.id = texture.gl.create_texture(texture.target);
+            texture.gl.bind_texture(texture.target, texture.id);
+            texture.gl.tex_image_2d(
                 texture.target,
                 0,
                 gl::RGBA16F as i32,
@@ -168,36 +186,34 @@ impl Texture {
                 height,
                 0,
                 gl::RGBA,
-                gl::UNSIGNED_BYTE,
+                gl::FLOAT, // GLES only accepts float types for RGBA16F, even without data
                 std::ptr::null()
             );
-            gl::BindTexture(texture.target, 0);
+            texture.gl.bind_texture(texture.target, 0);
 
             // Nearest just for simplicity
-            gl::TextureParameteri(texture.id, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
-            gl::TextureParameteri(texture.id, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32);
+            texture.gl.texture_parameter_i(texture.id, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32);
         }
 
         texture
     }
 
     pub fn ready_texture(&self, num: u32) {
-        unsafe {
-            gl::BindTextureUnit(num, self.id);
-        }
+        self.gl.bind_texture_unit(num, self.id);
     }
 
     // Unsafe because it doesn't need to be marked as mutable, which would interfere with RC
     // TODO: CHANGE WHEN WRITING RESOURCE MANAGER!
     pub unsafe fn resize(&self, width: i32, height: i32) -> Result<(), GlError> {
         if !self.can_resize {
-            return Err(GlError::CannotResize(self.id));
+            return Err(GlError::CannotResize(ObjectName::new(self.id, &self.label.borrow())));
         }
 
         unsafe {
-            gl::BindTexture(self.target, self.id);
+            self.gl.bind_texture(self.target, self.id);
             // Resizes texture on same ID
-            gl::TexImage2D(
+            self.gl.tex_image_2d(
                 self.target,
                 0,
                 gl::RGBA16F as i32,
@@ -205,40 +221,138 @@ impl Texture {
                 height,
                 0,
                 gl::RGBA,
-                gl::UNSIGNED_BYTE,
+                gl::FLOAT,
                 std::ptr::null()
             );
-            gl::BindTexture(self.target, 0);
+            self.gl.bind_texture(self.target, 0);
         }
 
         Ok(())
     }
 
+    // For reading or writing the texture with imageLoad()/imageStore() in shaders, `access` is
+    // GL_READ_ONLY, GL_WRITE_ONLY or GL_READ_WRITE. Cube maps bind all their faces
+    pub fn bind_image(&self, unit: u32, level: i32, access: gl::types::GLenum) -> Result<(), GlError> {
+        self.gl.require(GlFeature::ComputeShader)?;
+
+        let layered = self.target != gl::TEXTURE_2D;
+        self.gl.bind_image_texture(unit, self.id, level, layered, 0, access, self.internal_format);
+
+        Ok(())
+    }
+
     pub fn get_id(&self) -> u32 {
         self.id
     }
 
-    // Requires GL_ARB_bindless_texture
+    // Doesn't need to be mutable, since textures are shared through Rc (see resize)
+    pub fn set_label(&self, label: &str) {
+        self.gl.label_object(gl::TEXTURE, self.id, label);
+        *self.label.borrow_mut() = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<String> {
+        self.label.borrow().clone()
+    }
+
+    // Requires GL_ARB_bindless_texture, errors if the context doesn't have it (always on GLES)
     // Modifies texture to be immutable, but not its contents
-    pub unsafe fn get_handle(&self) -> u64 {
-        gl::GetTextureHandleARB(self.id)
+    pub unsafe fn get_handle(&self) -> Result<u64, GlError> {
+        self.gl.require(GlFeature::BindlessTexture)?;
+
+        Ok(self.gl.get_texture_handle(self.id))
     }
 
     // Needs to be used whenever texture is intended for use
-    pub unsafe fn make_resident(&self) {
-        gl::MakeTextureHandleResidentARB(self.get_handle());
+    pub unsafe fn make_resident(&self) -> Result<(), GlError> {
+        self.gl.make_texture_handle_resident(self.get_handle()?);
+
+        Ok(())
     }
 
     // Should be used whenever texture is not in use
-    pub unsafe fn revoke_resident(&self) {
-        gl::MakeTextureHandleNonResidentARB(self.get_handle());
+    pub unsafe fn revoke_resident(&self) -> Result<(), GlError> {
+        self.gl.make_texture_handle_non_resident(self.get_handle()?);
+
+        Ok(())
     }
 }
 
 impl Drop for Texture {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteTextures(1, &self.id);
-        }
+        self.gl.delete_texture(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GlCall, GlError, GlFeature, GlImage, gl};
+    use crate::mock_backend::testing::{get_args, get_names, mock_context, set_gles};
+    use super::Texture;
+
+    #[test]
+    fn texture_uploads_and_resizes() {
+        let (mock, gl) = mock_context(|_| ());
+
+        let image = GlImage {
+            bytes: vec![0; 4 * 4 * 4],
+            internal_format: gl::RGBA8,
+            data_format: gl::RGBA,
+            width: 4,
+            height: 4
+        };
+        let texture = Texture::from_2d(&gl, image);
+        let id = texture.get_id() as i64;
+
+        let calls = mock.take_calls();
+        assert_eq!(calls[0].args, [gl::TEXTURE_2D as i64, id]);
+        assert_eq!(get_args(&calls, "TextureStorage2D"), [[id, 1, gl::RGBA8 as i64, 4, 4]]);
+        assert_eq!(
+            get_args(&calls, "TextureSubImage2D"),
+            [[id, 0, 0, 0, 4, 4, gl::RGBA as i64, gl::UNSIGNED_BYTE as i64]]
+        );
+
+        // Only textures made with new_mut can be resized
+        assert!(matches!(unsafe { texture.resize(8, 8) }, Err(GlError::CannotResize(name)) if name.id == id as u32));
+        assert!(mock.take_calls().is_empty());
+
+        texture.ready_texture(3);
+        assert_eq!(mock.take_calls(), vec![GlCall { name: "BindTextureUnit", args: vec![3, id], text: None }]);
+
+        let texture = Texture::new_mut(&gl, 64, 32);
+        let id = texture.get_id() as i64;
+        mock.clear_calls();
+
+        unsafe { texture.resize(128, 64).unwrap() };
+        let calls = mock.take_calls();
+        assert_eq!(get_names(&calls), ["BindTexture", "TexImage2D", "BindTexture"]);
+        assert_eq!(calls[0].args, [gl::TEXTURE_2D as i64, id]);
+        assert_eq!(
+            calls[1].args,
+            [gl::TEXTURE_2D as i64, 0, gl::RGBA16F as i64, 128, 64, 0, gl::RGBA as i64, gl::FLOAT as i64]
+        );
+
+        // The mock has no extensions, so bindless textures are rejected before any GL call
+        assert!(matches!(
+            unsafe { texture.get_handle() },
+            Err(GlError::FeatureNotSupported(GlFeature::BindlessTexture))
+        ));
+        assert_eq!(mock.count("GetTextureHandleARB"), 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn gles_has_no_bindless() {
+        // Even if the driver lists the desktop extension
+        let (mock, gl) = mock_context(|mock| {
+            set_gles(mock);
+            mock.set_extensions(&["GL_ARB_bindless_texture"]);
+        });
+
+        let texture = Texture::new_mut(&gl, 4, 4);
+        assert!(matches!(
+            unsafe { texture.make_resident() },
+            Err(GlError::FeatureNotSupported(GlFeature::BindlessTexture))
+        ));
+        assert_eq!(mock.count("GetTextureHandleARB"), 0);
+    }
+}
@@ -1,19 +1,91 @@
+use std::marker::PhantomData;
 use super::ShaderProgram;
-use super::{GlError, gl};
+use super::{AsStd140, BlockLayoutInfo, BufferBlockType, GlError, GlContext, Std140Field, gl};
 
-// UBO can have multiple types of data, so it doesn't have a type
-pub struct UniformBuffer {
+// Without a type, a UBO can hold any mix of data written with write_data().
+// UniformBuffer<T> holds a struct deriving AsStd140 and only takes whole structs or fields of it
+pub struct UniformBuffer<T = ()> {
     id: u32,
     name: String,
-    buffer_size: isize
+    buffer_size: isize,
+    binding: u32,
+    label: Option<String>,
+    // Checked against the block of every program registered, for typed buffers
+    layout: Option<BlockLayoutInfo>,
+    gl: GlContext,
+    marker: PhantomData<T>
 }
 
 impl UniformBuffer {
-    pub fn new(shader_programs: Vec<&ShaderProgram>, name: &str, buffer_size: isize) -> Result<UniformBuffer, GlError> {
+    pub fn new(
+        gl: &GlContext,
+        shader_programs: Vec<&ShaderProgram>,
+        name: &str,
+        buffer_size: isize
+    ) -> Result<UniformBuffer, GlError> {
+        UniformBuffer::create(gl, shader_programs, name, buffer_size, None)
+    }
+
+    pub unsafe fn write_data<T>(&self, data: *const gl::types::GLvoid, offset: u32) {
+        self.gl.named_buffer_sub_data(self.id, offset as isize, std::mem::size_of::<T>() as isize, data);
+    }
+}
+
+impl<T: AsStd140> UniformBuffer<T> {
+    // The buffer is the size of T's std140 layout. The block in each program has to have the same
+    // layout, see ShaderProgram::check_block_layout()
+    pub fn new_typed(
+        gl: &GlContext,
+        shader_programs: Vec<&ShaderProgram>,
+        name: &str
+    ) -> Result<UniformBuffer<T>, GlError> {
+        let layout = BlockLayoutInfo::std140::<T>();
+
+        UniformBuffer::create(gl, shader_programs, name, layout.size as isize, Some(layout))
+    }
+
+    pub fn set(&self, value: &T) {
+        let data = value.as_std140();
+
+        unsafe {
+            self.gl.named_buffer_sub_data(self.id, 0, std::mem::size_of::<T::Output>() as isize, &data as *const T::Output as *const _);
+        }
+    }
+
+    // Writes one field, e.g. uniform_buffer.set_field(Lights::STD140.ambient, &ambient)
+    pub fn set_field<F: AsStd140>(&self, field: Std140Field<T, F>, value: &F) {
+        let data = value.as_std140();
+
+        unsafe {
+            self.gl.named_buffer_sub_data(
+                self.id,
+                field.get_offset() as isize,
+                std::mem::size_of::<F::Output>() as isize,
+                &data as *const F::Output as *const _
+            );
+        }
+    }
+}
+
+impl<T> UniformBuffer<T> {
+    fn create(
+        gl: &GlContext,
+        shader_programs: Vec<&ShaderProgram>,
+        name: &str,
+        buffer_size: isize,
+        layout: Option<BlockLayoutInfo>
+    ) -> Result<UniformBuffer<T>, GlError> {
+        gl.check_limit("GL_MAX_UNIFORM_BLOCK_SIZE", buffer_size as i32, gl.get_limits().max_uniform_block_size)?;
+
         let mut uniform_buffer = UniformBuffer {
             id: 0,
             name: String::from(name),
-            buffer_size
+            buffer_size,
+            binding: gl.get_block_binding(BufferBlockType::Uniform, name)?,
+            label: None,
+            layout,
+            gl: gl.clone(),
+            marker: PhantomData
         };
 
         for shader_program in shader_programs.iter() {
@@ -26,38 +98,49 @@ impl UniformBuffer {
     }
 
     pub fn register_shader_program(&self, shader_program: &ShaderProgram) -> Result<(), GlError> {
+        if let Some(layout) = &self.layout {
+            shader_program.check_block_layout(self.name.as_str(), layout)?;
+        }
+
         shader_program.bind_to_ubo(self.name.as_str())
     }
 
     pub fn create_ubo(&mut self) {
+        self.id = self.gl.create_buffer();
+
         unsafe {
-            gl::CreateBuffers(1, &mut self.id);
-            gl::NamedBufferData(self.id, self.buffer_size, std::ptr::null(), gl::DYNAMIC_DRAW);
-            gl::BindBufferRange(gl::UNIFORM_BUFFER, 0, self.id, 0, self.buffer_size);
+            self.gl.named_buffer_data(self.id, self.buffer_size, std::ptr::null(), gl::DYNAMIC_DRAW);
         }
+
+        self.bind_ubo();
     }
 
+    // Binds the buffer to its block's binding point, e.g. after something else was bound there
     pub fn bind_ubo(&self) {
-        unsafe {
-            gl::BindBufferRange(gl::UNIFORM_BUFFER, 0, self.id, 0, self.buffer_size);
-        }
+        self.gl.bind_buffer_range(gl::UNIFORM_BUFFER, self.binding, self.id, 0, self.buffer_size);
     }
 
-    pub fn write_data<T>(&self, data: *const gl::types::GLvoid, offset: u32) {
-        unsafe {
-            gl::NamedBufferSubData(self.id, offset as isize, std::mem::size_of::<T>() as isize, data);
-        }
+    // Shared by every program bound to the block
+    pub fn get_binding(&self) -> u32 {
+        self.binding
     }
 
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::BUFFER, self.id, label);
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
 }
 
-impl Drop for UniformBuffer {
+impl<T> Drop for UniformBuffer<T> {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteBuffers(1, &self.id);
-        }
+        self.gl.delete_buffer(self.id);
     }
 }
\ No newline at end of file
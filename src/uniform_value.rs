@@ -0,0 +1,333 @@
+use std::slice;
+use cgmath::{Matrix2, Matrix3, Matrix4, Vector2, Vector3, Vector4};
+use super::{GlBackend, GlFeature, gl::{self, types::GLenum}};
+
+// Anything ShaderProgram::set() can upload: a single value, or a slice/Vec of them for GLSL arrays
+pub trait UniformValue {
+    // Whether this can be set on a uniform of this GL type (GL_FLOAT_VEC3, etc.)
+    fn matches_type(type_: GLenum) -> bool;
+    // Number of array elements written
+    fn get_len(&self) -> usize;
+    fn upload(&self, gl: &dyn GlBackend, location: i32);
+    // Checked by ShaderProgram::set() before uploading
+    fn get_feature() -> Option<GlFeature>;
+}
+
+// One element of a uniform. Vectors are [T; N] or cgmath vectors,
+// matrices are [[T; rows]; columns] (column major, like GLSL) or cgmath matrices
+pub trait UniformElement: Sized {
+    fn matches_type(type_: GLenum) -> bool;
+    fn upload_slice(values: &[Self], gl: &dyn GlBackend, location: i32);
+
+    fn get_feature() -> Option<GlFeature> {
+        None
+    }
+}
+
+impl<T: UniformElement> UniformValue for T {
+    fn matches_type(type_: GLenum) -> bool {
+        T::matches_type(type_)
+    }
+
+    fn get_len(&self) -> usize {
+        1
+    }
+
+    fn upload(&self, gl: &dyn GlBackend, location: i32) {
+        T::upload_slice(slice::from_ref(self), gl, location)
+    }
+
+    fn get_feature() -> Option<GlFeature> {
+        T::get_feature()
+    }
+}
+
+impl<T: UniformElement> UniformValue for [T] {
+    fn matches_type(type_: GLenum) -> bool {
+        T::matches_type(type_)
+    }
+
+    fn get_len(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, gl: &dyn GlBackend, location: i32) {
+        T::upload_slice(self, gl, location)
+    }
+
+    fn get_feature() -> Option<GlFeature> {
+        T::get_feature()
+    }
+}
+
+impl<T: UniformElement> UniformValue for Vec<T> {
+    fn matches_type(type_: GLenum) -> bool {
+        T::matches_type(type_)
+    }
+
+    fn get_len(&self) -> usize {
+        self.len()
+    }
+
+    fn upload(&self, gl: &dyn GlBackend, location: i32) {
+        T::upload_slice(self, gl, location)
+    }
+
+    fn get_feature() -> Option<GlFeature> {
+        T::get_feature()
+    }
+}
+
+// Views the elements as their scalars, only used on types that are #[repr(C)] arrays of S
+fn flatten<T, S>(values: &[T]) -> &[S] {
+    let len = std::mem::size_of_val(values) / std::mem::size_of::<S>();
+    unsafe { slice::from_raw_parts(values.as_ptr() as *const S, len) }
+}
+
+macro_rules! uniform_vector {
+    ($type_:ty, $scalar:ty, $feature:expr, $components:expr, $upload:ident, $($gl_type:path)|+) => {
+        impl UniformElement for $type_ {
+            fn matches_type(type_: GLenum) -> bool {
+                matches!(type_, $($gl_type)|+)
+            }
+
+            fn upload_slice(values: &[Self], gl: &dyn GlBackend, location: i32) {
+                gl.$upload(location, $components, flatten::<Self, $scalar>(values))
+            }
+
+            fn get_feature() -> Option<GlFeature> {
+                $feature
+            }
+        }
+    };
+}
+
+macro_rules! uniform_matrix {
+    ($type_:ty, $scalar:ty, $feature:expr, $columns:expr, $rows:expr, $upload:ident, $gl_type:path) => {
+        impl UniformElement for $type_ {
+            fn matches_type(type_: GLenum) -> bool {
+                type_ == $gl_type
+            }
+
+            fn upload_slice(values: &[Self], gl: &dyn GlBackend, location: i32) {
+                gl.$upload(location, $columns, $rows, false, flatten::<Self, $scalar>(values))
+            }
+
+            fn get_feature() -> Option<GlFeature> {
+                $feature
+            }
+        }
+    };
+}
+
+// Bools are sent as ints, GL converts them back
+macro_rules! uniform_bool_vector {
+    ($type_:ty, $components:expr, $gl_type:path) => {
+        impl UniformElement for $type_ {
+            fn matches_type(type_: GLenum) -> bool {
+                type_ == $gl_type
+            }
+
+            fn upload_slice(values: &[Self], gl: &dyn GlBackend, location: i32) {
+                let ints: Vec<i32> = flatten::<Self, bool>(values).iter().map(|value| *value as i32).collect();
+                gl.uniform_iv(location, $components, &ints)
+            }
+        }
+    };
+}
+
+// Samplers and images are set with the texture unit they read from
+impl UniformElement for i32 {
+    fn matches_type(type_: GLenum) -> bool {
+        matches!(type_, gl::INT | gl::BOOL) || get_glsl_type_name(type_)
+            .is_some_and(|name| name.contains("sampler") || name.contains("image"))
+    }
+
+    fn upload_slice(values: &[Self], gl: &dyn GlBackend, location: i32) {
+        gl.uniform_iv(location, 1, values)
+    }
+}
+
+uniform_vector!([i32; 2], i32, None, 2, uniform_iv, gl::INT_VEC2 | gl::BOOL_VEC2);
+uniform_vector!([i32; 3], i32, None, 3, uniform_iv, gl::INT_VEC3 | gl::BOOL_VEC3);
+uniform_vector!([i32; 4], i32, None, 4, uniform_iv, gl::INT_VEC4 | gl::BOOL_VEC4);
+uniform_vector!(Vector2<i32>, i32, None, 2, uniform_iv, gl::INT_VEC2 | gl::BOOL_VEC2);
+uniform_vector!(Vector3<i32>, i32, None, 3, uniform_iv, gl::INT_VEC3 | gl::BOOL_VEC3);
+uniform_vector!(Vector4<i32>, i32, None, 4, uniform_iv, gl::INT_VEC4 | gl::BOOL_VEC4);
+
+uniform_vector!(u32, u32, None, 1, uniform_uiv, gl::UNSIGNED_INT | gl::BOOL);
+uniform_vector!([u32; 2], u32, None, 2, uniform_uiv, gl::UNSIGNED_INT_VEC2 | gl::BOOL_VEC2);
+uniform_vector!([u32; 3], u32, None, 3, uniform_uiv, gl::UNSIGNED_INT_VEC3 | gl::BOOL_VEC3);
+uniform_vector!([u32; 4], u32, None, 4, uniform_uiv, gl::UNSIGNED_INT_VEC4 | gl::BOOL_VEC4);
+uniform_vector!(Vector2<u32>, u32, None, 2, uniform_uiv, gl::UNSIGNED_INT_VEC2 | gl::BOOL_VEC2);
+uniform_vector!(Vector3<u32>, u32, None, 3, uniform_uiv, gl::UNSIGNED_INT_VEC3 | gl::BOOL_VEC3);
+uniform_vector!(Vector4<u32>, u32, None, 4, uniform_uiv, gl::UNSIGNED_INT_VEC4 | gl::BOOL_VEC4);
+
+uniform_vector!(f32, f32, None, 1, uniform_fv, gl::FLOAT | gl::BOOL);
+uniform_vector!([f32; 2], f32, None, 2, uniform_fv, gl::FLOAT_VEC2 | gl::BOOL_VEC2);
+uniform_vector!([f32; 3], f32, None, 3, uniform_fv, gl::FLOAT_VEC3 | gl::BOOL_VEC3);
+uniform_vector!([f32; 4], f32, None, 4, uniform_fv, gl::FLOAT_VEC4 | gl::BOOL_VEC4);
+uniform_vector!(Vector2<f32>, f32, None, 2, uniform_fv, gl::FLOAT_VEC2 | gl::BOOL_VEC2);
+uniform_vector!(Vector3<f32>, f32, None, 3, uniform_fv, gl::FLOAT_VEC3 | gl::BOOL_VEC3);
+uniform_vector!(Vector4<f32>, f32, None, 4, uniform_fv, gl::FLOAT_VEC4 | gl::BOOL_VEC4);
+
+// Doubles need GL 4.0 or ARB_gpu_shader_fp64
+uniform_vector!(f64, f64, Some(GlFeature::DoublePrecision), 1, uniform_dv, gl::DOUBLE);
+uniform_vector!([f64; 2], f64, Some(GlFeature::DoublePrecision), 2, uniform_dv, gl::DOUBLE_VEC2);
+uniform_vector!([f64; 3], f64, Some(GlFeature::DoublePrecision), 3, uniform_dv, gl::DOUBLE_VEC3);
+uniform_vector!([f64; 4], f64, Some(GlFeature::DoublePrecision), 4, uniform_dv, gl::DOUBLE_VEC4);
+uniform_vector!(Vector2<f64>, f64, Some(GlFeature::DoublePrecision), 2, uniform_dv, gl::DOUBLE_VEC2);
+uniform_vector!(Vector3<f64>, f64, Some(GlFeature::DoublePrecision), 3, uniform_dv, gl::DOUBLE_VEC3);
+uniform_vector!(Vector4<f64>, f64, Some(GlFeature::DoublePrecision), 4, uniform_dv, gl::DOUBLE_VEC4);
+
+uniform_bool_vector!(bool, 1, gl::BOOL);
+uniform_bool_vector!([bool; 2], 2, gl::BOOL_VEC2);
+uniform_bool_vector!([bool; 3], 3, gl::BOOL_VEC3);
+uniform_bool_vector!([bool; 4], 4, gl::BOOL_VEC4);
+
+uniform_matrix!([[f32; 2]; 2], f32, None, 2, 2, uniform_matrix_fv, gl::FLOAT_MAT2);
+uniform_matrix!([[f32; 3]; 2], f32, None, 2, 3, uniform_matrix_fv, gl::FLOAT_MAT2x3);
+uniform_matrix!([[f32; 4]; 2], f32, None, 2, 4, uniform_matrix_fv, gl::FLOAT_MAT2x4);
+uniform_matrix!([[f32; 2]; 3], f32, None, 3, 2, uniform_matrix_fv, gl::FLOAT_MAT3x2);
+uniform_matrix!([[f32; 3]; 3], f32, None, 3, 3, uniform_matrix_fv, gl::FLOAT_MAT3);
+uniform_matrix!([[f32; 4]; 3], f32, None, 3, 4, uniform_matrix_fv, gl::FLOAT_MAT3x4);
+uniform_matrix!([[f32; 2]; 4], f32, None, 4, 2, uniform_matrix_fv, gl::FLOAT_MAT4x2);
+uniform_matrix!([[f32; 3]; 4], f32, None, 4, 3, uniform_matrix_fv, gl::FLOAT_MAT4x3);
+uniform_matrix!([[f32; 4]; 4], f32, None, 4, 4, uniform_matrix_fv, gl::FLOAT_MAT4);
+uniform_matrix!(Matrix2<f32>, f32, None, 2, 2, uniform_matrix_fv, gl::FLOAT_MAT2);
+uniform_matrix!(Matrix3<f32>, f32, None, 3, 3, uniform_matrix_fv, gl::FLOAT_MAT3);
+uniform_matrix!(Matrix4<f32>, f32, None, 4, 4, uniform_matrix_fv, gl::FLOAT_MAT4);
+
+uniform_matrix!([[f64; 2]; 2], f64, Some(GlFeature::DoublePrecision), 2, 2, uniform_matrix_dv, gl::DOUBLE_MAT2);
+uniform_matrix!([[f64; 3]; 2], f64, Some(GlFeature::DoublePrecision), 2, 3, uniform_matrix_dv, gl::DOUBLE_MAT2x3);
+uniform_matrix!([[f64; 4]; 2], f64, Some(GlFeature::DoublePrecision), 2, 4, uniform_matrix_dv, gl::DOUBLE_MAT2x4);
+uniform_matrix!([[f64; 2]; 3], f64, Some(GlFeature::DoublePrecision), 3, 2, uniform_matrix_dv, gl::DOUBLE_MAT3x2);
+uniform_matrix!([[f64; 3]; 3], f64, Some(GlFeature::DoublePrecision), 3, 3, uniform_matrix_dv, gl::DOUBLE_MAT3);
+uniform_matrix!([[f64; 4]; 3], f64, Some(GlFeature::DoublePrecision), 3, 4, uniform_matrix_dv, gl::DOUBLE_MAT3x4);
+uniform_matrix!([[f64; 2]; 4], f64, Some(GlFeature::DoublePrecision), 4, 2, uniform_matrix_dv, gl::DOUBLE_MAT4x2);
+uniform_matrix!([[f64; 3]; 4], f64, Some(GlFeature::DoublePrecision), 4, 3, uniform_matrix_dv, gl::DOUBLE_MAT4x3);
+uniform_matrix!([[f64; 4]; 4], f64, Some(GlFeature::DoublePrecision), 4, 4, uniform_matrix_dv, gl::DOUBLE_MAT4);
+uniform_matrix!(Matrix2<f64>, f64, Some(GlFeature::DoublePrecision), 2, 2, uniform_matrix_dv, gl::DOUBLE_MAT2);
+uniform_matrix!(Matrix3<f64>, f64, Some(GlFeature::DoublePrecision), 3, 3, uniform_matrix_dv, gl::DOUBLE_MAT3);
+uniform_matrix!(Matrix4<f64>, f64, Some(GlFeature::DoublePrecision), 4, 4, uniform_matrix_dv, gl::DOUBLE_MAT4);
+
+// GLSL name of a uniform type from the reflection, None for types that can't be uniforms
+pub fn get_glsl_type_name(type_: GLenum) -> Option<&'static str> {
+    let name = match type_ {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::DOUBLE => "double",
+        gl::DOUBLE_VEC2 => "dvec2",
+        gl::DOUBLE_VEC3 => "dvec3",
+        gl::DOUBLE_VEC4 => "dvec4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::UNSIGNED_INT_VEC2 => "uvec2",
+        gl::UNSIGNED_INT_VEC3 => "uvec3",
+        gl::UNSIGNED_INT_VEC4 => "uvec4",
+        gl::BOOL => "bool",
+        gl::BOOL_VEC2 => "bvec2",
+        gl::BOOL_VEC3 => "bvec3",
+        gl::BOOL_VEC4 => "bvec4",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::FLOAT_MAT2x3 => "mat2x3",
+        gl::FLOAT_MAT2x4 => "mat2x4",
+        gl::FLOAT_MAT3x2 => "mat3x2",
+        gl::FLOAT_MAT3x4 => "mat3x4",
+        gl::FLOAT_MAT4x2 => "mat4x2",
+        gl::FLOAT_MAT4x3 => "mat4x3",
+        gl::DOUBLE_MAT2 => "dmat2",
+        gl::DOUBLE_MAT3 => "dmat3",
+        gl::DOUBLE_MAT4 => "dmat4",
+        gl::DOUBLE_MAT2x3 => "dmat2x3",
+        gl::DOUBLE_MAT2x4 => "dmat2x4",
+        gl::DOUBLE_MAT3x2 => "dmat3x2",
+        gl::DOUBLE_MAT3x4 => "dmat3x4",
+        gl::DOUBLE_MAT4x2 => "dmat4x2",
+        gl::DOUBLE_MAT4x3 => "dmat4x3",
+        gl::SAMPLER_1D => "sampler1D",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_3D => "sampler3D",
+        gl::SAMPLER_CUBE => "samplerCube",
+        gl::SAMPLER_1D_SHADOW => "sampler1DShadow",
+        gl::SAMPLER_2D_SHADOW => "sampler2DShadow",
+        gl::SAMPLER_1D_ARRAY => "sampler1DArray",
+        gl::SAMPLER_2D_ARRAY => "sampler2DArray",
+        gl::SAMPLER_1D_ARRAY_SHADOW => "sampler1DArrayShadow",
+        gl::SAMPLER_2D_ARRAY_SHADOW => "sampler2DArrayShadow",
+        gl::SAMPLER_2D_MULTISAMPLE => "sampler2DMS",
+        gl::SAMPLER_2D_MULTISAMPLE_ARRAY => "sampler2DMSArray",
+        gl::SAMPLER_CUBE_SHADOW => "samplerCubeShadow",
+        gl::SAMPLER_CUBE_MAP_ARRAY => "samplerCubeArray",
+        gl::SAMPLER_CUBE_MAP_ARRAY_SHADOW => "samplerCubeArrayShadow",
+        gl::SAMPLER_BUFFER => "samplerBuffer",
+        gl::SAMPLER_2D_RECT => "sampler2DRect",
+        gl::SAMPLER_2D_RECT_SHADOW => "sampler2DRectShadow",
+        gl::INT_SAMPLER_1D => "isampler1D",
+        gl::INT_SAMPLER_2D => "isampler2D",
+        gl::INT_SAMPLER_3D => "isampler3D",
+        gl::INT_SAMPLER_CUBE => "isamplerCube",
+        gl::INT_SAMPLER_1D_ARRAY => "isampler1DArray",
+        gl::INT_SAMPLER_2D_ARRAY => "isampler2DArray",
+        gl::INT_SAMPLER_2D_MULTISAMPLE => "isampler2DMS",
+        gl::INT_SAMPLER_2D_MULTISAMPLE_ARRAY => "isampler2DMSArray",
+        gl::INT_SAMPLER_CUBE_MAP_ARRAY => "isamplerCubeArray",
+        gl::INT_SAMPLER_BUFFER => "isamplerBuffer",
+        gl::INT_SAMPLER_2D_RECT => "isampler2DRect",
+        gl::UNSIGNED_INT_SAMPLER_1D => "usampler1D",
+        gl::UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+        gl::UNSIGNED_INT_SAMPLER_3D => "usampler3D",
+        gl::UNSIGNED_INT_SAMPLER_CUBE => "usamplerCube",
+        gl::UNSIGNED_INT_SAMPLER_1D_ARRAY => "usampler1DArray",
+        gl::UNSIGNED_INT_SAMPLER_2D_ARRAY => "usampler2DArray",
+        gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE => "usampler2DMS",
+        gl::UNSIGNED_INT_SAMPLER_2D_MULTISAMPLE_ARRAY => "usampler2DMSArray",
+        gl::UNSIGNED_INT_SAMPLER_CUBE_MAP_ARRAY => "usamplerCubeArray",
+        gl::UNSIGNED_INT_SAMPLER_BUFFER => "usamplerBuffer",
+        gl::UNSIGNED_INT_SAMPLER_2D_RECT => "usampler2DRect",
+        gl::IMAGE_1D => "image1D",
+        gl::IMAGE_2D => "image2D",
+        gl::IMAGE_3D => "image3D",
+        gl::IMAGE_CUBE => "imageCube",
+        gl::IMAGE_1D_ARRAY => "image1DArray",
+        gl::IMAGE_2D_ARRAY => "image2DArray",
+        gl::IMAGE_CUBE_MAP_ARRAY => "imageCubeArray",
+        gl::IMAGE_BUFFER => "imageBuffer",
+        gl::IMAGE_2D_RECT => "image2DRect",
+        gl::IMAGE_2D_MULTISAMPLE => "image2DMS",
+        gl::IMAGE_2D_MULTISAMPLE_ARRAY => "image2DMSArray",
+        gl::INT_IMAGE_1D => "iimage1D",
+        gl::INT_IMAGE_2D => "iimage2D",
+        gl::INT_IMAGE_3D => "iimage3D",
+        gl::INT_IMAGE_CUBE => "iimageCube",
+        gl::INT_IMAGE_1D_ARRAY => "iimage1DArray",
+        gl::INT_IMAGE_2D_ARRAY => "iimage2DArray",
+        gl::INT_IMAGE_CUBE_MAP_ARRAY => "iimageCubeArray",
+        gl::INT_IMAGE_BUFFER => "iimageBuffer",
+        gl::INT_IMAGE_2D_RECT => "iimage2DRect",
+        gl::INT_IMAGE_2D_MULTISAMPLE => "iimage2DMS",
+        gl::INT_IMAGE_2D_MULTISAMPLE_ARRAY => "iimage2DMSArray",
+        gl::UNSIGNED_INT_IMAGE_1D => "uimage1D",
+        gl::UNSIGNED_INT_IMAGE_2D => "uimage2D",
+        gl::UNSIGNED_INT_IMAGE_3D => "uimage3D",
+        gl::UNSIGNED_INT_IMAGE_CUBE => "uimageCube",
+        gl::UNSIGNED_INT_IMAGE_1D_ARRAY => "uimage1DArray",
+        gl::UNSIGNED_INT_IMAGE_2D_ARRAY => "uimage2DArray",
+        gl::UNSIGNED_INT_IMAGE_CUBE_MAP_ARRAY => "uimageCubeArray",
+        gl::UNSIGNED_INT_IMAGE_BUFFER => "uimageBuffer",
+        gl::UNSIGNED_INT_IMAGE_2D_RECT => "uimage2DRect",
+        gl::UNSIGNED_INT_IMAGE_2D_MULTISAMPLE => "uimage2DMS",
+        gl::UNSIGNED_INT_IMAGE_2D_MULTISAMPLE_ARRAY => "uimage2DMSArray",
+        gl::UNSIGNED_INT_ATOMIC_COUNTER => "atomic_uint",
+        _ => return None
+    };
+
+    Some(name)
+}
@@ -1,22 +1,25 @@
-use super::{Buffer, gl};
+use super::{Buffer, GlContext, GlError, GlFeature, gl};
 
 pub struct VertexArray {
     id: u32,
     attrib_index: u32,
-    buffer_index: u32
+    buffer_index: u32,
+    // Drawn as GL_PATCHES with this many vertices each when set, GL_TRIANGLES otherwise
+    patch_vertices: Option<i32>,
+    label: Option<String>,
+    gl: GlContext
 }
 
 impl VertexArray {
-    pub fn new() -> VertexArray {
-        let mut vert_array = VertexArray {
-            id: 0, attrib_index: 0, buffer_index: 0
-        };
-
-        unsafe {
-            gl::CreateVertexArrays(1, &mut vert_array.id);
+    pub fn new(gl: &GlContext) -> VertexArray {
+        VertexArray {
+            id: gl.create_vertex_array(),
+            attrib_index: 0,
+            buffer_index: 0,
+            patch_vertices: None,
+            label: None,
+            gl: gl.clone()
         }
-
-        vert_array
     }
 
     pub fn add_vertex_buffer<T>(&mut self, buffer: &mut Buffer<T>) {
@@ -28,23 +31,21 @@ impl VertexArray {
     }
 
     pub fn set_element_buffer<T>(&mut self, buffer: &mut Buffer<T>) {
-        unsafe {
-            gl::VertexArrayElementBuffer(self.id, buffer.get_id());
-        }
+        self.gl.vertex_array_element_buffer(self.id, buffer.get_id());
     }
 
     pub fn add_attrib<T>(&mut self, buffer: &mut Buffer<T>, size: i32, offset: u32, type_: gl::types::GLenum) {
-        unsafe {
-            gl::EnableVertexArrayAttrib(self.id, self.attrib_index);
-            gl::VertexArrayAttribFormat(
-                self.id,
-                self.attrib_index,
-                size,
-                type_,
-                gl::FALSE,
-                offset
-            );
+        self.gl.enable_vertex_array_attrib(self.id, self.attrib_index);
+        self.gl.vertex_array_attrib_format(
+            self.id,
+            self.attrib_index,
+            size,
+            type_,
+            false,
+            offset
+        );
 
+        unsafe {
             buffer.bind_to_vao_attrib(self.id, self.attrib_index);
         }
 
@@ -66,29 +67,60 @@ impl VertexArray {
     }
 
     pub unsafe fn bind(&self) {
-        gl::BindVertexArray(self.id);
+        self.gl.bind_vertex_array(self.id);
+    }
+
+    pub unsafe fn unbind(&self) {
+        self.gl.bind_vertex_array(0);
+    }
+
+    // For tessellation, the program needs tessellation stages to draw patches.
+    // Models use triangle lists, so they need 3
+    pub fn set_patch_vertices(&mut self, patch_vertices: Option<i32>) -> Result<(), GlError> {
+        if patch_vertices.is_some() {
+            self.gl.require(GlFeature::Tessellation)?;
+        }
+
+        self.patch_vertices = patch_vertices;
+
+        Ok(())
+    }
+
+    pub fn get_patch_vertices(&self) -> Option<i32> {
+        self.patch_vertices
+    }
+
+    // Returns the draw mode, for patches the patch size (global state) is set first
+    fn apply_mode(&self) -> gl::types::GLenum {
+        match self.patch_vertices {
+            Some(patch_vertices) => {
+                self.gl.patch_parameter_i(gl::PATCH_VERTICES, patch_vertices);
+                gl::PATCHES
+            },
+            None => gl::TRIANGLES
+        }
     }
 
     // Get count and instance_count from in-built buffer objects
     pub fn draw_elements(&self, count: i32, instance_count: i32) {
         unsafe {
-            gl::BindVertexArray(self.id);
-            gl::DrawElementsInstanced(
-                gl::TRIANGLES,
+            self.gl.bind_vertex_array(self.id);
+            self.gl.draw_elements_instanced(
+                self.apply_mode(),
                 count,
                 gl::UNSIGNED_INT,
                 std::ptr::null(),
                 instance_count
             );
-            gl::BindVertexArray(0);
+            self.gl.bind_vertex_array(0);
         }
     }
 
     // Requires VAO to be bound already
     pub fn draw_elements_offset(&self, count: i32, offset: usize, instance_count: i32) {
         unsafe {
-            gl::DrawElementsInstanced(
-                gl::TRIANGLES,
+            self.gl.draw_elements_instanced(
+                self.apply_mode(),
                 count,
                 gl::UNSIGNED_INT,
                 std::ptr::null::<u32>().add(offset) as *const gl::types::GLvoid,
@@ -99,29 +131,40 @@ impl VertexArray {
 
     // Need to generate and bind commands array beforehand
     // GL_DRAW_INDIRECT_BUFFER must be bound
-    pub fn draw_elements_multi_indirect(&self, command_count: i32) {
+    pub fn draw_elements_multi_indirect(&self, command_count: i32) -> Result<(), GlError> {
+        self.gl.require(GlFeature::MultiDrawIndirect)?;
+
         unsafe {
-            gl::MultiDrawElementsIndirect(
-                gl::TRIANGLES,
+            self.gl.multi_draw_elements_indirect(
+                self.apply_mode(),
                 gl::UNSIGNED_INT,
                 std::ptr::null(),
                 command_count,
                 0 // Draw commands are tightly packed
             )
         }
+
+        Ok(())
     }
 
     pub fn get_id(&self) -> u32 {
         self.id
     }
+
+    pub fn set_label(&mut self, label: &str) {
+        self.gl.label_object(gl::VERTEX_ARRAY, self.id, label);
+        self.label = Some(label.to_owned());
+    }
+
+    pub fn get_label(&self) -> Option<&str> {
+        self.label.as_deref()
+    }
 }
 
 impl Drop for VertexArray {
     fn drop(&mut self) {
-        unsafe {
-            gl::BindVertexArray(0);
-            gl::DeleteVertexArrays(1, &self.id);
-        }
+        self.gl.bind_vertex_array(0);
+        self.gl.delete_vertex_array(self.id);
     }
 }
 
@@ -134,4 +177,66 @@ pub struct DrawCommand {
     first_index: u32,
     base_vertex: i32,
     base_instance: u32
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Matrix4;
+    use crate::{Buffer, GlCall, gl, mock_backend::testing::{get_args, mock_context}};
+    use super::VertexArray;
+
+    #[test]
+    fn vertex_array_sets_up_attribs() {
+        let (mock, gl) = mock_context(|_| ());
+
+        let mut vertices = Buffer::<[f32; 3]>::new(&gl);
+        let mut instances = Buffer::<Matrix4<f32>>::new(&gl);
+        let mut indices = Buffer::<u32>::new(&gl);
+        let mut vao = VertexArray::new(&gl);
+        let id = vao.get_id() as i64;
+        mock.clear_calls();
+
+        vao.add_vertex_buffer(&mut vertices);
+        vao.add_attrib(&mut vertices, 3, 0, gl::FLOAT);
+        vao.add_vertex_buffer(&mut instances);
+        vao.add_attrib_divisor(&mut instances, 4);
+        vao.set_element_buffer(&mut indices);
+
+        let calls = mock.take_calls();
+        assert_eq!(
+            get_args(&calls, "VertexArrayVertexBuffer"),
+            [[id, 0, vertices.get_id() as i64, 0, 12], [id, 1, instances.get_id() as i64, 0, 64]]
+        );
+        assert_eq!(get_args(&calls, "EnableVertexArrayAttrib"), (0..5).map(|i| vec![id, i]).collect::<Vec<_>>());
+        // The matrix takes one vec4 attribute per row
+        assert_eq!(
+            get_args(&calls, "VertexArrayAttribFormat"),
+            [
+                [id, 0, 3, gl::FLOAT as i64, 0, 0],
+                [id, 1, 4, gl::FLOAT as i64, 0, 0],
+                [id, 2, 4, gl::FLOAT as i64, 0, 16],
+                [id, 3, 4, gl::FLOAT as i64, 0, 32],
+                [id, 4, 4, gl::FLOAT as i64, 0, 48]
+            ]
+        );
+        assert_eq!(
+            get_args(&calls, "VertexArrayAttribBinding"),
+            [[id, 0, 0], [id, 1, 1], [id, 2, 1], [id, 3, 1], [id, 4, 1]]
+        );
+        assert_eq!(get_args(&calls, "VertexArrayBindingDivisor"), [[id, 1, 1]]);
+        assert_eq!(get_args(&calls, "VertexArrayElementBuffer"), [[id, indices.get_id() as i64]]);
+
+        vao.draw_elements(6, 2);
+        assert_eq!(
+            mock.take_calls(),
+            vec![
+                GlCall { name: "BindVertexArray", args: vec![id], text: None },
+                GlCall { name: "DrawElementsInstanced", args: vec![gl::TRIANGLES as i64, 6, gl::UNSIGNED_INT as i64, 0, 2], text: None },
+                GlCall { name: "BindVertexArray", args: vec![0], text: None }
+            ]
+        );
+
+        drop(vao);
+        assert_eq!(mock.find("DeleteVertexArrays").unwrap().args, [id]);
+    }
+}
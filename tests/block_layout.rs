@@ -0,0 +1,117 @@
+use cgmath::{Matrix3, Vector3};
+use silver_gl::{AsStd140, AsStd430, BlockLayoutInfo};
+
+#[derive(AsStd140, AsStd430)]
+struct Light {
+    position: Vector3<f32>,
+    intensity: f32
+}
+
+#[derive(AsStd140, AsStd430)]
+struct Scene {
+    time: f32,
+    weights: [f32; 3],
+    lights: [Light; 2],
+    normal: Matrix3<f32>,
+    ambient: Vector3<f32>,
+    exposure: f32
+}
+
+#[test]
+fn vec3_followed_by_float() {
+    // The float fills the fourth component of the vec3 in both layouts
+    assert_eq!(Light::STD140.position.get_offset(), 0);
+    assert_eq!(Light::STD140.intensity.get_offset(), 12);
+    assert_eq!(std::mem::size_of::<LightStd140>(), 16);
+
+    assert_eq!(Light::STD430.position.get_offset(), 0);
+    assert_eq!(Light::STD430.intensity.get_offset(), 12);
+    assert_eq!(std::mem::size_of::<LightStd430>(), 16);
+}
+
+#[test]
+fn std140_layout() {
+    // Arrays have a stride of 16, and arrays, structs and mat3 columns are aligned to 16
+    let offsets = [
+        Scene::STD140.time.get_offset(),
+        Scene::STD140.weights.get_offset(),
+        Scene::STD140.lights.get_offset(),
+        Scene::STD140.normal.get_offset(),
+        Scene::STD140.ambient.get_offset(),
+        Scene::STD140.exposure.get_offset()
+    ];
+    assert_eq!(offsets, [0, 16, 64, 96, 144, 156]);
+    assert_eq!(std::mem::size_of::<SceneStd140>(), 160);
+
+    // The members GL reflects, for ShaderProgram::check_block_layout()
+    let layout = BlockLayoutInfo::std140::<Scene>();
+    let members: Vec<(&str, usize)> = layout.members.iter().map(|member| (member.name.as_str(), member.offset)).collect();
+    assert_eq!(layout.size, 160);
+    assert_eq!(members, [
+        ("time", 0),
+        ("weights[0]", 16),
+        ("weights[1]", 32),
+        ("weights[2]", 48),
+        ("lights[0].position", 64),
+        ("lights[0].intensity", 76),
+        ("lights[1].position", 80),
+        ("lights[1].intensity", 92),
+        ("normal", 96),
+        ("ambient", 144),
+        ("exposure", 156)
+    ]);
+}
+
+#[test]
+fn std430_layout() {
+    // Scalar arrays are packed, structs and mat3 columns are still aligned like their vec3s
+    let offsets = [
+        Scene::STD430.time.get_offset(),
+        Scene::STD430.weights.get_offset(),
+        Scene::STD430.lights.get_offset(),
+        Scene::STD430.normal.get_offset(),
+        Scene::STD430.ambient.get_offset(),
+        Scene::STD430.exposure.get_offset()
+    ];
+    assert_eq!(offsets, [0, 4, 16, 48, 96, 108]);
+    assert_eq!(std::mem::size_of::<SceneStd430>(), 112);
+}
+
+#[test]
+fn converted_values_land_at_their_offsets() {
+    let scene = Scene {
+        time: 1.0,
+        weights: [2.0, 3.0, 4.0],
+        lights: [
+            Light { position: Vector3::new(5.0, 6.0, 7.0), intensity: 8.0 },
+            Light { position: Vector3::new(9.0, 10.0, 11.0), intensity: 12.0 }
+        ],
+        normal: Matrix3::new(13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 19.0, 20.0, 21.0),
+        ambient: Vector3::new(22.0, 23.0, 24.0),
+        exposure: 25.0
+    };
+
+    let read = |bytes: &[u8], offset: usize| f32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+    let std140 = scene.as_std140();
+    let bytes = unsafe { std::slice::from_raw_parts(&std140 as *const SceneStd140 as *const u8, 160) };
+    let values: Vec<f32> = [0, 16, 32, 48, 64, 76, 80, 92, 96, 112, 128, 136, 144, 156].iter()
+        .map(|offset| read(bytes, *offset))
+        .collect();
+    assert_eq!(values, [1.0, 2.0, 3.0, 4.0, 5.0, 8.0, 9.0, 12.0, 13.0, 16.0, 19.0, 21.0, 22.0, 25.0]);
+
+    let std430 = scene.as_std430();
+    let bytes = unsafe { std::slice::from_raw_parts(&std430 as *const SceneStd430 as *const u8, 112) };
+    let values: Vec<f32> = [0, 4, 8, 12, 16, 28, 32, 44, 48, 64, 80, 88, 96, 108].iter()
+        .map(|offset| read(bytes, *offset))
+        .collect();
+    assert_eq!(values, [1.0, 2.0, 3.0, 4.0, 5.0, 8.0, 9.0, 12.0, 13.0, 16.0, 19.0, 21.0, 22.0, 25.0]);
+}
+
+#[test]
+fn layout_asserts_fail_the_build() {
+    let tests = trybuild::TestCases::new();
+    tests.pass("tests/ui/layout_asserts_pass.rs");
+    tests.compile_fail("tests/ui/wrong_offset.rs");
+    tests.compile_fail("tests/ui/wrong_size.rs");
+}
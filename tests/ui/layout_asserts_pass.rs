@@ -0,0 +1,18 @@
+use cgmath::Vector3;
+use silver_gl::{AsStd140, AsStd430};
+
+#[derive(AsStd140, AsStd430)]
+#[std140(size = 32)]
+#[std430(size = 32)]
+struct Material {
+    #[std140(offset = 0)]
+    albedo: Vector3<f32>,
+    #[std140(offset = 12)]
+    #[std430(offset = 12)]
+    roughness: f32,
+    #[std140(offset = 16)]
+    #[std430(offset = 16)]
+    emissive: Vector3<f32>
+}
+
+fn main() {}
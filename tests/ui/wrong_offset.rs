@@ -0,0 +1,12 @@
+use cgmath::Vector3;
+use silver_gl::AsStd140;
+
+#[derive(AsStd140)]
+struct Material {
+    roughness: f32,
+    // Aligned to 16, not right after the float
+    #[std140(offset = 4)]
+    albedo: Vector3<f32>
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use silver_gl::AsStd430;
+
+#[derive(AsStd430)]
+#[std430(size = 64)]
+struct Weights {
+    weights: [f32; 3]
+}
+
+fn main() {}